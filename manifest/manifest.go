@@ -164,6 +164,8 @@ func validateStoreBuilder(module *Module) error {
 		"set:bytes",
 		"set:string",
 		"set:proto",
+		"set:set_sum_int64",
+		"set:set_sum_bigint",
 		"set_if_not_exists:bytes",
 		"set_if_not_exists:string",
 		"set_if_not_exists:proto",
@@ -467,4 +467,7 @@ var validValueTypes = map[string]bool{
 	"bytes":    true,
 	"string":   true,
 	"proto":    true,
+
+	"set_sum_int64":  true,
+	"set_sum_bigint": true,
 }
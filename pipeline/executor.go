@@ -200,7 +200,8 @@ func (e *BaseExecutor) wasmCall(ctx context.Context, vals map[string][]byte, clo
 	for _, input := range e.wasmInputs {
 		switch input.Type {
 		case wasm.InputSource:
-			val := vals[input.Name]
+			val, found := vals[input.Name]
+			input.Absent = !found
 			if len(val) != 0 {
 				input.StreamData = val
 				hasInput = true
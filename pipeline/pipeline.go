@@ -640,6 +640,7 @@ func (p *Pipeline) buildWASM(ctx context.Context, request *pbsubstreams.Request,
 		if err != nil {
 			return fmt.Errorf("new wasm module: %w", err)
 		}
+		wasmModule.SetInitialBlock(module.InitialBlock)
 
 		switch kind := module.Kind.(type) {
 		case *pbsubstreams.Module_KindMap_:
@@ -1,5 +1,4 @@
-// use proc_macro2::{Span};
-// type AttributeArgs = syn::punctuated::Punctuated<syn::NestedMeta, syn::Token![,]>;
+use proc_macro2::Span;
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum ModuleType {
@@ -7,103 +6,196 @@ pub enum ModuleType {
     Map,
 }
 
-impl ModuleType {
-    // fn from_str(s: &str) -> Result<ModuleType, String> {
-    //     match s {
-    //         "store" => Ok(ModuleType::Store),
-    //         "map" => Ok(ModuleType::Map),
-    //         _ => Err(format!("No such module type `{}`. The modules types are `store` and `map`.", s)),
-    //     }
-    // }
+pub struct FinalConfiguration {
+    pub module_type: ModuleType,
+    pub panic_hook: bool,
+    pub timed: bool,
+    pub policy: Option<String>,
+    pub value_type: Option<String>,
 }
 
-pub struct FinalConfiguration {
-    pub module_type: ModuleType
+struct Configuration {
+    module_type: ModuleType,
+    panic_hook: Option<bool>,
+    timed: bool,
+    policy: Option<String>,
+    value_type: Option<String>,
 }
 
-// struct Configuration {
-//     module_type: Option<ModuleType>
-// }
-//
-// impl Configuration {
-//     fn new() -> Self {
-//         Configuration {
-//             module_type: None,
-//         }
-//     }
-//
-//     fn set_module_type(&mut self, runtime: syn::Lit, span: Span) -> Result<(), syn::Error> {
-//         if self.module_type.is_some() {
-//             return Err(syn::Error::new(span, "`type` set multiple times."));
-//         }
-//
-//         let runtime_str = parse_string(runtime, span, "type")?;
-//         let mod_type =
-//             ModuleType::from_str(&runtime_str).map_err(|err| syn::Error::new(span, err))?;
-//         self.module_type = Some(mod_type);
-//         Ok(())
-//     }
-//
-//     fn build(&self) -> Result<FinalConfiguration, syn::Error> {
-//         let mod_type = self.module_type.unwrap_or(ModuleType::Map);
-//         Ok(FinalConfiguration {
-//             module_type: mod_type,
-//         })
-//     }
-// }
-
-// fn parse_string(int: syn::Lit, span: Span, field: &str) -> Result<String, syn::Error> {
-//     match int {
-//         syn::Lit::Str(s) => Ok(s.value()),
-//         syn::Lit::Verbatim(s) => Ok(s.to_string()),
-//         _ => Err(syn::Error::new(
-//             span,
-//             format!("Failed to parse value of `{}` as string.", field),
-//         )),
-//     }
-// }
-
-
-// fn build_config(
-//     args: AttributeArgs,
-// ) -> Result<FinalConfiguration, syn::Error> {
-//
-//     let mut config = Configuration::new();
-//
-//     for arg in args {
-//         match arg {
-//             syn::NestedMeta::Meta(syn::Meta::NameValue(namevalue)) => {
-//                 let ident = namevalue
-//                     .path
-//                     .get_ident()
-//                     .ok_or_else(|| {
-//                         syn::Error::new_spanned(&namevalue, "Must have specified ident")
-//                     })?
-//                     .to_string()
-//                     .to_lowercase();
-//                 match ident.as_str() {
-//                     "type" => {
-//                         config.set_module_type(
-//                             namevalue.lit.clone(),
-//                             syn::spanned::Spanned::span(&namevalue.lit),
-//                         )?;
-//                     }
-//                     name => {
-//                         let msg = format!(
-//                             "Unknown attribute {} is specified; expected one of: `type`",
-//                             name,
-//                         );
-//                         return Err(syn::Error::new_spanned(namevalue, msg));
-//                     }
-//                 }
-//             }
-//             other => {
-//                 return Err(syn::Error::new_spanned(
-//                     other,
-//                     "Unknown attribute inside the macro",
-//                 ));
-//             }
-//         }
-//     }
-//     config.build()
-// }
\ No newline at end of file
+const POLICIES: [&'static str; 6] = ["set", "set_if_not_exists", "add", "min", "max", "append"];
+
+impl Configuration {
+    fn new(module_type: ModuleType) -> Self {
+        Configuration {
+            module_type,
+            panic_hook: None,
+            timed: false,
+            policy: None,
+            value_type: None,
+        }
+    }
+
+    fn set_panic_hook(&mut self, value: syn::Lit, span: Span) -> Result<(), syn::Error> {
+        if self.panic_hook.is_some() {
+            return Err(syn::Error::new(span, "`panic_hook` set multiple times."));
+        }
+
+        self.panic_hook = Some(parse_bool(value, span, "panic_hook")?);
+        Ok(())
+    }
+
+    fn set_timed(&mut self, span: Span) -> Result<(), syn::Error> {
+        if self.timed {
+            return Err(syn::Error::new(span, "`timed` set multiple times."));
+        }
+
+        self.timed = true;
+        Ok(())
+    }
+
+    fn set_policy(&mut self, value: syn::Lit, span: Span) -> Result<(), syn::Error> {
+        self.ensure_store("policy", span)?;
+        if self.policy.is_some() {
+            return Err(syn::Error::new(span, "`policy` set multiple times."));
+        }
+
+        let policy = parse_string(value, span, "policy")?;
+        if !POLICIES.contains(&policy.as_str()) {
+            return Err(syn::Error::new(
+                span,
+                format!(
+                    "Unknown policy `{}`; expected one of: `{}`",
+                    policy,
+                    POLICIES.join("`, `")
+                ),
+            ));
+        }
+        self.policy = Some(policy);
+        Ok(())
+    }
+
+    fn set_value_type(&mut self, value: syn::Lit, span: Span) -> Result<(), syn::Error> {
+        self.ensure_store("value_type", span)?;
+        if self.value_type.is_some() {
+            return Err(syn::Error::new(span, "`value_type` set multiple times."));
+        }
+
+        self.value_type = Some(parse_string(value, span, "value_type")?);
+        Ok(())
+    }
+
+    fn ensure_store(&self, field: &str, span: Span) -> Result<(), syn::Error> {
+        if self.module_type != ModuleType::Store {
+            return Err(syn::Error::new(
+                span,
+                format!("`{}` is only supported on store handlers.", field),
+            ));
+        }
+        Ok(())
+    }
+
+    fn build(&self) -> Result<FinalConfiguration, syn::Error> {
+        Ok(FinalConfiguration {
+            module_type: self.module_type,
+            panic_hook: self.panic_hook.unwrap_or(true),
+            timed: self.timed,
+            policy: self.policy.clone(),
+            value_type: self.value_type.clone(),
+        })
+    }
+}
+
+fn parse_bool(value: syn::Lit, span: Span, field: &str) -> Result<bool, syn::Error> {
+    match value {
+        syn::Lit::Bool(b) => Ok(b.value),
+        _ => Err(syn::Error::new(
+            span,
+            format!("Failed to parse value of `{}` as bool.", field),
+        )),
+    }
+}
+
+fn parse_string(value: syn::Lit, span: Span, field: &str) -> Result<String, syn::Error> {
+    match value {
+        syn::Lit::Str(s) => Ok(s.value()),
+        _ => Err(syn::Error::new(
+            span,
+            format!("Failed to parse value of `{}` as string.", field),
+        )),
+    }
+}
+
+pub fn build_config(
+    args: syn::AttributeArgs,
+    module_type: ModuleType,
+) -> Result<FinalConfiguration, syn::Error> {
+    let mut config = Configuration::new(module_type);
+
+    for arg in args {
+        match arg {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(namevalue)) => {
+                let ident = namevalue
+                    .path
+                    .get_ident()
+                    .ok_or_else(|| {
+                        syn::Error::new_spanned(&namevalue, "Must have specified ident")
+                    })?
+                    .to_string()
+                    .to_lowercase();
+                match ident.as_str() {
+                    "panic_hook" => {
+                        config.set_panic_hook(
+                            namevalue.lit.clone(),
+                            syn::spanned::Spanned::span(&namevalue.lit),
+                        )?;
+                    }
+                    "policy" => {
+                        config.set_policy(
+                            namevalue.lit.clone(),
+                            syn::spanned::Spanned::span(&namevalue.lit),
+                        )?;
+                    }
+                    "value_type" => {
+                        config.set_value_type(
+                            namevalue.lit.clone(),
+                            syn::spanned::Spanned::span(&namevalue.lit),
+                        )?;
+                    }
+                    name => {
+                        let msg = format!(
+                            "Unknown attribute {} is specified; expected one of: `panic_hook`, `policy`, `value_type`, `timed`",
+                            name,
+                        );
+                        return Err(syn::Error::new_spanned(namevalue, msg));
+                    }
+                }
+            }
+            syn::NestedMeta::Meta(syn::Meta::Path(path)) => {
+                let ident = path
+                    .get_ident()
+                    .ok_or_else(|| syn::Error::new_spanned(&path, "Must have specified ident"))?
+                    .to_string()
+                    .to_lowercase();
+                match ident.as_str() {
+                    "timed" => {
+                        config.set_timed(syn::spanned::Spanned::span(&path))?;
+                    }
+                    name => {
+                        let msg = format!(
+                            "Unknown attribute {} is specified; expected one of: `timed`",
+                            name,
+                        );
+                        return Err(syn::Error::new_spanned(path, msg));
+                    }
+                }
+            }
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "Unknown attribute inside the macro",
+                ));
+            }
+        }
+    }
+    config.build()
+}
@@ -0,0 +1,172 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+
+struct EntitiesConfiguration {
+    entities: syn::Ident,
+    key: syn::Path,
+    ordinal: Option<syn::Ident>,
+}
+
+pub(crate) fn main(args: TokenStream, item: TokenStream) -> TokenStream {
+    let original = item.clone();
+
+    let args = syn::parse_macro_input!(args as syn::AttributeArgs);
+    let config = match parse_config(args) {
+        Ok(config) => config,
+        Err(e) => return token_stream_with_error(original, e),
+    };
+    let input = syn::parse_macro_input!(item as syn::ItemFn);
+
+    if input.sig.output == syn::ReturnType::Default {
+        return token_stream_with_error(
+            original,
+            syn::Error::new(
+                Span::call_site(),
+                "entities extraction should have a return of type Result<YOUR_TYPE, SubstreamError>",
+            ),
+        );
+    }
+
+    let mut arg_names: Vec<syn::Ident> = Vec::with_capacity(input.sig.inputs.len());
+    for i in input.sig.inputs.iter() {
+        match i {
+            syn::FnArg::Typed(pat_type) => match &*pat_type.pat {
+                syn::Pat::Ident(v) => arg_names.push(v.ident.clone()),
+                _ => {
+                    return token_stream_with_error(
+                        original,
+                        syn::Error::new(pat_type.span(), "unknown argument type"),
+                    )
+                }
+            },
+            syn::FnArg::Receiver(_) => {
+                return token_stream_with_error(
+                    original,
+                    syn::Error::new(
+                        i.span(),
+                        "entities extraction does not support 'self' receiver",
+                    ),
+                )
+            }
+        }
+    }
+
+    let name = &input.sig.ident;
+    let map_name = format_ident!("map_{}", name);
+    let store_name = format_ident!("store_{}", name);
+    let inputs: Vec<&syn::FnArg> = input.sig.inputs.iter().collect();
+    let output = &input.sig.output;
+    let entities = &config.entities;
+    let key = &config.key;
+    let ordinal = match &config.ordinal {
+        Some(field) => quote! { entity.#field as u64 },
+        None => quote! { 0 },
+    };
+
+    let result = quote! {
+        #input
+
+        #[substreams::handlers::map]
+        fn #map_name(#(#inputs),*) #output {
+            #name(#(#arg_names),*)
+        }
+
+        #[substreams::handlers::store]
+        fn #store_name(#(#inputs,)* entities_store: substreams::store::StoreSet) {
+            let output = match #name(#(#arg_names),*) {
+                Ok(output) => output,
+                Err(e) => panic!("{}", e),
+            };
+
+            for entity in output.#entities.iter() {
+                entities_store.set(#ordinal, #key(entity), &substreams::proto::encode(entity).unwrap());
+            }
+        }
+    };
+    result.into()
+}
+
+fn parse_config(args: syn::AttributeArgs) -> Result<EntitiesConfiguration, syn::Error> {
+    let mut entities = None;
+    let mut key = None;
+    let mut ordinal = None;
+
+    for arg in args {
+        match arg {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(namevalue)) => {
+                let ident = namevalue
+                    .path
+                    .get_ident()
+                    .ok_or_else(|| {
+                        syn::Error::new_spanned(&namevalue, "Must have specified ident")
+                    })?
+                    .to_string();
+                let value = match &namevalue.lit {
+                    syn::Lit::Str(s) => s.clone(),
+                    other => {
+                        return Err(syn::Error::new_spanned(
+                            other,
+                            format!("Failed to parse value of `{}` as string.", ident),
+                        ))
+                    }
+                };
+                let target = match ident.as_str() {
+                    "entities" => &mut entities,
+                    "key" => &mut key,
+                    "ordinal" => &mut ordinal,
+                    name => {
+                        return Err(syn::Error::new_spanned(
+                            &namevalue,
+                            format!(
+                                "Unknown attribute {} is specified; expected one of: `entities`, `key`, `ordinal`",
+                                name
+                            ),
+                        ))
+                    }
+                };
+                if target.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        &namevalue,
+                        format!("`{}` set multiple times.", ident),
+                    ));
+                }
+                *target = Some(value);
+            }
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "Unknown attribute inside the macro",
+                ))
+            }
+        }
+    }
+
+    let entities = entities.ok_or_else(|| {
+        syn::Error::new(
+            Span::call_site(),
+            "entities requires `entities`, the name of the repeated field holding the entities",
+        )
+    })?;
+    let key = key.ok_or_else(|| {
+        syn::Error::new(
+            Span::call_site(),
+            "entities requires `key`, the path of a `fn(&Entity) -> String` returning the key of an entity",
+        )
+    })?;
+
+    Ok(EntitiesConfiguration {
+        entities: entities.parse()?,
+        key: key.parse()?,
+        ordinal: match ordinal {
+            Some(ordinal) => Some(ordinal.parse()?),
+            None => None,
+        },
+    })
+}
+
+fn token_stream_with_error(mut tokens: TokenStream, error: syn::Error) -> TokenStream {
+    tokens.extend(TokenStream::from(error.into_compile_error()));
+    tokens
+}
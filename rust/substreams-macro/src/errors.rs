@@ -2,6 +2,8 @@ use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum SubstreamMacroError {
-    #[error("unknown input type")]
+    #[error("unsupported input type `{0}`, handler inputs must be a decodable proto message, a store like `store::StoreGet`, `store::Deltas` or `&[u8]` raw bytes")]
     UnknownInputType(String),
+    #[error("unsupported input type `{0}`, stores are only recognized by the name of their type, declare the store by value like `s: store::StoreAddInt64`")]
+    UnresolvedStoreType(String),
 }
\ No newline at end of file
@@ -0,0 +1,164 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use tiny_keccak::{Hasher, Keccak};
+
+pub(crate) fn main(args: TokenStream, item: TokenStream) -> TokenStream {
+    let original = item.clone();
+
+    let args = syn::parse_macro_input!(args as syn::AttributeArgs);
+    let signature = match parse_signature(args) {
+        Ok(signature) => signature,
+        Err(e) => return token_stream_with_error(original, e),
+    };
+    let input = syn::parse_macro_input!(item as syn::ItemFn);
+
+    if input.sig.inputs.len() != 1 {
+        return token_stream_with_error(
+            original,
+            syn::Error::new(
+                input.sig.inputs.span(),
+                "event handler must take a single log argument",
+            ),
+        );
+    }
+    let log_name = match input.sig.inputs.first() {
+        Some(syn::FnArg::Typed(pat_type)) => match &*pat_type.pat {
+            syn::Pat::Ident(v) => v.ident.clone(),
+            _ => {
+                return token_stream_with_error(
+                    original,
+                    syn::Error::new(pat_type.span(), "unknown argument type"),
+                )
+            }
+        },
+        _ => {
+            return token_stream_with_error(
+                original,
+                syn::Error::new(
+                    input.sig.inputs.span(),
+                    "event handler does not support 'self' receiver",
+                ),
+            )
+        }
+    };
+
+    if !returns_option(&input.sig.output) {
+        return token_stream_with_error(
+            original,
+            syn::Error::new(
+                Span::call_site(),
+                "event handler should have a return of type Option<YOUR_TYPE>",
+            ),
+        );
+    }
+
+    let topic = keccak256(signature.value().as_bytes());
+    let topic_name = format_ident!("{}_TOPIC", input.sig.ident.to_string().to_uppercase());
+    let attrs = &input.attrs;
+    let vis = &input.vis;
+    let sig = &input.sig;
+    let body = &input.block;
+    let doc = format!("Topic 0 of the `{}` event", signature.value());
+
+    let result = quote! {
+        #[doc = #doc]
+        #vis const #topic_name: [u8; 32] = [#(#topic),*];
+
+        #(#attrs)*
+        #vis #sig {
+            if #log_name.topics.get(0).map(|topic| topic.as_slice()) != Some(&#topic_name[..]) {
+                return None;
+            }
+            #body
+        }
+    };
+    result.into()
+}
+
+fn parse_signature(args: syn::AttributeArgs) -> Result<syn::LitStr, syn::Error> {
+    let mut signature = None;
+
+    for arg in args {
+        match arg {
+            syn::NestedMeta::Meta(syn::Meta::NameValue(namevalue))
+                if namevalue.path.is_ident("signature") =>
+            {
+                if signature.is_some() {
+                    return Err(syn::Error::new_spanned(
+                        namevalue,
+                        "`signature` set multiple times.",
+                    ));
+                }
+                match namevalue.lit {
+                    syn::Lit::Str(s) => signature = Some(s),
+                    other => {
+                        return Err(syn::Error::new_spanned(
+                            other,
+                            "Failed to parse value of `signature` as string.",
+                        ))
+                    }
+                }
+            }
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "Unknown attribute inside the macro; expected one of: `signature`",
+                ))
+            }
+        }
+    }
+
+    signature.ok_or_else(|| {
+        syn::Error::new(
+            Span::call_site(),
+            "event handler requires a `signature`, like `signature = \"Transfer(address,address,uint256)\"`",
+        )
+    })
+}
+
+fn returns_option(output: &syn::ReturnType) -> bool {
+    match output {
+        syn::ReturnType::Type(_, ty) => match &**ty {
+            syn::Type::Path(p) => p
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident == "Option")
+                .unwrap_or(false),
+            _ => false,
+        },
+        syn::ReturnType::Default => false,
+    }
+}
+
+fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    let mut output = [0u8; 32];
+    hasher.update(data);
+    hasher.finalize(&mut output);
+    output
+}
+
+fn token_stream_with_error(mut tokens: TokenStream, error: syn::Error) -> TokenStream {
+    tokens.extend(TokenStream::from(error.into_compile_error()));
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::event::keccak256;
+
+    #[test]
+    fn it_computes_event_topic() {
+        assert_eq!(
+            keccak256("Transfer(address,address,uint256)".as_bytes()).to_vec(),
+            vec![
+                0xdd, 0xf2, 0x52, 0xad, 0x1b, 0xe2, 0xc8, 0x9b, 0x69, 0xc2, 0xb0, 0x68, 0xfc, 0x37,
+                0x8d, 0xaa, 0x95, 0x2b, 0xa7, 0xf1, 0x63, 0xc4, 0xa1, 0x16, 0x28, 0xf5, 0x5a, 0x4d,
+                0xf5, 0x23, 0xb3, 0xef,
+            ]
+        );
+    }
+}
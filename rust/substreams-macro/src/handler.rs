@@ -1,17 +1,27 @@
 
 use proc_macro::TokenStream;
 use proc_macro2::{Span};
-use quote::{quote, ToTokens, format_ident};
+use quote::{quote, quote_spanned, ToTokens, format_ident};
 use syn::{spanned::Spanned};
 use crate::errors;
-use crate::config::{ModuleType, FinalConfiguration};
+use crate::config::{self, ModuleType, FinalConfiguration};
 
-pub fn main(_args: TokenStream, item: TokenStream, module_type: ModuleType) -> TokenStream {
+pub fn main(args: TokenStream, item: TokenStream, module_type: ModuleType) -> TokenStream {
     let original = item.clone();
 
-    let final_config = FinalConfiguration { module_type };
+    let args = syn::parse_macro_input!(args as syn::AttributeArgs);
+    let final_config = match config::build_config(args, module_type) {
+        Ok(final_config) => final_config,
+        Err(e) => {
+            return token_stream_with_error(original, e)
+        }
+    };
     let input = syn::parse_macro_input!(item as syn::ItemFn);
 
+    if let Err(e) = check_not_generic(&input.sig) {
+        return token_stream_with_error(original, e)
+    }
+
     let output_result = parse_func_output(&final_config, input.sig.output.clone());
     match output_result {
         Ok(_) => {}
@@ -24,8 +34,9 @@ pub fn main(_args: TokenStream, item: TokenStream, module_type: ModuleType) -> T
     let mut proto_decodings: Vec<proc_macro2::TokenStream> = Vec::with_capacity(input.sig.inputs.len());
     let mut read_only_stores: Vec<proc_macro2::TokenStream> = Vec::with_capacity(input.sig.inputs.len());
     let mut writable_store: proc_macro2::TokenStream = quote! {};
+    let mut decoded_inputs: Vec<String> = Vec::with_capacity(input.sig.inputs.len());
 
-    for i in (&input.sig.inputs).into_iter() {
+    for (position, i) in (&input.sig.inputs).into_iter().enumerate() {
         match i {
             syn::FnArg::Receiver(_) => {
                 return token_stream_with_error(original, syn::Error::new(i.span(), format!("handler function does not support 'self' receiver")));
@@ -36,17 +47,65 @@ pub fn main(_args: TokenStream, item: TokenStream, module_type: ModuleType) -> T
                         let var_name = v.ident.clone();
 
                         let argument_type = &*pat_type.ty;
-                        let input_res = parse_input_type(argument_type);
-                        if input_res.is_err() {
-                            return token_stream_with_error(original, syn::Error::new(pat_type.span(), format!("failed to parse input {:?}",input_res.err())));
+                        // The params are read from the host, they don't take an argument of the exported function
+                        if position == 0 && var_name == "params" {
+                            if is_string(argument_type) {
+                                proto_decodings.push(quote! { let #var_name: #argument_type = substreams::handlers::params_string(); });
+                            } else {
+                                proto_decodings.push(quote! { let #var_name: #argument_type = substreams::handlers::decode_params(); });
+                            }
+                            continue
+                        }
+
+                        if is_raw_bytes(argument_type) {
+                            let var_name_str = var_name.to_string();
+                            let decoded_name = var_name_str.strip_suffix("_raw").filter(|name| decoded_inputs.iter().any(|decoded| decoded == name));
+                            let (var_ptr, var_len) = match decoded_name {
+                                Some(name) => (format_ident!("{}_ptr", name), format_ident!("{}_len", name)),
+                                None => {
+                                    let var_ptr = format_ident!("{}_ptr", var_name);
+                                    let var_len = format_ident!("{}_len", var_name);
+                                    args.push(quote! { #var_ptr: *mut u8 });
+                                    args.push(quote! { #var_len: usize });
+                                    (var_ptr, var_len)
+                                }
+                            };
+
+                            proto_decodings.push(quote! { let #var_name: &[u8] = unsafe { substreams::memory::input_as_slice(#var_ptr, #var_len) }; });
+                            continue
                         }
-                        let input_obj = input_res.unwrap();
+
+                        if let Some(inner_type) = inner_type(argument_type, "Option") {
+                            let arg_index = args.len() as u32;
+                            let var_ptr = format_ident!("{}_ptr", var_name);
+                            let var_len = format_ident!("{}_len", var_name);
+                            args.push(quote! { #var_ptr: *mut u8 });
+                            args.push(quote! { #var_len: usize });
+                            decoded_inputs.push(var_name.to_string());
+
+                            proto_decodings.push(quote! {
+                                let #var_name: Option<#inner_type> = if substreams::handlers::input_present(#arg_index) {
+                                    Some(substreams::proto::decode_ptr(#var_ptr, #var_len).unwrap())
+                                } else {
+                                    None
+                                };
+                            });
+                            continue
+                        }
+
+                        let input_obj = match parse_input_type(argument_type) {
+                            Ok(input_obj) => input_obj,
+                            Err(e) => return token_stream_with_error(original, syn::Error::new(argument_type.span(), e.to_string())),
+                        };
 
                         if input_obj.is_writable_store {
                             if has_seen_writable_store {
                                 return token_stream_with_error(original, syn::Error::new(pat_type.span(), format!("handler cannot have more then one writable store as an input")));
                             }
                             has_seen_writable_store = true;
+                            if let Err(e) = check_store_config(&final_config, &input_obj.resolved_ty, pat_type.ty.span()) {
+                                return token_stream_with_error(original, e)
+                            }
                             writable_store = quote! { let #var_name: #argument_type = #argument_type::new(); };
                             continue
                         }
@@ -54,7 +113,8 @@ pub fn main(_args: TokenStream, item: TokenStream, module_type: ModuleType) -> T
                         if input_obj.is_readable_store {
                             let var_idx = format_ident!("{}_idx",var_name);
                             args.push(quote! { #var_idx: u32 });
-                            read_only_stores.push(quote! { let #var_name: #argument_type = #argument_type::new(#var_idx); });
+                            // A qualified path, `<store::StoreGetProto<T>>::new`, as the store type can have generic arguments
+                            read_only_stores.push(quote! { let #var_name: #argument_type = <#argument_type>::new(#var_idx); });
                             continue
                         }
 
@@ -67,15 +127,22 @@ pub fn main(_args: TokenStream, item: TokenStream, module_type: ModuleType) -> T
                         let var_len = format_ident!("{}_len", var_name);
                         args.push(quote! { #var_ptr: *mut u8 });
                         args.push(quote! { #var_len: usize });
+                        decoded_inputs.push(var_name.to_string());
 
-                        if input_obj.is_deltas {
-                            proto_decodings.push(quote! { let #var_name: #argument_type = substreams::proto::decode_ptr::<substreams::pb::substreams::StoreDeltas>(#var_ptr, #var_len).unwrap().deltas; })
+                        if input_obj.is_clock {
+                            proto_decodings.push(quote! { let #var_name: #argument_type = substreams::handlers::decode_clock(#var_ptr, #var_len); });
+                        } else if input_obj.is_deltas {
+                            let deltas = quote! { substreams::proto::decode_ptr::<substreams::pb::substreams::StoreDeltas>(#var_ptr, #var_len).unwrap().deltas };
+                            match inner_type(argument_type, "Deltas") {
+                                Some(value_type) => proto_decodings.push(quote! { let #var_name: #argument_type = substreams::deltas::decode_deltas::<#value_type>(#deltas); }),
+                                None => proto_decodings.push(quote! { let #var_name: #argument_type = #deltas; }),
+                            }
                         } else {
                             proto_decodings.push(quote! { let #var_name: #argument_type = substreams::proto::decode_ptr(#var_ptr, #var_len).unwrap(); })
                         }
                     },
                     _ => {
-                        return token_stream_with_error(original, syn::Error::new(pat_type.span(), format!("unknown argument type")));
+                        return token_stream_with_error(original, syn::Error::new(pat_type.pat.span(), "handler arguments must be named with a plain identifier, like `blk: eth::Block`"));
                     }
                 }
             },
@@ -84,12 +151,25 @@ pub fn main(_args: TokenStream, item: TokenStream, module_type: ModuleType) -> T
 
 
     match final_config.module_type {
-        ModuleType::Store => build_store_handler(input, args, proto_decodings, read_only_stores, writable_store),
-        ModuleType::Map => build_map_handler(input, args, proto_decodings, read_only_stores, writable_store)
+        ModuleType::Store => build_store_handler(&final_config, input, args, proto_decodings, read_only_stores, writable_store),
+        ModuleType::Map => build_map_handler(&final_config, input, args, proto_decodings, read_only_stores, writable_store)
+    }
+}
+
+// Handlers are exported to the host as `extern "C"` functions, which can't be generic, so
+// generic parameters, lifetimes included, and where clauses are reported on the signature
+// instead of failing in the generated code
+fn check_not_generic(sig: &syn::Signature) -> Result<(), syn::Error> {
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new(sig.generics.span(), format!("handler `{}` cannot be generic, it's exported to the host as an `extern \"C\"` function, use concrete types for its inputs and output", sig.ident)));
+    }
+    if let Some(where_clause) = &sig.generics.where_clause {
+        return Err(syn::Error::new(where_clause.span(), format!("handler `{}` cannot have a where clause, it's exported to the host as an `extern \"C\"` function, use concrete types for its inputs and output", sig.ident)));
     }
+    Ok(())
 }
 
-const WRITABLE_STORE: [&'static str; 15] = [
+const WRITABLE_STORE: [&'static str; 19] = [
     "StoreSet",
     "StoreSetIfNotExists",
     "StoreAddInt64",
@@ -104,15 +184,66 @@ const WRITABLE_STORE: [&'static str; 15] = [
     "StoreMinBigInt",
     "StoreMinFloat64",
     "StoreMinBigFloat",
-    "StoreAppend"
+    "StoreAppend",
+    "StoreSortedSet",
+    "StoreDelete",
+    "StoreSetSumInt64",
+    "StoreSetSumBigInt"
 ];
-const READABLE_STORE: [&'static str; 1] = ["StoreGet"];
+const READABLE_STORE: [&'static str; 6] = ["StoreGet", "StoreGetBigInt", "StoreGetInt64", "StoreGetFloat64", "StoreGetProto", "StoreGetSortedSet"];
+
+// Returns the policy and, when it's not free, the value type of the store module a
+// writable store is used in
+fn writable_store_config(resolved_ty: &str) -> Option<(&'static str, Option<&'static str>)> {
+    let config = match resolved_ty {
+        "StoreSet" => ("set", None),
+        "StoreSetIfNotExists" => ("set_if_not_exists", None),
+        "StoreAddInt64" => ("add", Some("int64")),
+        "StoreAddFloat64" => ("add", Some("float64")),
+        "StoreAddBigFloat" => ("add", Some("bigfloat")),
+        "StoreAddBigInt" => ("add", Some("bigint")),
+        "StoreMaxInt64" => ("max", Some("int64")),
+        "StoreMaxBigInt" => ("max", Some("bigint")),
+        "StoreMaxFloat64" => ("max", Some("float64")),
+        "StoreMaxBigFloat" => ("max", Some("bigfloat")),
+        "StoreMinInt64" => ("min", Some("int64")),
+        "StoreMinBigInt" => ("min", Some("bigint")),
+        "StoreMinFloat64" => ("min", Some("float64")),
+        "StoreMinBigFloat" => ("min", Some("bigfloat")),
+        "StoreAppend" => ("append", None),
+        "StoreSortedSet" => ("set", Some("int64")),
+        "StoreSetSumInt64" => ("set", Some("set_sum_int64")),
+        "StoreSetSumBigInt" => ("set", Some("set_sum_bigint")),
+        _ => return None,
+    };
+    Some(config)
+}
+
+fn check_store_config(final_config: &FinalConfiguration, resolved_ty: &str, span: Span) -> Result<(), syn::Error> {
+    let (policy, value_type) = match writable_store_config(resolved_ty) {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+
+    if let Some(declared) = &final_config.policy {
+        if declared != policy {
+            return Err(syn::Error::new(span, format!("`{}` writes a store with `policy = \"{}\"`, the handler declares `policy = \"{}\"`", resolved_ty, policy, declared)));
+        }
+    }
+    if let (Some(declared), Some(value_type)) = (&final_config.value_type, value_type) {
+        if declared != value_type {
+            return Err(syn::Error::new(span, format!("`{}` writes a store with `value_type = \"{}\"`, the handler declares `value_type = \"{}\"`", resolved_ty, value_type, declared)));
+        }
+    }
+    Ok(())
+}
 
 #[derive(Debug)]
 struct Input {
     is_writable_store: bool,
     is_readable_store: bool,
     is_deltas: bool,
+    is_clock: bool,
     resolved_ty: String
 }
 
@@ -124,12 +255,17 @@ fn parse_input_type(ty: &syn::Type) -> Result<Input, errors::SubstreamMacroError
                 is_writable_store: false,
                 is_readable_store: false,
                 is_deltas: false,
+                is_clock: false,
                 resolved_ty: "".to_owned()
             };
             let mut last_type = "".to_owned();
             for segment in p.path.segments.iter() {
                     last_type = segment.ident.to_string();
             }
+            if last_type == "Vec" {
+                // A `Vec` is never a message, the typical mistake is to ask for raw bytes
+                return Err(errors::SubstreamMacroError::UnknownInputType(type_name(ty)));
+            }
             input.resolved_ty = last_type.clone();
             for t in WRITABLE_STORE {
                 if last_type == t.to_owned() {
@@ -145,20 +281,89 @@ fn parse_input_type(ty: &syn::Type) -> Result<Input, errors::SubstreamMacroError
                 // todo: should check that it's fully qualified to be our `store::Deltas`
                 input.is_deltas = true;
             }
+            // `substreams::pb::substreams::Clock`, or `pb::substreams::Clock` within the crate, not
+            // any message named `Clock`
+            let segments: Vec<String> = p.path.segments.iter().map(|segment| segment.ident.to_string()).collect();
+            if segments.ends_with(&["pb".to_owned(), "substreams".to_owned(), "Clock".to_owned()]) {
+                input.is_clock = true;
+            }
             Ok(input)
         }
+        // The macro only sees the tokens of the handler, a bound like `impl Balances` can't be
+        // resolved to the store it stands for, and stores are constructed by value
+        syn::Type::ImplTrait(_) => Err(errors::SubstreamMacroError::UnresolvedStoreType(type_name(ty))),
+        syn::Type::Reference(r) if matches!(&*r.elem, syn::Type::ImplTrait(_)) || is_store_path(&r.elem) => {
+            Err(errors::SubstreamMacroError::UnresolvedStoreType(type_name(ty)))
+        }
         _ => {
-            Err(errors::SubstreamMacroError::UnknownInputType("unable to parse input type".to_owned()))
+            Err(errors::SubstreamMacroError::UnknownInputType(type_name(ty)))
         }
     }
 }
 
+fn is_store_path(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(p) => p.path.segments.last().map_or(false, |segment| {
+            let name = segment.ident.to_string();
+            WRITABLE_STORE.contains(&name.as_str()) || READABLE_STORE.contains(&name.as_str())
+        }),
+        _ => false,
+    }
+}
+
+// Renders a type like it's usually written, the token stream puts spaces between all tokens
+fn type_name(ty: &syn::Type) -> String {
+    let mut name = ty.to_token_stream().to_string();
+    for (spaced, compact) in [(" :: ", "::"), (" < ", "<"), (" >", ">"), (" ,", ","), ("( ", "("), (" )", ")"), ("[ ", "["), (" ]", "]"), ("& ", "&")] {
+        name = name.replace(spaced, compact);
+    }
+    name
+}
+
+fn is_raw_bytes(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Reference(r) => match &*r.elem {
+            syn::Type::Slice(s) => match &*s.elem {
+                syn::Type::Path(p) => p.path.is_ident("u8"),
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn is_string(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(p) => p.path.segments.last().map_or(false, |segment| segment.ident == "String" && segment.arguments.is_empty()),
+        _ => false,
+    }
+}
+
+// Returns `T` for a `<wrapper><T>` input, like `Option<T>`
+fn inner_type<'a>(ty: &'a syn::Type, wrapper: &str) -> Option<&'a syn::Type> {
+    let segment = match ty {
+        syn::Type::Path(p) => p.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != wrapper {
+        return None;
+    }
+
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first()? {
+            syn::GenericArgument::Type(inner) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
 
 fn parse_func_output(final_config: &FinalConfiguration, output: syn::ReturnType) -> Result<(), syn::Error> {
     match final_config.module_type {
         ModuleType::Map => {
             if output == syn::ReturnType::Default {
-                return Err(syn::Error::new(Span::call_site(), "Module of type Map should have a return of type Result<YOUR_TYPE, SubstreamError>"));
+                return Err(syn::Error::new(Span::call_site(), "Module of type Map should have a return of type Result<YOUR_TYPE, SubstreamError> or Option<YOUR_TYPE>"));
             }
 
             let expected = vec!["-".to_owned(), ">".to_owned(), "Result".to_owned()];
@@ -169,10 +374,11 @@ fn parse_func_output(final_config: &FinalConfiguration, output: syn::ReturnType)
                     if valid {
                         return Ok(())
                     } else {
-                        return Err(syn::Error::new(Span::call_site(), "Module of type Map should return a Result<>"));
+                        return Err(syn::Error::new(Span::call_site(), "Module of type Map should return a Result<> or an Option<>"));
                     }
                 }
-                if i.to_string() != expected[index] {
+                let is_option = index == expected.len() - 1 && i.to_string() == "Option";
+                if i.to_string() != expected[index] && !is_option {
                     valid = false
                 }
                 index += 1;
@@ -181,53 +387,83 @@ fn parse_func_output(final_config: &FinalConfiguration, output: syn::ReturnType)
         },
         ModuleType::Store => {
             if output != syn::ReturnType::Default {
-                return Err(syn::Error::new(Span::call_site(), "Module of type Store should not have a return statement"));
+                return Err(syn::Error::new_spanned(&output, "Module of type Store should not have a return statement"));
             }
             return Ok(())
         }
     }
 }
 
-fn build_map_handler(input: syn::ItemFn, collected_args: Vec<proc_macro2::TokenStream>, decodings: Vec<proc_macro2::TokenStream>, read_only_stores: Vec<proc_macro2::TokenStream>, writable_store: proc_macro2::TokenStream) -> TokenStream {
+fn build_map_handler(final_config: &FinalConfiguration, input: syn::ItemFn, collected_args: Vec<proc_macro2::TokenStream>, decodings: Vec<proc_macro2::TokenStream>, read_only_stores: Vec<proc_macro2::TokenStream>, writable_store: proc_macro2::TokenStream) -> TokenStream {
     let body = &input.block;
     let header = quote! {
         #[no_mangle]
     };
+    let panic_hook = build_panic_hook(final_config);
     let func_name = input.sig.ident.clone();
+    let module_name = func_name.to_string();
+    let timer = build_timer(final_config, &func_name);
     let lambda_return = input.sig.output.clone();
+    // The writable store, if any, is constructed before the body so its writes all happen
+    // before the output is emitted, the guard flushing its buffered writes is dropped when
+    // the body returns, also before
     let lambda = quote! {
         let func = || #lambda_return {
+            let _flush_guard = substreams::handlers::FlushGuard;
             #(#decodings)*
             #(#read_only_stores)*
             #writable_store
             #body
         };
     };
+    let output_assertion = build_output_assertion(&input.sig.output);
+    let output = if returns_option(&input.sig.output) {
+        quote! {
+            if let Some(output) = func() {
+                substreams::output(output);
+            }
+        }
+    } else {
+        quote! {
+            match func() {
+                Ok(output) => substreams::output(output),
+                Err(err) => substreams::set_error(err),
+            }
+        }
+    };
     let result = quote! {
+        #output_assertion
+
         #header
         pub extern "C" fn #func_name(#(#collected_args),*){
-            substreams::register_panic_hook();
+            const SUBSTREAMS_MODULE_NAME: &str = #module_name;
+            #panic_hook
+            substreams::handlers::begin_invocation(SUBSTREAMS_MODULE_NAME);
+            #timer
             #lambda
-            let result = func();
-            if result.is_err() {
-                panic!("{:?}", result.err().unwrap())
-            }
-            substreams::output(result.unwrap());
+            #output
         }
     };
     result.into()
 }
 
-fn build_store_handler(input: syn::ItemFn, collected_args: Vec<proc_macro2::TokenStream>, decodings: Vec<proc_macro2::TokenStream>, read_only_stores: Vec<proc_macro2::TokenStream>, writable_store: proc_macro2::TokenStream) -> TokenStream {
+fn build_store_handler(final_config: &FinalConfiguration, input: syn::ItemFn, collected_args: Vec<proc_macro2::TokenStream>, decodings: Vec<proc_macro2::TokenStream>, read_only_stores: Vec<proc_macro2::TokenStream>, writable_store: proc_macro2::TokenStream) -> TokenStream {
     let body = &input.block;
     let header = quote! {
         #[no_mangle]
     };
+    let panic_hook = build_panic_hook(final_config);
     let func_name = input.sig.ident.clone();
+    let module_name = func_name.to_string();
+    let timer = build_timer(final_config, &func_name);
     let result = quote! {
         #header
         pub extern "C" fn #func_name(#(#collected_args),*){
-            substreams::register_panic_hook();
+            const SUBSTREAMS_MODULE_NAME: &str = #module_name;
+            #panic_hook
+            substreams::handlers::begin_invocation(SUBSTREAMS_MODULE_NAME);
+            #timer
+            let _flush_guard = substreams::handlers::FlushGuard;
             #(#decodings)*
             #(#read_only_stores)*
             #writable_store
@@ -237,8 +473,89 @@ fn build_store_handler(input: syn::ItemFn, collected_args: Vec<proc_macro2::Toke
     result.into()
 }
 
+// Statically asserts that the `Ok` type of the map output implements `prost::Message`, so
+// a bad output type is reported on the handler signature instead of on the generated call
+// to `substreams::output`.
+fn build_output_assertion(output: &syn::ReturnType) -> proc_macro2::TokenStream {
+    let output_type = match output {
+        syn::ReturnType::Type(_, ty) => match &**ty {
+            syn::Type::Path(p) => p.path.segments.last().and_then(|segment| match &segment.arguments {
+                syn::PathArguments::AngleBracketed(args) if segment.ident == "Result" || segment.ident == "Option" => args.args.first().and_then(|arg| match arg {
+                    syn::GenericArgument::Type(ty) => Some(ty.clone()),
+                    _ => None,
+                }),
+                _ => None,
+            }),
+            _ => None,
+        },
+        syn::ReturnType::Default => None,
+    };
+
+    match output_type {
+        Some(ty) => quote_spanned! { ty.span() =>
+            const _: fn() = || {
+                substreams::handlers::assert_output_message::<#ty>();
+            };
+        },
+        None => quote! {},
+    }
+}
+
+fn returns_option(output: &syn::ReturnType) -> bool {
+    match output {
+        syn::ReturnType::Type(_, ty) => match &**ty {
+            syn::Type::Path(p) => p.path.segments.last().map_or(false, |segment| segment.ident == "Option"),
+            _ => false,
+        },
+        syn::ReturnType::Default => false,
+    }
+}
+
+fn build_panic_hook(final_config: &FinalConfiguration) -> proc_macro2::TokenStream {
+    if !final_config.panic_hook {
+        return quote! {};
+    }
+
+    quote! {
+        substreams::register_panic_hook();
+    }
+}
+
+// The timer is bound to a variable so it's only dropped, and logs the elapsed time, when
+// the handler returns.
+fn build_timer(final_config: &FinalConfiguration, func_name: &syn::Ident) -> proc_macro2::TokenStream {
+    if !final_config.timed {
+        return quote! {};
+    }
+
+    let module_name = func_name.to_string();
+    quote! {
+        let _timer = substreams::handlers::Timer::start(#module_name);
+    }
+}
 
 fn token_stream_with_error(mut tokens: TokenStream, error: syn::Error) -> TokenStream {
     tokens.extend(TokenStream::from(error.into_compile_error()));
     tokens
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::config::{FinalConfiguration, ModuleType};
+    use crate::handler::parse_func_output;
+
+    #[test]
+    fn it_rejects_store_return_type() {
+        let config = FinalConfiguration {
+            module_type: ModuleType::Store,
+            panic_hook: true,
+            timed: false,
+            policy: None,
+            value_type: None,
+        };
+
+        let err = parse_func_output(&config, syn::parse_quote! { -> Option<u64> }).unwrap_err();
+        assert_eq!(err.to_string(), "Module of type Store should not have a return statement");
+        assert!(parse_func_output(&config, syn::ReturnType::Default).is_ok());
+    }
+}
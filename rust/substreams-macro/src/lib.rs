@@ -1,9 +1,12 @@
 use proc_macro::TokenStream;
 
 mod config;
+mod entities;
 mod errors;
+mod event;
 mod handler;
 mod store;
+mod store_key;
 
 #[proc_macro_attribute]
 pub fn map(args: TokenStream, item: TokenStream) -> TokenStream {
@@ -15,7 +18,22 @@ pub fn store(args: TokenStream, item: TokenStream) -> TokenStream {
     return handler::main(args, item, config::ModuleType::Store);
 }
 
+#[proc_macro_attribute]
+pub fn entities(args: TokenStream, item: TokenStream) -> TokenStream {
+    entities::main(args, item)
+}
+
+#[proc_macro_attribute]
+pub fn event(args: TokenStream, item: TokenStream) -> TokenStream {
+    event::main(args, item)
+}
+
 #[proc_macro_derive(StoreWriter)]
 pub fn derive(input: TokenStream) -> TokenStream {
     store::main(input)
 }
+
+#[proc_macro_derive(StoreKey, attributes(store_key))]
+pub fn derive_store_key(input: TokenStream) -> TokenStream {
+    store_key::main(input)
+}
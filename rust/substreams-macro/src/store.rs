@@ -8,14 +8,41 @@ pub(crate) fn main(input: TokenStream) -> TokenStream {
 
     let tokens = quote! {
         impl #name {
-            pub fn new() -> #name { #name{} }
+            /// Returns the writer of the output store of the module, the handler macro
+            /// creates it and passes it to the handler.
+            ///
+            /// # Panics
+            ///
+            /// Panics if a writable store was already created by this call of the module, a
+            /// module only writes its own output store.
+            pub fn new() -> #name {
+                claim_writer(stringify!(#name));
+                #name{}
+            }
 
-            /// Allows you to delete a set of keys by prefix. Do not use this to delete
+            /// Allows you to delete a set of keys by prefix, whatever the update policy of the
+            /// store, so an `add` or `max` store can also prune its keys. Do not use this to delete
             /// individual keys if you want consistent highly performant parallelized operations.
             /// Rather, design key spaces where you can delete large number of keys in
             /// one swift using a meaningful prefix.
-            pub fn delete_prefix(&self, ord: i64, prefix: &String) {
-                state::delete_prefix(ord, prefix);
+            pub fn delete_prefix<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, prefix: K) {
+                state::delete_prefix(ord.into().as_i64(), prefix);
+            }
+
+            /// Reads a key of this store as it was at the end of the previous block, before
+            /// any change applied within the current block. When a block is undone on a reorg,
+            /// the store is rewound with it, so this always returns the value of the canonical
+            /// previous block.
+            pub fn get_previous_block<K: AsRef<str>>(&self, key: K) -> Option<Vec<u8>> {
+                state::get_previous_block(key)
+            }
+
+            /// Deletes every key of the store at the given ordinal, a delete delta is
+            /// emitted for each key, so clearing a big store produces a lot of deltas.
+            /// Keys written after the `clear` call are kept, the delete applies to the
+            /// keys set before it.
+            pub fn clear(&self, ord: impl Into<Ordinal>) {
+                state::clear(ord.into().as_i64());
             }
         }
     };
@@ -0,0 +1,119 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, DeriveInput};
+
+pub(crate) fn main(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match build(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+fn build(input: DeriveInput) -> Result<proc_macro2::TokenStream, syn::Error> {
+    let name = &input.ident;
+    let prefix = parse_prefix(&input.attrs)?;
+
+    let fields = match &input.data {
+        syn::Data::Struct(syn::DataStruct {
+            fields: syn::Fields::Named(fields),
+            ..
+        }) => fields
+            .named
+            .iter()
+            .map(|f| f.ident.clone().unwrap())
+            .collect::<Vec<_>>(),
+        _ => {
+            return Err(syn::Error::new(
+                input.span(),
+                "StoreKey can only be derived for structs with named fields",
+            ))
+        }
+    };
+    let field_names: Vec<String> = fields.iter().map(|f| f.to_string()).collect();
+
+    let (write_prefix, check_prefix, parts) = match &prefix {
+        Some(prefix) => (
+            quote! { store_key_parts.push(#prefix.to_owned()); },
+            quote! {
+                if store_key_parts.next() != Some(#prefix) {
+                    return Err(substreams::errors::Error::Unexpected(format!("key {} does not start with prefix {}", store_key, #prefix)));
+                }
+            },
+            fields.len() + 1,
+        ),
+        None => (quote! {}, quote! {}, fields.len()),
+    };
+
+    Ok(quote! {
+        impl substreams::store::StoreKey for #name {
+            fn to_key(&self) -> String {
+                let mut store_key_parts: Vec<String> = Vec::with_capacity(#parts);
+                #write_prefix
+                #( store_key_parts.push(substreams::store::KeyField::to_key_field(&self.#fields)); )*
+                store_key_parts.join(":")
+            }
+
+            fn parse_key(store_key: &str) -> Result<Self, substreams::errors::Error> {
+                let mut store_key_parts = store_key.splitn(#parts, ':');
+                #check_prefix
+                #(
+                    let #fields = store_key_parts
+                        .next()
+                        .and_then(substreams::store::KeyField::parse_key_field)
+                        .ok_or_else(|| substreams::errors::Error::Unexpected(format!("key {} has an invalid `{}` field", store_key, #field_names)))?;
+                )*
+
+                Ok(#name { #(#fields),* })
+            }
+        }
+    })
+}
+
+fn parse_prefix(attrs: &[syn::Attribute]) -> Result<Option<syn::LitStr>, syn::Error> {
+    let mut prefix = None;
+
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("store_key")) {
+        let list = match attr.parse_meta()? {
+            syn::Meta::List(list) => list,
+            other => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "expected `#[store_key(prefix = \"...\")]`",
+                ))
+            }
+        };
+
+        for nested in list.nested {
+            match nested {
+                syn::NestedMeta::Meta(syn::Meta::NameValue(namevalue))
+                    if namevalue.path.is_ident("prefix") =>
+                {
+                    if prefix.is_some() {
+                        return Err(syn::Error::new_spanned(
+                            namevalue,
+                            "`prefix` set multiple times.",
+                        ));
+                    }
+                    match namevalue.lit {
+                        syn::Lit::Str(s) if !s.value().contains(':') => prefix = Some(s),
+                        other => {
+                            return Err(syn::Error::new_spanned(
+                                other,
+                                "`prefix` must be a string without `:`",
+                            ))
+                        }
+                    }
+                }
+                other => {
+                    return Err(syn::Error::new_spanned(
+                        other,
+                        "Unknown attribute inside the macro; expected one of: `prefix`",
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(prefix)
+}
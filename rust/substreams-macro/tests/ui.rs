@@ -0,0 +1,8 @@
+// The errors reported by the handler macros, each file of `tests/ui` fails to compile
+// with the output in the `.stderr` file next to it, run with `TRYBUILD=overwrite` to
+// update them.
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,22 @@
+use substreams::errors::Error;
+use substreams::pb::substreams::Clock;
+
+#[substreams::handlers::map]
+fn map_generic<T: prost::Message>(clock: Clock) -> Result<T, Error> {
+    unimplemented!("do something");
+}
+
+#[substreams::handlers::map]
+fn map_lifetime<'a>(clock: Clock) -> Result<Clock, Error> {
+    unimplemented!("do something");
+}
+
+#[substreams::handlers::map]
+fn map_where(clock: Clock) -> Result<Clock, Error>
+where
+    Clock: Default,
+{
+    unimplemented!("do something");
+}
+
+fn main() {}
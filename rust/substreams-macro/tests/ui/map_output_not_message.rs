@@ -0,0 +1,8 @@
+struct NotAMessage;
+
+#[substreams::handlers::map]
+fn map_handler() -> Result<NotAMessage, substreams::errors::Error> {
+    Ok(NotAMessage)
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+struct NotAMessage;
+
+#[substreams::handlers::map]
+fn map_bytes() -> Vec<u8> {
+    Vec::new()
+}
+
+#[substreams::handlers::map]
+fn map_optional() -> Option<NotAMessage> {
+    None
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+use substreams::errors::Error;
+use substreams::pb::substreams::Clock;
+
+#[substreams::handlers::map]
+fn map_pair(pair: (u64, u64)) -> Result<Clock, Error> {
+    unimplemented!("do something");
+}
+
+#[substreams::handlers::map]
+fn map_bytes(data: Vec<u8>) -> Result<Clock, Error> {
+    unimplemented!("do something");
+}
+
+#[substreams::handlers::map]
+fn map_name(name: &str) -> Result<Clock, Error> {
+    unimplemented!("do something");
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+use substreams::pb::substreams::Clock;
+use substreams::store;
+
+#[substreams::handlers::store(policy = "add", value_type = "bigint")]
+fn store_volumes(clock: Clock, s: store::StoreAddInt64) {
+    s.add(clock.number, "blocks", 1);
+}
+
+#[substreams::handlers::store(policy = "set")]
+fn store_totals(clock: Clock, s: store::StoreAddFloat64) {
+    s.add(clock.number, "blocks", 1.0);
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use substreams::pb::substreams::Clock;
+use substreams::store;
+
+#[substreams::handlers::store]
+fn store_blocks(clock: Clock, s: store::StoreAddInt64) -> u64 {
+    s.add(clock.number, "blocks", 1);
+    clock.number
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use substreams::pb::substreams::Clock;
+use substreams::store;
+
+// The macro only sees the handler, `Balances` is taken for a protobuf message
+type Balances = store::StoreAddInt64;
+
+#[substreams::handlers::store]
+fn store_balances(clock: Clock, s: Balances) {
+    s.add(clock.number, "blocks", 1);
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+use substreams::pb::substreams::Clock;
+use substreams::store;
+
+trait Balances {}
+
+#[substreams::handlers::store]
+fn store_impl(clock: Clock, s: impl Balances) {
+    unimplemented!("do something");
+}
+
+#[substreams::handlers::store]
+fn store_ref_impl(clock: Clock, s: &impl Balances) {
+    unimplemented!("do something");
+}
+
+#[substreams::handlers::store]
+fn store_ref(clock: Clock, s: &store::StoreAddInt64) {
+    unimplemented!("do something");
+}
+
+fn main() {}
@@ -0,0 +1,112 @@
+use num_bigint::{BigInt, Sign};
+use thiserror::Error;
+
+/// OverflowError is returned when big-endian bytes hold a value too big for the integer
+/// they are converted to
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("value of {significant} significant bytes overflows a {bits} bits integer")]
+pub struct OverflowError {
+    /// The number of bytes of the value once its leading zero bytes are dropped
+    pub significant: usize,
+    /// The size of the target integer
+    pub bits: u32,
+}
+
+/// Converts unsigned big-endian bytes, like an Ethereum `uint256` topic holding a token
+/// id, to a `u64`. The input can be of any length, it's left padded with zero bytes so
+/// only the value must fit in 8 bytes.
+///
+/// # Examples
+///
+/// ```
+/// let topic = [[0u8; 24].as_ref(), &42u64.to_be_bytes()].concat();
+/// assert_eq!(substreams::bytes_to_u64_be(&topic), Ok(42));
+///
+/// assert!(substreams::bytes_to_u64_be(&[1u8; 9]).is_err());
+/// ```
+pub fn bytes_to_u64_be(bytes: &[u8]) -> Result<u64, OverflowError> {
+    let mut value = [0u8; 8];
+    copy_significant(bytes, &mut value)?;
+    Ok(u64::from_be_bytes(value))
+}
+
+/// Converts unsigned big-endian bytes to a `u128` like [bytes_to_u64_be], the value must
+/// fit in 16 bytes.
+pub fn bytes_to_u128_be(bytes: &[u8]) -> Result<u128, OverflowError> {
+    let mut value = [0u8; 16];
+    copy_significant(bytes, &mut value)?;
+    Ok(u128::from_be_bytes(value))
+}
+
+/// Converts unsigned big-endian bytes to a positive, or zero, [BigInt], which any value
+/// fits in. Empty bytes are zero.
+pub fn bytes_to_bigint_be(bytes: &[u8]) -> BigInt {
+    BigInt::from_bytes_be(Sign::Plus, bytes)
+}
+
+// Copies the bytes of the value into the end of `out`, without the leading zero bytes
+fn copy_significant(bytes: &[u8], out: &mut [u8]) -> Result<(), OverflowError> {
+    let first = bytes
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(bytes.len());
+    let significant = &bytes[first..];
+    if significant.len() > out.len() {
+        return Err(OverflowError {
+            significant: significant.len(),
+            bits: out.len() as u32 * 8,
+        });
+    }
+
+    let start = out.len() - significant.len();
+    out[start..].copy_from_slice(significant);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bytes::{bytes_to_bigint_be, bytes_to_u128_be, bytes_to_u64_be, OverflowError};
+    use num_bigint::BigInt;
+
+    #[test]
+    fn it_converts_exact_fit() {
+        assert_eq!(bytes_to_u64_be(&u64::MAX.to_be_bytes()), Ok(u64::MAX));
+        assert_eq!(bytes_to_u64_be(&[0x01, 0x02]), Ok(0x0102));
+        assert_eq!(bytes_to_u64_be(&[]), Ok(0));
+        assert_eq!(bytes_to_u128_be(&u128::MAX.to_be_bytes()), Ok(u128::MAX));
+    }
+
+    #[test]
+    fn it_ignores_leading_zeros() {
+        let mut topic = [0u8; 32];
+        topic[24..].copy_from_slice(&1234u64.to_be_bytes());
+        assert_eq!(bytes_to_u64_be(&topic), Ok(1234));
+        assert_eq!(bytes_to_u128_be(&topic), Ok(1234));
+        assert_eq!(bytes_to_bigint_be(&topic), BigInt::from(1234));
+
+        assert_eq!(bytes_to_u64_be(&[0u8; 32]), Ok(0));
+        assert_eq!(bytes_to_bigint_be(&[]), BigInt::from(0));
+    }
+
+    #[test]
+    fn it_reports_overflow() {
+        let mut topic = [0u8; 32];
+        topic[23] = 1;
+        assert_eq!(
+            bytes_to_u64_be(&topic),
+            Err(OverflowError {
+                significant: 9,
+                bits: 64
+            })
+        );
+        assert_eq!(bytes_to_u128_be(&topic), Ok(1 << 64));
+        assert_eq!(
+            bytes_to_u128_be(&[0xff; 32]).unwrap_err().to_string(),
+            "value of 32 significant bytes overflows a 128 bits integer"
+        );
+        assert_eq!(
+            bytes_to_bigint_be(&topic),
+            BigInt::from(u64::MAX) + BigInt::from(1)
+        );
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+/// Removes in place the items of `items` whose key, as returned by `key_fn`, was already
+/// seen, only the first occurrence of each key is kept and the order of the remaining
+/// items is preserved.
+///
+/// # Examples
+///
+/// ```
+/// let mut transfers = vec![("0xa1", 0), ("0xa1", 1), ("0xa1", 0), ("0xb2", 0)];
+/// substreams::dedup_by_key(&mut transfers, |(trx_hash, log_index)| (*trx_hash, *log_index));
+///
+/// assert_eq!(transfers, vec![("0xa1", 0), ("0xa1", 1), ("0xb2", 0)]);
+/// ```
+pub fn dedup_by_key<T, K, F>(items: &mut Vec<T>, mut key_fn: F)
+where
+    K: Eq + Hash,
+    F: FnMut(&T) -> K,
+{
+    let mut seen = HashSet::with_capacity(items.len());
+    items.retain(|item| seen.insert(key_fn(item)));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dedup::dedup_by_key;
+
+    #[derive(Debug, PartialEq)]
+    struct Transfer {
+        trx_hash: Vec<u8>,
+        log_index: u32,
+        token_id: u64,
+    }
+
+    fn transfer(trx_hash: u8, log_index: u32, token_id: u64) -> Transfer {
+        Transfer {
+            trx_hash: vec![trx_hash; 32],
+            log_index,
+            token_id,
+        }
+    }
+
+    #[test]
+    fn it_keeps_first_occurrence_in_order() {
+        let mut transfers = vec![
+            transfer(2, 0, 10),
+            transfer(1, 0, 11),
+            transfer(2, 0, 12),
+            transfer(1, 1, 13),
+            transfer(1, 0, 14),
+        ];
+        dedup_by_key(&mut transfers, |t| (t.trx_hash.clone(), t.log_index));
+
+        assert_eq!(
+            transfers,
+            vec![transfer(2, 0, 10), transfer(1, 0, 11), transfer(1, 1, 13)]
+        );
+    }
+
+    #[test]
+    fn it_keeps_unique_items_untouched() {
+        let mut transfers = vec![transfer(3, 0, 1), transfer(1, 0, 2), transfer(2, 0, 3)];
+        dedup_by_key(&mut transfers, |t| t.token_id);
+
+        assert_eq!(
+            transfers,
+            vec![transfer(3, 0, 1), transfer(1, 0, 2), transfer(2, 0, 3)]
+        );
+    }
+}
@@ -0,0 +1,606 @@
+//! Deltas helpers for Substreams.
+//!
+//! This crate offers helpers to work with the [store::Deltas](crate::store::Deltas)
+//! received by handlers that have a store in delta mode as input.
+//!
+
+use crate::pb::substreams::store_delta::Operation;
+use crate::pb::substreams::StoreDelta;
+use crate::proto;
+use crate::store::Deltas;
+use bigdecimal::BigDecimal;
+use num_bigint::BigInt;
+use std::str::FromStr;
+
+/// DeltaDecoded is a [StoreDelta] with its old and new values decoded as `T`. An
+/// empty value, like the old value of a key's first write or the new value of a
+/// deleted key, is decoded as `None`.
+#[derive(Debug, PartialEq)]
+pub struct DeltaDecoded<T> {
+    pub operation: Operation,
+    pub ordinal: u64,
+    pub old_value: Option<T>,
+    pub new_value: Option<T>,
+}
+
+impl<T: Default + prost::Message> DeltaDecoded<T> {
+    /// Decodes the old and new values of the delta as `T`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a non-empty value can't be decoded as a `T`.
+    pub fn decode(delta: &StoreDelta) -> DeltaDecoded<T> {
+        DeltaDecoded {
+            operation: Operation::from_i32(delta.operation).unwrap_or(Operation::Unset),
+            ordinal: delta.ordinal,
+            old_value: decode_value(&delta.key, &delta.old_value),
+            new_value: decode_value(&delta.key, &delta.new_value),
+        }
+    }
+}
+
+/// DeltaValue is the type of the values of a store, giving the type of the deltas of
+/// [store::Deltas](crate::store::Deltas). It's implemented for all the protobuf messages,
+/// decoding the deltas, and for [Untyped] keeping the raw deltas.
+pub trait DeltaValue {
+    type Delta;
+
+    fn decode_delta(delta: StoreDelta) -> Self::Delta;
+}
+
+/// Untyped is the values type of [store::Deltas](crate::store::Deltas) holding the raw
+/// [StoreDelta], it's the default.
+pub struct Untyped;
+
+impl DeltaValue for Untyped {
+    type Delta = StoreDelta;
+
+    fn decode_delta(delta: StoreDelta) -> StoreDelta {
+        delta
+    }
+}
+
+impl<T: Default + prost::Message> DeltaValue for T {
+    type Delta = (String, DeltaDecoded<T>);
+
+    /// # Panics
+    ///
+    /// Panics if a non-empty value can't be decoded as a `T`.
+    fn decode_delta(delta: StoreDelta) -> (String, DeltaDecoded<T>) {
+        let decoded = DeltaDecoded::decode(&delta);
+        (delta.key, decoded)
+    }
+}
+
+// Called by the generated handlers to decode a `Deltas<T>` input.
+#[doc(hidden)]
+pub fn decode_deltas<T: DeltaValue>(deltas: Vec<StoreDelta>) -> Deltas<T> {
+    deltas.into_iter().map(T::decode_delta).collect()
+}
+
+fn decode_value<T: Default + prost::Message>(key: &str, value: &Vec<u8>) -> Option<T> {
+    if value.is_empty() {
+        return None;
+    }
+
+    Some(decode_present(key, value))
+}
+
+fn decode_present<T: Default + prost::Message>(key: &str, value: &Vec<u8>) -> T {
+    match proto::decode(value) {
+        Ok(decoded) => decoded,
+        Err(e) => panic!("value of key {} can't be decoded: {}", key, e),
+    }
+}
+
+/// Decodes the new value of each delta as `T` and returns the results of `f` on them, in
+/// the order of the deltas, for a map module re-emitting the writes of the store it
+/// follows in another shape. Deletions, which have no new value, are skipped. Deletions
+/// are told by the operation of the delta, an empty new value of another operation is
+/// decoded as a message with all its fields at their default value.
+///
+/// # Panics
+///
+/// Panics if a new value can't be decoded as a `T`.
+///
+/// # Examples
+///
+/// ```
+/// use substreams::deltas::map_new_values;
+/// # use substreams::pb::substreams::Clock as Token;
+/// # let deltas: substreams::store::Deltas = vec![];
+///
+/// let ids: Vec<String> = map_new_values(&deltas, |delta, token: Token| {
+///     format!("{}@{}", delta.key, token.id)
+/// });
+/// ```
+pub fn map_new_values<V, T, F>(deltas: &Deltas, mut f: F) -> Vec<T>
+where
+    V: Default + prost::Message,
+    F: FnMut(&StoreDelta, V) -> T,
+{
+    deltas
+        .iter()
+        .filter(|delta| classify(delta) != DeltaOp::Delete)
+        .map(|delta| f(delta, decode_present(&delta.key, &delta.new_value)))
+        .collect()
+}
+
+/// Decodes the old value of each delta as `T` and returns the results of `f` on them, like
+/// [map_new_values]. Creations, which have no old value, are skipped.
+///
+/// # Panics
+///
+/// Panics if an old value can't be decoded as a `T`.
+pub fn map_old_values<V, T, F>(deltas: &Deltas, mut f: F) -> Vec<T>
+where
+    V: Default + prost::Message,
+    F: FnMut(&StoreDelta, V) -> T,
+{
+    deltas
+        .iter()
+        .filter(|delta| classify(delta) != DeltaOp::Create)
+        .map(|delta| f(delta, decode_present(&delta.key, &delta.old_value)))
+        .collect()
+}
+
+/// Returns the old and new values of a delta of a `bigint` store, like one written by
+/// [StoreAddBigInt](crate::store::StoreAddBigInt). An empty value, like the old value of
+/// a key's first write or the new value of a deleted key, is returned as `None`.
+///
+/// # Panics
+///
+/// Panics if a non-empty value is not a valid `bigint`.
+pub fn as_bigint_delta(delta: &StoreDelta) -> (Option<BigInt>, Option<BigInt>) {
+    let parse = |value: &Vec<u8>| {
+        parse_number(&delta.key, value, "bigint", |text| {
+            BigInt::parse_bytes(text.as_bytes(), 10)
+        })
+    };
+
+    (parse(&delta.old_value), parse(&delta.new_value))
+}
+
+/// Returns the old and new values of a delta of a `bigfloat` store, like one written by
+/// [StoreAddBigFloat](crate::store::StoreAddBigFloat). An empty value is returned as
+/// `None`, like for [as_bigint_delta].
+///
+/// # Panics
+///
+/// Panics if a non-empty value is not a valid `bigfloat`.
+pub fn as_bigdecimal_delta(delta: &StoreDelta) -> (Option<BigDecimal>, Option<BigDecimal>) {
+    let parse = |value: &Vec<u8>| {
+        parse_number(&delta.key, value, "bigfloat", |text| {
+            BigDecimal::from_str(text).ok()
+        })
+    };
+
+    (parse(&delta.old_value), parse(&delta.new_value))
+}
+
+fn parse_number<T, F>(key: &str, value: &Vec<u8>, value_type: &str, parse: F) -> Option<T>
+where
+    F: FnOnce(&str) -> Option<T>,
+{
+    if value.is_empty() {
+        return None;
+    }
+
+    let parsed = std::str::from_utf8(value).ok().and_then(parse);
+    match parsed {
+        Some(number) => Some(number),
+        None => panic!("value of key {} is not a valid {}", key, value_type),
+    }
+}
+
+/// Returns an iterator over the deltas whose key starts with `prefix`, in their original
+/// order, without copying them. An empty prefix matches every delta.
+///
+/// # Examples
+///
+/// ```
+/// use substreams::deltas::filter_by_prefix;
+/// use substreams::pb::substreams::StoreDelta;
+///
+/// let deltas = vec![
+///     StoreDelta { key: "token:a".to_owned(), ..Default::default() },
+///     StoreDelta { key: "pair:a:b".to_owned(), ..Default::default() },
+/// ];
+///
+/// let keys: Vec<&str> = filter_by_prefix(&deltas, "token:").map(|d| d.key.as_str()).collect();
+/// assert_eq!(keys, vec!["token:a"]);
+/// ```
+pub fn filter_by_prefix<'a>(
+    deltas: &'a Deltas,
+    prefix: &'a str,
+) -> impl Iterator<Item = &'a StoreDelta> + 'a {
+    deltas
+        .iter()
+        .filter(move |delta| delta.key.starts_with(prefix))
+}
+
+/// DeltaOp is the operation of a [StoreDelta], decoded from its `operation` field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeltaOp {
+    /// The key was written for the first time, the delta has no old value
+    Create,
+    /// The key was written again, the delta has both an old and a new value
+    Update,
+    /// The key was deleted, the delta has no new value
+    Delete,
+    /// The operation is unset, or unknown to this version of the crate
+    Unset,
+}
+
+/// Returns the operation of a delta as reported by the host, rather than guessed from
+/// the emptiness of its values: an update can have an empty old or new value.
+pub fn classify(delta: &StoreDelta) -> DeltaOp {
+    match Operation::from_i32(delta.operation) {
+        Some(Operation::Create) => DeltaOp::Create,
+        Some(Operation::Update) => DeltaOp::Update,
+        Some(Operation::Delete) => DeltaOp::Delete,
+        Some(Operation::Unset) | None => DeltaOp::Unset,
+    }
+}
+
+/// Returns the deltas with the operation `op`, in their original order.
+///
+/// # Examples
+///
+/// ```
+/// use substreams::deltas::{only, DeltaOp};
+/// use substreams::pb::substreams::store_delta::Operation;
+/// use substreams::pb::substreams::StoreDelta;
+///
+/// let deltas = vec![
+///     StoreDelta { key: "a".to_owned(), operation: Operation::Create as i32, ..Default::default() },
+///     StoreDelta { key: "b".to_owned(), operation: Operation::Delete as i32, ..Default::default() },
+/// ];
+///
+/// let deleted: Vec<&str> = only(&deltas, DeltaOp::Delete).map(|d| d.key.as_str()).collect();
+/// assert_eq!(deleted, vec!["b"]);
+/// ```
+pub fn only<'a>(deltas: &'a Deltas, op: DeltaOp) -> impl Iterator<Item = &'a StoreDelta> + 'a {
+    deltas.iter().filter(move |delta| classify(delta) == op)
+}
+
+/// DeltasExt adds helper methods to [store::Deltas](crate::store::Deltas).
+pub trait DeltasExt {
+    /// Returns the deltas whose key starts with `prefix`, in their original order,
+    /// with their values decoded as `T`. Returned keys are the full keys, the prefix
+    /// is retained.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a value of a matching delta can't be decoded as a `T`.
+    fn decoded_with_prefix<T: Default + prost::Message>(
+        &self,
+        prefix: &str,
+    ) -> Vec<(String, DeltaDecoded<T>)>;
+
+    /// Sorts the deltas by key, then by ordinal within each key. The sort is stable,
+    /// deltas with the same key and ordinal keep their original relative order, so
+    /// the result is the same on every platform.
+    fn sort_by_key_then_ordinal(&mut self);
+}
+
+impl DeltasExt for Vec<StoreDelta> {
+    fn decoded_with_prefix<T: Default + prost::Message>(
+        &self,
+        prefix: &str,
+    ) -> Vec<(String, DeltaDecoded<T>)> {
+        filter_by_prefix(self, prefix)
+            .map(|delta| (delta.key.clone(), DeltaDecoded::decode(delta)))
+            .collect()
+    }
+
+    fn sort_by_key_then_ordinal(&mut self) {
+        self.sort_by(|a, b| a.key.cmp(&b.key).then(a.ordinal.cmp(&b.ordinal)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::deltas::{
+        as_bigdecimal_delta, as_bigint_delta, classify, decode_deltas, filter_by_prefix,
+        map_new_values, map_old_values, only, DeltaDecoded, DeltaOp, DeltasExt, Untyped,
+    };
+    use crate::pb::substreams::store_delta::Operation;
+    use crate::pb::substreams::StoreDelta;
+    use crate::proto;
+    use crate::store::Deltas;
+    use bigdecimal::BigDecimal;
+    use num_bigint::BigInt;
+    use std::str::FromStr;
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    struct Token {
+        #[prost(string, tag = "1")]
+        symbol: String,
+    }
+
+    fn token(symbol: &str) -> Token {
+        Token {
+            symbol: symbol.to_owned(),
+        }
+    }
+
+    fn delta(
+        operation: Operation,
+        ordinal: u64,
+        key: &str,
+        old: Vec<u8>,
+        new: Vec<u8>,
+    ) -> StoreDelta {
+        StoreDelta {
+            operation: operation as i32,
+            ordinal,
+            key: key.to_owned(),
+            old_value: old,
+            new_value: new,
+        }
+    }
+
+    #[test]
+    fn it_decodes_deltas_with_prefix() {
+        let encode = |symbol: &str| proto::encode(&token(symbol)).unwrap();
+        let deltas: Deltas = vec![
+            delta(Operation::Create, 1, "token:a", vec![], encode("AAA")),
+            delta(Operation::Create, 2, "pair:a:b", vec![], vec![1, 2, 3]),
+            delta(
+                Operation::Update,
+                3,
+                "token:a",
+                encode("AAA"),
+                encode("AAB"),
+            ),
+            delta(Operation::Delete, 4, "token:b", encode("BBB"), vec![]),
+        ];
+
+        assert_eq!(
+            deltas.decoded_with_prefix::<Token>("token:"),
+            vec![
+                (
+                    "token:a".to_owned(),
+                    DeltaDecoded {
+                        operation: Operation::Create,
+                        ordinal: 1,
+                        old_value: None,
+                        new_value: Some(token("AAA")),
+                    }
+                ),
+                (
+                    "token:a".to_owned(),
+                    DeltaDecoded {
+                        operation: Operation::Update,
+                        ordinal: 3,
+                        old_value: Some(token("AAA")),
+                        new_value: Some(token("AAB")),
+                    }
+                ),
+                (
+                    "token:b".to_owned(),
+                    DeltaDecoded {
+                        operation: Operation::Delete,
+                        ordinal: 4,
+                        old_value: Some(token("BBB")),
+                        new_value: None,
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_decodes_typed_deltas() {
+        let encode = |symbol: &str| proto::encode(&token(symbol)).unwrap();
+        let raw: Deltas = vec![
+            delta(Operation::Create, 1, "token:a", vec![], encode("AAA")),
+            delta(Operation::Delete, 2, "token:b", encode("BBB"), vec![]),
+        ];
+
+        let decoded: Deltas<Token> = decode_deltas::<Token>(raw.clone());
+        assert_eq!(
+            decoded,
+            vec![
+                (
+                    "token:a".to_owned(),
+                    DeltaDecoded {
+                        operation: Operation::Create,
+                        ordinal: 1,
+                        old_value: None,
+                        new_value: Some(token("AAA")),
+                    }
+                ),
+                (
+                    "token:b".to_owned(),
+                    DeltaDecoded {
+                        operation: Operation::Delete,
+                        ordinal: 2,
+                        old_value: Some(token("BBB")),
+                        new_value: None,
+                    }
+                ),
+            ]
+        );
+
+        let untyped: Deltas = decode_deltas::<Untyped>(raw.clone());
+        assert_eq!(untyped, raw);
+    }
+
+    #[test]
+    fn it_maps_values_skipping_missing_ones() {
+        let encode = |symbol: &str| proto::encode(&token(symbol)).unwrap();
+        let deltas: Deltas = vec![
+            delta(Operation::Create, 1, "token:a", vec![], encode("AAA")),
+            delta(Operation::Delete, 2, "token:b", encode("BBB"), vec![]),
+            delta(
+                Operation::Update,
+                3,
+                "token:a",
+                encode("AAA"),
+                encode("AAB"),
+            ),
+            delta(Operation::Update, 4, "token:c", encode("CCC"), vec![]),
+            delta(Operation::Create, 5, "token:d", vec![], encode("DDD")),
+        ];
+        let symbol = |delta: &StoreDelta, token: Token| (delta.ordinal, token.symbol);
+
+        assert_eq!(
+            map_new_values(&deltas, symbol),
+            vec![
+                (1, "AAA".to_owned()),
+                (3, "AAB".to_owned()),
+                (4, "".to_owned()),
+                (5, "DDD".to_owned()),
+            ]
+        );
+        assert_eq!(
+            map_old_values(&deltas, symbol),
+            vec![
+                (2, "BBB".to_owned()),
+                (3, "AAA".to_owned()),
+                (4, "CCC".to_owned()),
+            ]
+        );
+        assert!(map_new_values(&vec![], symbol).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "value of key token:a can't be decoded")]
+    fn it_rejects_undecodable_new_value() {
+        let deltas: Deltas = vec![delta(Operation::Create, 1, "token:a", vec![], vec![0xff])];
+        map_new_values(&deltas, |_, token: Token| token);
+    }
+
+    #[test]
+    fn it_parses_bigint_deltas() {
+        let created = delta(Operation::Create, 1, "a", vec![], b"10".to_vec());
+        assert_eq!(as_bigint_delta(&created), (None, Some(BigInt::from(10))));
+
+        let updated = delta(
+            Operation::Update,
+            2,
+            "a",
+            b"10".to_vec(),
+            b"-123456789012345678901234567890".to_vec(),
+        );
+        assert_eq!(
+            as_bigint_delta(&updated),
+            (
+                Some(BigInt::from(10)),
+                Some(BigInt::parse_bytes(b"-123456789012345678901234567890", 10).unwrap())
+            )
+        );
+
+        let deleted = delta(Operation::Delete, 3, "a", b"7".to_vec(), vec![]);
+        assert_eq!(as_bigint_delta(&deleted), (Some(BigInt::from(7)), None));
+    }
+
+    #[test]
+    #[should_panic(expected = "value of key a is not a valid bigint")]
+    fn it_rejects_malformed_bigint_delta() {
+        as_bigint_delta(&delta(Operation::Create, 1, "a", vec![], b"1.5".to_vec()));
+    }
+
+    #[test]
+    fn it_parses_bigdecimal_deltas() {
+        let decimal = |text: &str| Some(BigDecimal::from_str(text).unwrap());
+
+        let created = delta(Operation::Create, 1, "a", vec![], b"1.5".to_vec());
+        assert_eq!(as_bigdecimal_delta(&created), (None, decimal("1.5")));
+
+        let updated = delta(Operation::Update, 2, "a", b"1.5".to_vec(), b"-2e3".to_vec());
+        assert_eq!(
+            as_bigdecimal_delta(&updated),
+            (decimal("1.5"), decimal("-2000"))
+        );
+
+        let deleted = delta(Operation::Delete, 3, "a", b"0.25".to_vec(), vec![]);
+        assert_eq!(as_bigdecimal_delta(&deleted), (decimal("0.25"), None));
+    }
+
+    #[test]
+    fn it_filters_by_prefix() {
+        let deltas: Deltas = vec![
+            delta(Operation::Create, 1, "token:a", vec![], vec![1]),
+            delta(Operation::Create, 2, "pair:a:b", vec![], vec![2]),
+            delta(Operation::Update, 3, "token:b", vec![1], vec![3]),
+        ];
+        let keys = |prefix: &str| -> Vec<String> {
+            filter_by_prefix(&deltas, prefix)
+                .map(|d| d.key.clone())
+                .collect()
+        };
+
+        assert_eq!(keys("token:"), vec!["token:a", "token:b"]);
+        assert_eq!(keys("pair:a"), vec!["pair:a:b"]);
+        assert_eq!(keys(""), vec!["token:a", "pair:a:b", "token:b"]);
+        assert!(keys("token:a:longer-than-any-key").is_empty());
+        assert_eq!(filter_by_prefix(&vec![], "token:").count(), 0);
+    }
+
+    #[test]
+    fn it_classifies_deltas() {
+        let create = delta(Operation::Create, 1, "a", vec![], vec![1]);
+        let update = delta(Operation::Update, 2, "a", vec![1], vec![]);
+        let delete = delta(Operation::Delete, 3, "a", vec![], vec![]);
+        let unset = delta(Operation::Unset, 4, "a", vec![], vec![1]);
+        let mut unknown = delta(Operation::Create, 5, "a", vec![], vec![1]);
+        unknown.operation = 42;
+
+        assert_eq!(classify(&create), DeltaOp::Create);
+        assert_eq!(classify(&update), DeltaOp::Update);
+        assert_eq!(classify(&delete), DeltaOp::Delete);
+        assert_eq!(classify(&unset), DeltaOp::Unset);
+        assert_eq!(classify(&unknown), DeltaOp::Unset);
+    }
+
+    #[test]
+    fn it_filters_by_operation() {
+        let deltas: Deltas = vec![
+            delta(Operation::Create, 1, "a", vec![], vec![1]),
+            delta(Operation::Update, 2, "a", vec![1], vec![2]),
+            delta(Operation::Create, 3, "b", vec![], vec![1]),
+            delta(Operation::Delete, 4, "a", vec![2], vec![]),
+        ];
+        let ordinals = |op: DeltaOp| -> Vec<u64> { only(&deltas, op).map(|d| d.ordinal).collect() };
+
+        assert_eq!(ordinals(DeltaOp::Create), vec![1, 3]);
+        assert_eq!(ordinals(DeltaOp::Update), vec![2]);
+        assert_eq!(ordinals(DeltaOp::Delete), vec![4]);
+        assert!(ordinals(DeltaOp::Unset).is_empty());
+    }
+
+    #[test]
+    fn it_sorts_by_key_then_ordinal() {
+        let mut deltas: Deltas = vec![
+            delta(Operation::Create, 3, "b", vec![], vec![1]),
+            delta(Operation::Create, 1, "a", vec![], vec![1]),
+            delta(Operation::Update, 5, "b", vec![1], vec![2]),
+            delta(Operation::Update, 2, "a", vec![1], vec![2]),
+            delta(Operation::Update, 2, "a", vec![2], vec![3]),
+            delta(Operation::Create, 4, "c", vec![], vec![1]),
+            delta(Operation::Update, 0, "b", vec![], vec![0]),
+        ];
+
+        deltas.sort_by_key_then_ordinal();
+
+        let order: Vec<(&str, u64, Vec<u8>)> = deltas
+            .iter()
+            .map(|d| (d.key.as_str(), d.ordinal, d.new_value.clone()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("a", 1, vec![1]),
+                ("a", 2, vec![2]),
+                ("a", 2, vec![3]),
+                ("b", 0, vec![0]),
+                ("b", 3, vec![1]),
+                ("b", 5, vec![2]),
+                ("c", 4, vec![1]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,84 @@
+use crate::log;
+use std::fmt::Debug;
+
+/// Calls `f` on each of `items` and returns the values of the successful calls in order,
+/// a failing item is logged with its index and skipped instead of failing the whole block.
+///
+/// An item fails when `f` returns an error. A panic is not recovered: Substreams modules
+/// are built for `wasm32-unknown-unknown` which aborts on panic, so a panic in `f` still
+/// aborts the whole handler. Return errors rather than panic, for example with
+/// [crate::require], for items expected to be malformed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use substreams::errors::Error;
+///
+/// let raw_values = vec!["1", "2", "not a number", "4"];
+/// let values: Vec<u64> = substreams::try_each(raw_values, |raw| {
+///     raw.parse::<u64>().map_err(|e| Error::Unexpected(e.to_string()))
+/// });
+///
+/// assert_eq!(values, vec![1, 2, 4]);
+/// ```
+pub fn try_each<I, T, R, E, F>(items: I, f: F) -> Vec<R>
+where
+    I: IntoIterator<Item = T>,
+    E: Debug,
+    F: FnMut(T) -> Result<R, E>,
+{
+    try_each_with(items, f, |index, failure| {
+        log::info!("item {} failed, skipping it: {}", index, failure)
+    })
+}
+
+fn try_each_with<I, T, R, E, F, L>(items: I, mut f: F, mut on_failure: L) -> Vec<R>
+where
+    I: IntoIterator<Item = T>,
+    E: Debug,
+    F: FnMut(T) -> Result<R, E>,
+    L: FnMut(usize, String),
+{
+    let mut values = Vec::new();
+    for (index, item) in items.into_iter().enumerate() {
+        match f(item) {
+            Ok(value) => values.push(value),
+            Err(err) => on_failure(index, format!("{:?}", err)),
+        }
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::each::try_each_with;
+    use crate::errors::Error;
+
+    fn parse(raw: &str) -> Result<u64, Error> {
+        raw.parse::<u64>()
+            .map_err(|e| Error::Unexpected(e.to_string()))
+    }
+
+    #[test]
+    fn it_skips_failing_item() {
+        let mut failures = vec![];
+        let values = try_each_with(vec!["1", "2", "x"], parse, |index, failure| {
+            failures.push((index, failure))
+        });
+
+        assert_eq!(values, vec![1, 2]);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, 2);
+        assert!(failures[0].1.starts_with("Unexpected("));
+    }
+
+    #[test]
+    fn it_keeps_all_items() {
+        let values = try_each_with(vec!["1", "2"], parse, |_, failure| {
+            panic!("unexpected failure {}", failure)
+        });
+
+        assert_eq!(values, vec![1, 2]);
+    }
+}
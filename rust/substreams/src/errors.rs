@@ -4,10 +4,175 @@
 //! return in your Substreams handler
 //!
 
+use std::fmt::Display;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("unexpected error: `{0}`")]
     Unexpected(String),
+
+    #[error("{0}")]
+    Custom(String),
+
+    #[error("decode error: {0}")]
+    Decode(String),
+
+    #[error("parse error: {0}")]
+    Parse(String),
+
+    #[error("{context}: {source}")]
+    Context { context: String, source: Box<Error> },
+}
+
+impl Error {
+    /// Wraps the error with some context, like the transaction hash or the key
+    /// being processed, the context is prepended to the error message when formatted.
+    /// Context can be attached multiple times, the outermost context comes first.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use substreams::errors::Error;
+    ///
+    /// let err = Error::Unexpected("invalid topic".to_string()).context("trx 0xabcd");
+    /// assert_eq!(err.to_string(), "trx 0xabcd: unexpected error: `invalid topic`");
+    /// ```
+    pub fn context<C: Display>(self, context: C) -> Error {
+        Error::Context {
+            context: context.to_string(),
+            source: Box::new(self),
+        }
+    }
+}
+
+// Common standard library errors convert into `Error::Custom` with their message, so `?`
+// can be used on them directly in handlers.
+macro_rules! impl_from_std_error {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Error {
+                fn from(err: $t) -> Self {
+                    Error::Custom(err.to_string())
+                }
+            }
+        )*
+    };
+}
+
+impl_from_std_error!(
+    std::str::Utf8Error,
+    std::string::FromUtf8Error,
+    std::num::ParseIntError,
+    std::num::ParseFloatError,
+    std::num::TryFromIntError,
+    std::char::CharTryFromError,
+    std::fmt::Error
+);
+
+// Protobuf decoding failures convert into `Error::Decode` and number parsing failures into
+// `Error::Parse`, so decoding a message or a big number can use `?` in handlers.
+impl From<prost::DecodeError> for Error {
+    fn from(err: prost::DecodeError) -> Self {
+        Error::Decode(err.to_string())
+    }
+}
+
+impl From<num_bigint::ParseBigIntError> for Error {
+    fn from(err: num_bigint::ParseBigIntError) -> Self {
+        Error::Parse(err.to_string())
+    }
+}
+
+impl From<bigdecimal::ParseBigDecimalError> for Error {
+    fn from(err: bigdecimal::ParseBigDecimalError) -> Self {
+        Error::Parse(err.to_string())
+    }
+}
+
+// A value too big for its integer is a conversion failure like `TryFromIntError`
+impl From<crate::bytes::OverflowError> for Error {
+    fn from(err: crate::bytes::OverflowError) -> Self {
+        Error::Custom(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::Error;
+    use crate::pb::substreams::Clock;
+    use num_bigint::BigInt;
+    use std::convert::TryFrom;
+    use std::str::FromStr;
+
+    #[test]
+    fn it_formats_context_chain() {
+        let err = Error::Unexpected("boom".to_owned())
+            .context("key total:01")
+            .context(format!("block #{}", 12));
+
+        assert_eq!(
+            err.to_string(),
+            "block #12: key total:01: unexpected error: `boom`"
+        );
+    }
+
+    #[test]
+    fn it_converts_std_errors() {
+        fn parse(bytes: &[u8]) -> Result<u8, Error> {
+            let value = std::str::from_utf8(bytes)?.parse::<i64>()?;
+            Ok(u8::try_from(value)?)
+        }
+
+        assert_eq!(parse(b"42").unwrap(), 42);
+        assert!(matches!(
+            parse(&[0xff]),
+            Err(Error::Custom(msg)) if msg == std::str::from_utf8(&[0xff]).unwrap_err().to_string()
+        ));
+        assert!(
+            matches!(parse(b"x"), Err(Error::Custom(msg)) if msg == "invalid digit found in string")
+        );
+        assert!(matches!(parse(b"300"), Err(Error::Custom(_))));
+    }
+
+    #[test]
+    fn it_converts_decode_errors() {
+        fn handler(clock_raw: Vec<u8>) -> Result<u64, Error> {
+            let clock: Clock = crate::proto::decode(&clock_raw)?;
+            Ok(clock.number)
+        }
+
+        assert_eq!(handler(vec![0x10, 0x2a]).unwrap(), 42);
+        assert!(matches!(
+            handler(vec![0x10]),
+            Err(Error::Decode(msg)) if msg.contains("failed to decode Protobuf message")
+        ));
+    }
+
+    #[test]
+    fn it_converts_parse_errors() {
+        fn parse(raw: &str) -> Result<BigInt, Error> {
+            Ok(BigInt::from_str(raw)?)
+        }
+
+        assert_eq!(parse("-42").unwrap(), BigInt::from(-42));
+        assert!(matches!(parse("4x2"), Err(Error::Parse(_))));
+        assert_eq!(
+            parse("").unwrap_err().to_string(),
+            "parse error: cannot parse integer from empty string"
+        );
+    }
+
+    #[test]
+    fn it_converts_overflow_errors() {
+        fn token_id(topic: &[u8]) -> Result<u64, Error> {
+            Ok(crate::bytes_to_u64_be(topic)?)
+        }
+
+        assert_eq!(token_id(&[0, 0, 7]).unwrap(), 7);
+        assert!(matches!(
+            token_id(&[1; 9]),
+            Err(Error::Custom(msg)) if msg == "value of 9 significant bytes overflows a 64 bits integer"
+        ));
+    }
 }
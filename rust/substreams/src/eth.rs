@@ -0,0 +1,484 @@
+//! Ethereum helpers for Substreams.
+//!
+//! This module offers the [Address] and [Hash] types, thin wrappers over the raw bytes
+//! found in Ethereum blocks, to compare and print them without slicing byte vectors by
+//! hand. The Ethereum models are generated within each module, the [Block] and
+//! [Transaction] traits are implemented by the module for them to use the block helpers.
+//!
+//! # Examples
+//!
+//! ```
+//! use substreams::eth::{Address, Hash};
+//! use substreams::hex;
+//!
+//! const TRACKED_CONTRACT: Address = Address(hex!("bc4ca0eda7647a8ab7c2061c2e118a18a936f13d"));
+//!
+//! let log_address = hex!("bc4ca0eda7647a8ab7c2061c2e118a18a936f13d").to_vec();
+//! assert!(TRACKED_CONTRACT == log_address);
+//!
+//! let topic = Hash::from_slice(&hex!(
+//!     "000000000000000000000000bc4ca0eda7647a8ab7c2061c2e118a18a936f13d"
+//! ))
+//! .unwrap();
+//! assert_eq!(Address::from_topic(&topic), TRACKED_CONTRACT);
+//! ```
+
+use crate::hex::{Hex, HexError};
+use std::fmt;
+
+macro_rules! fixed_bytes {
+    ($name:ident, $len:expr) => {
+        impl $name {
+            /// Returns the value of the bytes of `bytes`, or `None` if it's not exactly
+            #[doc = concat!(stringify!($len), " bytes long.")]
+            pub fn from_slice(bytes: &[u8]) -> Option<$name> {
+                if bytes.len() != $len {
+                    return None;
+                }
+
+                let mut value = [0u8; $len];
+                value.copy_from_slice(bytes);
+                Some($name(value))
+            }
+
+            /// Decodes an hexadecimal string like [Hex::decode], with or without a `0x`
+            #[doc = concat!("prefix, which must decode to exactly ", stringify!($len), " bytes.")]
+            pub fn from_hex(s: &str) -> Result<$name, HexError> {
+                let mut value = [0u8; $len];
+                Hex::decode_into(s, &mut value)?;
+                Ok($name(value))
+            }
+
+            /// Returns the value as a byte slice
+            pub fn as_bytes(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.0
+            }
+        }
+
+        impl PartialEq<[u8]> for $name {
+            fn eq(&self, other: &[u8]) -> bool {
+                self.0[..] == *other
+            }
+        }
+
+        impl PartialEq<Vec<u8>> for $name {
+            fn eq(&self, other: &Vec<u8>) -> bool {
+                self.0[..] == other[..]
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&Hex(&self.0), f)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}({})", stringify!($name), Hex(&self.0))
+            }
+        }
+    };
+}
+
+/// Address is a 20 bytes Ethereum address, displayed as lower case hexadecimal without
+/// `0x` prefix like [Hex].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Address(pub [u8; 20]);
+
+/// Hash is a 32 bytes Ethereum hash, like a transaction hash or a log topic, displayed as
+/// lower case hexadecimal without `0x` prefix like [Hex].
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Hash(pub [u8; 32]);
+
+fixed_bytes!(Address, 20);
+fixed_bytes!(Hash, 32);
+
+impl Address {
+    /// Returns the address held by a log topic, an indexed `address` event parameter is
+    /// left-padded with 12 zero bytes to fill the 32 bytes of the topic. The padding is
+    /// dropped without being checked.
+    pub fn from_topic(topic: &Hash) -> Address {
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&topic.0[12..]);
+        Address(address)
+    }
+}
+
+/// LogTopics reads the topics of a log, the `topics` field of the generated `Log` models,
+/// returning `None` for a topic the log doesn't have so a malformed log can't make the
+/// handler panic.
+///
+/// # Examples
+///
+/// ```
+/// use substreams::eth::{Address, LogTopics};
+///
+/// let mut padded = vec![0u8; 12];
+/// padded.extend_from_slice(&[0xbc; 20]);
+/// let topics = vec![vec![0xdd; 32], padded];
+///
+/// assert_eq!(topics.topic(0), Some(&[0xdd; 32][..]));
+/// assert_eq!(topics.topic_as_address(1), Some(Address([0xbc; 20])));
+/// assert_eq!(topics.topic(2), None);
+/// ```
+pub trait LogTopics {
+    /// Returns the topic at index `i`, or `None` if the log has fewer topics
+    fn topic(&self, i: usize) -> Option<&[u8]>;
+
+    /// Returns the address held by the topic at index `i`, like [Address::from_topic], or
+    /// `None` if the log has fewer topics or the topic is not 32 bytes long.
+    fn topic_as_address(&self, i: usize) -> Option<Address>;
+}
+
+impl LogTopics for [Vec<u8>] {
+    fn topic(&self, i: usize) -> Option<&[u8]> {
+        self.get(i).map(Vec::as_slice)
+    }
+
+    fn topic_as_address(&self, i: usize) -> Option<Address> {
+        self.topic(i)
+            .and_then(Hash::from_slice)
+            .map(|topic| Address::from_topic(&topic))
+    }
+}
+
+/// Log is implemented by the module for the logs of its generated Ethereum models, like
+/// the `Log` of `sf.ethereum.type.v1`.
+pub trait Log {
+    /// Returns the index of the log within its transaction receipt
+    fn index(&self) -> u32;
+
+    /// Returns the index of the log within the block, across all transactions
+    fn block_index(&self) -> u32;
+}
+
+/// Transaction is implemented by the module for the transactions of its generated Ethereum
+/// models, like the `TransactionTrace` of `sf.ethereum.type.v1`.
+///
+/// # Examples
+///
+/// ```
+/// # mod eth {
+/// #     #[derive(Default)]
+/// #     pub struct TransactionTrace { pub hash: Vec<u8>, pub to: Vec<u8>, pub index: u32, pub receipt: Option<TransactionReceipt> }
+/// #     #[derive(Default)]
+/// #     pub struct TransactionReceipt { pub logs: Vec<Log> }
+/// #     pub struct Log { pub index: u32, pub block_index: u32 }
+/// # }
+/// # impl substreams::eth::Log for eth::Log {
+/// #     fn index(&self) -> u32 { self.index }
+/// #     fn block_index(&self) -> u32 { self.block_index }
+/// # }
+/// use substreams::eth::Transaction;
+///
+/// impl Transaction for eth::TransactionTrace {
+///     type Log = eth::Log;
+///
+///     fn hash(&self) -> &[u8] {
+///         &self.hash
+///     }
+///
+///     fn index(&self) -> u32 {
+///         self.index
+///     }
+///
+///     fn to(&self) -> &[u8] {
+///         &self.to
+///     }
+///
+///     fn logs(&self) -> &[eth::Log] {
+///         self.receipt.as_ref().map_or(&[], |receipt| &receipt.logs)
+///     }
+/// }
+///
+/// assert!(eth::TransactionTrace::default().is_contract_creation());
+/// ```
+pub trait Transaction {
+    type Log: Log;
+
+    /// Returns the hash of the transaction
+    fn hash(&self) -> &[u8];
+
+    /// Returns the index of the transaction within the block
+    fn index(&self) -> u32;
+
+    /// Returns the raw `to` field of the transaction, empty for a contract creation
+    fn to(&self) -> &[u8];
+
+    /// Returns the logs of the transaction receipt, empty for a transaction without receipt
+    fn logs(&self) -> &[Self::Log];
+
+    /// Returns the address the transaction is sent to, `None` for a contract creation
+    /// which has no `to` address.
+    fn to_address(&self) -> Option<&[u8]> {
+        if self.is_contract_creation() {
+            return None;
+        }
+
+        Some(self.to())
+    }
+
+    /// Returns `true` if the transaction creates a contract, it has an empty `to` address.
+    fn is_contract_creation(&self) -> bool {
+        self.to().is_empty()
+    }
+}
+
+/// Block is implemented by the module for the blocks of its generated Ethereum models,
+/// like the `Block` of `sf.ethereum.type.v1` whose transactions are its
+/// `transaction_traces`.
+pub trait Block {
+    type Transaction: Transaction;
+
+    /// Returns the transactions of the block, in block order
+    fn transactions(&self) -> &[Self::Transaction];
+
+    /// Returns all the logs of the block, collected from the receipt of each transaction,
+    /// in canonical order: sorted by block level log index, which is transaction order then
+    /// log order within the transaction. Logs sharing a block level index, like on blocks
+    /// that don't populate it, keep their transaction then receipt order. Transactions
+    /// without a receipt have no logs.
+    fn indexed_logs(&self) -> Vec<IndexedLog<'_, <Self::Transaction as Transaction>::Log>> {
+        let mut logs: Vec<IndexedLog<'_, _>> = self
+            .transactions()
+            .iter()
+            .flat_map(|trx| {
+                trx.logs().iter().map(move |log| IndexedLog {
+                    trx_hash: trx.hash(),
+                    trx_index: trx.index(),
+                    log_index: log.block_index(),
+                    log,
+                })
+            })
+            .collect();
+
+        logs.sort_by_key(|log| log.log_index);
+        logs
+    }
+}
+
+/// A log of a block along with its position in the block, see [Block::indexed_logs].
+#[derive(Debug)]
+pub struct IndexedLog<'a, L> {
+    pub trx_hash: &'a [u8],
+    /// Index of the transaction emitting the log within the block
+    pub trx_index: u32,
+    /// Index of the log within the block, across all transactions
+    pub log_index: u32,
+    pub log: &'a L,
+}
+
+/// Returns the transactions of the block sent to `address`, in block order. Contract
+/// creations are never returned, even for an empty `address`.
+pub fn transactions_to<'a, B: Block>(
+    block: &'a B,
+    address: &'a [u8],
+) -> impl Iterator<Item = &'a B::Transaction> {
+    block
+        .transactions()
+        .iter()
+        .filter(move |trx| trx.to_address() == Some(address))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eth::{transactions_to, Address, Block, Hash, Log, LogTopics, Transaction};
+    use crate::hex::HexError;
+    use hex_literal::hex;
+
+    const ADDRESS: [u8; 20] = hex!("bc4ca0eda7647a8ab7c2061c2e118a18a936f13d");
+
+    #[test]
+    fn it_extracts_address_from_topic() {
+        let topic = Hash(hex!(
+            "000000000000000000000000bc4ca0eda7647a8ab7c2061c2e118a18a936f13d"
+        ));
+
+        assert_eq!(Address::from_topic(&topic), Address(ADDRESS));
+        assert_eq!(Address::from_topic(&Hash::default()), Address::default());
+    }
+
+    #[test]
+    fn it_round_trips_hex() {
+        let address = Address::from_hex("0xBC4CA0EDA7647A8AB7C2061C2E118A18A936F13D").unwrap();
+        assert_eq!(address, Address(ADDRESS));
+        assert_eq!(
+            address.to_string(),
+            "bc4ca0eda7647a8ab7c2061c2e118a18a936f13d"
+        );
+        assert_eq!(Address::from_hex(&address.to_string()), Ok(address));
+        assert_eq!(
+            format!("{:?}", address),
+            "Address(bc4ca0eda7647a8ab7c2061c2e118a18a936f13d)"
+        );
+
+        let hash = Hash([0xab; 32]);
+        assert_eq!(Hash::from_hex(&hash.to_string()), Ok(hash));
+        assert_eq!(
+            Hash::from_hex("0xbc4c"),
+            Err(HexError::InvalidLength {
+                expected: 32,
+                actual: 2
+            })
+        );
+    }
+
+    #[test]
+    fn it_compares_with_slices() {
+        let address = Address::from_slice(&ADDRESS).unwrap();
+
+        assert!(address == ADDRESS.to_vec());
+        assert!(address == ADDRESS[..]);
+        assert!(address != vec![0u8; 20]);
+        assert!(address != ADDRESS[1..]);
+
+        assert_eq!(Address::from_slice(&ADDRESS[1..]), None);
+        assert_eq!(Hash::from_slice(&[0u8; 20]), None);
+    }
+
+    #[test]
+    fn it_reads_topics() {
+        let mut padded = vec![0u8; 12];
+        padded.extend_from_slice(&ADDRESS);
+
+        let none: Vec<Vec<u8>> = vec![];
+        assert_eq!(none.topic(0), None);
+        assert_eq!(none.topic_as_address(0), None);
+
+        let one = vec![vec![0xdd; 32]];
+        assert_eq!(one.topic(0), Some(&[0xdd; 32][..]));
+        assert_eq!(one.topic(1), None);
+        assert_eq!(one.topic_as_address(1), None);
+
+        // a log has at most 4 topics, the event signature then 3 indexed parameters
+        let four = vec![vec![0xdd; 32], padded.clone(), ADDRESS.to_vec(), padded];
+        assert_eq!(four.topic(0), Some(&[0xdd; 32][..]));
+        assert_eq!(four.topic_as_address(1), Some(Address(ADDRESS)));
+        assert_eq!(four.topic(2), Some(&ADDRESS[..]));
+        assert_eq!(four.topic_as_address(2), None);
+        assert_eq!(four.topic_as_address(3), Some(Address(ADDRESS)));
+        assert_eq!(four.topic(4), None);
+        assert_eq!(four.topic_as_address(4), None);
+    }
+
+    struct TestLog {
+        index: u32,
+        block_index: u32,
+    }
+
+    impl Log for TestLog {
+        fn index(&self) -> u32 {
+            self.index
+        }
+
+        fn block_index(&self) -> u32 {
+            self.block_index
+        }
+    }
+
+    #[derive(Default)]
+    struct TestTransaction {
+        hash: Vec<u8>,
+        index: u32,
+        to: Vec<u8>,
+        logs: Vec<TestLog>,
+    }
+
+    impl Transaction for TestTransaction {
+        type Log = TestLog;
+
+        fn hash(&self) -> &[u8] {
+            &self.hash
+        }
+
+        fn index(&self) -> u32 {
+            self.index
+        }
+
+        fn to(&self) -> &[u8] {
+            &self.to
+        }
+
+        fn logs(&self) -> &[TestLog] {
+            &self.logs
+        }
+    }
+
+    struct TestBlock(Vec<TestTransaction>);
+
+    impl Block for TestBlock {
+        type Transaction = TestTransaction;
+
+        fn transactions(&self) -> &[TestTransaction] {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn it_finds_transactions_to() {
+        let trx = |hash: u8, to: &[u8]| TestTransaction {
+            hash: vec![hash],
+            to: to.to_vec(),
+            ..Default::default()
+        };
+        let block = TestBlock(vec![
+            trx(1, &[]),
+            trx(2, &ADDRESS),
+            trx(3, &[0u8; 20]),
+            trx(4, &ADDRESS),
+        ]);
+        let hashes = |address: &[u8]| -> Vec<u8> {
+            transactions_to(&block, address)
+                .map(|trx| trx.hash[0])
+                .collect()
+        };
+
+        assert_eq!(hashes(&ADDRESS), vec![2, 4]);
+        assert_eq!(hashes(&[]), Vec::<u8>::new());
+        assert!(block.0[0].is_contract_creation());
+        assert_eq!(block.0[0].to_address(), None);
+        assert_eq!(block.0[1].to_address(), Some(&ADDRESS[..]));
+    }
+
+    #[test]
+    fn it_collects_logs_in_block_order() {
+        let trx = |hash: u8, index: u32, logs: Vec<(u32, u32)>| TestTransaction {
+            hash: vec![hash],
+            index,
+            logs: logs
+                .into_iter()
+                .map(|(index, block_index)| TestLog { index, block_index })
+                .collect(),
+            ..Default::default()
+        };
+        let block = TestBlock(vec![
+            trx(0xaa, 0, vec![(0, 0), (1, 1)]),
+            trx(0xbb, 1, vec![]),
+            trx(0xcc, 3, vec![(0, 3)]),
+            trx(0xdd, 2, vec![(0, 2), (1, 2)]),
+        ]);
+
+        let logs: Vec<(u8, u32, u32, u32)> = block
+            .indexed_logs()
+            .iter()
+            .map(|log| (log.trx_hash[0], log.trx_index, log.log_index, log.log.index))
+            .collect();
+        assert_eq!(
+            logs,
+            vec![
+                (0xaa, 0, 0, 0),
+                (0xaa, 0, 1, 1),
+                (0xdd, 2, 2, 0),
+                (0xdd, 2, 2, 1),
+                (0xcc, 3, 3, 0)
+            ]
+        );
+    }
+}
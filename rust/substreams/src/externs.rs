@@ -1,6 +1,8 @@
 #[link(wasm_import_module = "env")]
 extern "C" {
     pub fn output(ptr: *const u8, len: u32);
+    #[cfg(feature = "debug-output")]
+    pub fn output_debug(ptr: *const u8, len: u32);
     pub fn register_panic(
         msg_ptr: *const u8,
         msg_len: u32,
@@ -9,6 +11,14 @@ extern "C" {
         line: u32,
         column: u32,
     );
+    pub fn set_error(msg_ptr: *const u8, msg_len: u32);
+    pub fn start_block() -> u64;
+    pub fn params(output_ptr: u32);
+    pub fn input_present(arg_index: u32) -> u32;
+    #[cfg(feature = "progress")]
+    pub fn progress(fraction: f32, note_ptr: *const u8, note_len: u32);
+    #[cfg(feature = "timer")]
+    pub fn now_nanos() -> u64;
 }
 
 #[link(wasm_import_module = "logger")]
@@ -19,8 +29,33 @@ extern "C" {
 pub mod state {
     #[link(wasm_import_module = "state")]
     extern "C" {
+        pub fn store_bound(store_idx: u32) -> u32;
         pub fn get_first(store_idx: u32, key_ptr: *const u8, key_len: u32, output_ptr: u32) -> u32;
         pub fn get_last(store_idx: u32, key_ptr: *const u8, key_len: u32, output_ptr: u32) -> u32;
+        pub fn get_last_with_ord(
+            store_idx: u32,
+            key_ptr: *const u8,
+            key_len: u32,
+            output_ptr: u32,
+        ) -> i64;
+        pub fn get_last_len(store_idx: u32, key_ptr: *const u8, key_len: u32) -> i64;
+        pub fn get_last_range(
+            store_idx: u32,
+            key_ptr: *const u8,
+            key_len: u32,
+            offset: u64,
+            len: u64,
+            output_ptr: u32,
+        ) -> u32;
+        pub fn get_last_many(
+            store_idx: u32,
+            keys_ptr: *const u8,
+            keys_len: u32,
+            output_ptr: u32,
+        ) -> u32;
+        pub fn get_previous_block(key_ptr: *const u8, key_len: u32, output_ptr: u32) -> u32;
+        pub fn get_output_at(ord: i64, key_ptr: *const u8, key_len: u32, output_ptr: u32) -> u32;
+        pub fn get_output_len(ord: i64, key_ptr: *const u8, key_len: u32) -> i64;
         pub fn get_at(
             store_idx: u32,
             ord: i64,
@@ -28,6 +63,30 @@ pub mod state {
             key_len: u32,
             output_ptr: u32,
         ) -> u32;
+        pub fn get_at_relative(
+            store_idx: u32,
+            back: u64,
+            key_ptr: *const u8,
+            key_len: u32,
+            output_ptr: u32,
+        ) -> u32;
+        pub fn get_at_block(
+            store_idx: u32,
+            block_num: u64,
+            key_ptr: *const u8,
+            key_len: u32,
+            output_ptr: u32,
+        ) -> u32;
+        pub fn scan_prefix(
+            store_idx: u32,
+            ord: i64,
+            prefix_ptr: *const u8,
+            prefix_len: u32,
+            output_ptr: u32,
+        ) -> u32;
+        pub fn count_prefix(store_idx: u32, prefix_ptr: *const u8, prefix_len: u32) -> u64;
+        pub fn has_at(store_idx: u32, ord: i64, key_ptr: *const u8, key_len: u32) -> u32;
+        pub fn has_last(store_idx: u32, key_ptr: *const u8, key_len: u32) -> u32;
         pub fn set(
             ord: i64,
             key_ptr: *const u8,
@@ -42,6 +101,13 @@ pub mod state {
             value_ptr: *const u8,
             value_len: u32,
         );
+        pub fn set_if_not_exists_reporting(
+            ord: i64,
+            key_ptr: *const u8,
+            key_len: u32,
+            value_ptr: *const u8,
+            value_len: u32,
+        ) -> u32;
         pub fn append(
             ord: i64,
             key_ptr: *const u8,
@@ -49,7 +115,10 @@ pub mod state {
             value_ptr: *const u8,
             value_len: u32,
         );
+        pub fn set_batch(ops_ptr: *const u8, ops_len: u32);
+        pub fn delete(ord: i64, key_ptr: *const u8, key_len: u32);
         pub fn delete_prefix(ord: i64, prefix_ptr: *const u8, prefix_len: u32);
+        pub fn clear(ord: i64);
         pub fn add_bigint(
             ord: i64,
             key_ptr: *const u8,
@@ -57,12 +126,31 @@ pub mod state {
             value_ptr: *const u8,
             value_len: u32,
         );
+        pub fn add_bigint_returning(
+            ord: i64,
+            key_ptr: *const u8,
+            key_len: u32,
+            value_ptr: *const u8,
+            value_len: u32,
+            output_ptr: u32,
+        );
+        pub fn add_bigint_batch(ops_ptr: *const u8, ops_len: u32);
         pub fn add_int64(
             ord: i64,
             key_ptr: *const u8,
             key_len: u32,
             value: i64,
         );
+        pub fn add_int64_batch(ops_ptr: *const u8, ops_len: u32);
+        pub fn set_sum_int64(ord: i64, key_ptr: *const u8, key_len: u32, value: i64, sum: u32);
+        pub fn set_sum_bigint(
+            ord: i64,
+            key_ptr: *const u8,
+            key_len: u32,
+            value_ptr: *const u8,
+            value_len: u32,
+            sum: u32,
+        );
         pub fn add_float64(
             ord: i64,
             key_ptr: *const u8,
@@ -76,6 +164,14 @@ pub mod state {
             value_ptr: *const u8,
             value_len: u32,
         );
+        pub fn add_bigfloat_scaled(
+            ord: i64,
+            key_ptr: *const u8,
+            key_len: u32,
+            value_ptr: *const u8,
+            value_len: u32,
+            scale: u32,
+        );
         pub fn set_min_int64(
             ord: i64,
             key_ptr: *const u8,
@@ -0,0 +1,67 @@
+//! Guard helpers for Substreams.
+//!
+//! This crate offers macros to validate the inputs of a handler, returning
+//! a clean [Error](crate::errors::Error) instead of panicking.
+//!
+
+/// Returns early with an [Error::Custom](crate::errors::Error::Custom) if the condition is
+/// not met, usable in any function returning a `Result<_, substreams::errors::Error>`.
+///
+/// The message is formatted exactly like [std::format!], it's only built when the
+/// condition fails.
+///
+/// # Examples
+///
+/// ```
+/// use substreams::errors::Error;
+///
+/// fn token_id(topics: &Vec<Vec<u8>>) -> Result<Vec<u8>, Error> {
+///     substreams::require!(topics.len() == 4, "bad topic count {}", topics.len());
+///
+///     Ok(topics[3].clone())
+/// }
+///
+/// assert_eq!(token_id(&vec![vec![]; 4]).unwrap(), Vec::<u8>::new());
+/// assert_eq!(token_id(&vec![]).unwrap_err().to_string(), "bad topic count 0");
+/// ```
+#[doc(hidden)]
+#[macro_export]
+macro_rules! guard_require {
+    ($cond:expr, $($arg:tt)+) => {
+        if !$cond {
+            return Err($crate::errors::Error::Custom(format!($($arg)+)));
+        }
+    };
+}
+
+pub use guard_require as require;
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::Error;
+    use crate::guard::require;
+
+    fn check(value: u64) -> Result<u64, Error> {
+        require!(value > 0, "value must be positive");
+        require!(value % 2 == 0, "value {} is odd", value);
+
+        Ok(value / 2)
+    }
+
+    #[test]
+    fn it_passes_when_conditions_hold() {
+        assert_eq!(check(4).unwrap(), 2);
+    }
+
+    #[test]
+    fn it_fails_with_message() {
+        match check(0) {
+            Err(Error::Custom(msg)) => assert_eq!(msg, "value must be positive"),
+            other => panic!("expected error, got {:?}", other),
+        }
+        match check(3) {
+            Err(Error::Custom(msg)) => assert_eq!(msg, "value 3 is odd"),
+            other => panic!("expected error, got {:?}", other),
+        }
+    }
+}
@@ -43,16 +43,309 @@
 ///             unimplemented!("do something");
 ///         }
 ///     };
-///     let result = func();
-///     if result.is_err() {
-///         panic!(result.err().unwrap())
+///     match func() {
+///         Ok(output) => substreams::output(output),
+///         Err(err) => substreams::set_error(err),
 ///     }
-///     substreams::output(substreams::proto::encode(&result.unwrap()).unwrap());
+/// }
+/// ```
+///
+/// A returned `Err` is reported to the host as the error of the module, which then
+/// returns without output, instead of panicking: a panic is left for unexpected states.
+///
+/// The `Ok` type of the returned `Result` must implement `prost::Message`, this is
+/// checked on the handler signature:
+///
+/// ```ignore
+/// struct NotAMessage;
+///
+/// #[substreams::handlers::map]
+/// fn map_handler() -> Result<NotAMessage, substreams::errors::Error> {
+///     Ok(NotAMessage)
+/// }
+/// ```
+///
+/// Each input must be a protobuf message, a store or raw bytes, other types are reported
+/// on the argument:
+///
+/// ```ignore
+/// # mod proto { pub type Custom = (); }
+/// #[substreams::handlers::map]
+/// fn map_handler(pair: (u64, u64)) -> Result<proto::Custom, substreams::errors::Error> {
+///     unimplemented!("do something");
+/// }
+/// ```
+///
+/// Handlers are exported to the host as `extern "C"` functions, they can't have generic
+/// parameters, lifetimes or where clauses:
+///
+/// ```ignore
+/// # mod eth { pub type Block = (); }
+/// #[substreams::handlers::map]
+/// fn map_handler<T: prost::Message>(blk: eth::Block) -> Result<T, substreams::errors::Error> {
+///     unimplemented!("do something");
+/// }
+/// ```
+///
+/// ## Raw inputs
+///
+/// An argument of type `&[u8]` receives the raw bytes of an input instead of its
+/// decoded value. When named `<input>_raw` and declared after a decoded `<input>`
+/// argument, it is a view over the same bytes as `<input>` and does not consume a
+/// host input of its own. The slice is only valid while the handler runs.
+///
+/// ```rust
+/// # mod eth { pub type Block = (); }
+/// # mod proto { pub type Custom = (); }
+///
+/// #[substreams::handlers::map]
+/// fn map_handler(blk: eth::Block, blk_raw: &[u8]) -> Result<proto::Custom, substreams::errors::Error> {
+///     substreams::log::info!("block is {} bytes", blk_raw.len());
+///     unimplemented!("do something");
+/// }
+/// ```
+///
+/// ## Clock input
+///
+/// An argument of type `substreams::pb::substreams::Clock` receives the clock of the
+/// block, its number, id and timestamp, from its own input, so a module only needing
+/// the timing of the block doesn't take and decode the whole block. The clock is
+/// recognized by its full path, so write the type in full rather than importing it.
+///
+/// ```rust
+/// # mod proto { pub type Custom = (); }
+///
+/// #[substreams::handlers::map]
+/// fn map_handler(clock: substreams::pb::substreams::Clock) -> Result<proto::Custom, substreams::errors::Error> {
+///     substreams::log::info!("block #{}", clock.number);
+///     unimplemented!("do something");
+/// }
+/// ```
+///
+/// ## Params input
+///
+/// A first argument named `params` receives the parameters given to the module by the
+/// host, see [crate::module::params], instead of one of its inputs, so one compiled
+/// module can track a different contract for each use. A `params: String` is read as
+/// UTF-8 text, any other type is decoded as a protobuf message. Both are empty when the
+/// module has no parameters. The following arguments are the inputs of the module, in
+/// order.
+///
+/// The manifest and the module definitions sent to the server have no way to declare
+/// parameters yet, so a module run by the pipeline always gets empty ones, only a host
+/// embedding the runtime directly can set them.
+///
+/// ```rust
+/// # mod eth { pub type Block = (); }
+/// # mod proto { pub type Custom = (); }
+///
+/// #[substreams::handlers::map]
+/// fn map_handler(params: String, blk: eth::Block) -> Result<proto::Custom, substreams::errors::Error> {
+///     let tracked_contract = substreams::eth::Address::from_hex(&params).unwrap();
+///     unimplemented!("do something");
+/// }
+/// ```
+///
+/// ## Optional inputs
+///
+/// An argument of type `Option<T>` is `None` when the host has no data at all for the
+/// input on the current block, and `Some` otherwise, including for a present message
+/// with all its fields at their default value, which is encoded as zero bytes. The
+/// presence is asked to the host rather than inferred from the length of the input, so
+/// an empty message is never mistaken for an absent one. Note that the host does not
+/// call a handler at all when none of its inputs has any data.
+///
+/// ```rust
+/// # mod eth { pub type Block = (); }
+/// # mod proto { pub type Custom = (); }
+///
+/// #[substreams::handlers::map]
+/// fn map_handler(blk: eth::Block, prices: Option<proto::Custom>) -> Result<proto::Custom, substreams::errors::Error> {
+///     unimplemented!("do something");
+/// }
+/// ```
+///
+/// ## Optional output
+///
+/// A handler can return an `Option` instead of a `Result`, when it returns `None` nothing
+/// is output for the block, not even an empty message, so downstream modules have no
+/// data to process from it. An `Option` handler can't fail, panic on unrecoverable
+/// errors.
+///
+/// ```rust
+/// # mod eth { pub type Block = (); }
+/// # mod proto { pub type Custom = (); }
+///
+/// #[substreams::handlers::map]
+/// fn map_handler(blk: eth::Block) -> Option<proto::Custom> {
+///     unimplemented!("do something");
+/// }
+/// ```
+///
+/// ## Writable store
+///
+/// A `map` handler can also take one writable store argument, like a `store` handler,
+/// to both write to its store and emit an output. The store is constructed before the
+/// handler body runs, so all the writes of the body happen before the returned value is
+/// emitted. The host must provide an output store to the module for its writes to be
+/// accepted.
+///
+/// ```rust
+/// use substreams::store;
+/// # mod eth { pub type Block = (); }
+/// # mod proto { pub type Custom = (); }
+///
+/// #[substreams::handlers::map]
+/// fn map_handler(blk: eth::Block, s: store::StoreSet) -> Result<proto::Custom, substreams::errors::Error> {
+///     s.set(1, "last_block", &b"done".to_vec());
+///     unimplemented!("do something");
+/// }
+/// ```
+///
+/// ## Attributes
+///
+/// * `panic_hook = false`: Do not register the Substreams panic hook with
+///   [crate::register_panic_hook] at the start of the handler. Use this only if you install
+///   your own hook, without one the panic message and location are not reported
+///   to the host, which only sees that the module crashed.
+/// * `timed`: Measure the time spent in the handler and log it with [crate::log::debug!]
+///   once it returns, as `<handler> took <duration>`, to find the slow modules of a
+///   pipeline. The time is read from the host with the `env.now_nanos` binding, used only
+///   when the `timer` feature is enabled, otherwise the attribute is a no-op.
+///
+/// ## Module name
+///
+/// The generated handler declares a `SUBSTREAMS_MODULE_NAME` constant holding the name of
+/// the handler function, the name of the module in the Manifest, which the body can use in
+/// its messages. The name is also returned by [crate::current_module] while the handler
+/// runs: it's added to the panic log line, and to every log line once enabled with
+/// [crate::log::prefix_module].
+///
+/// ```rust
+/// # mod proto { pub type Custom = (); }
+/// #[substreams::handlers::map]
+/// fn map_transfers(data: proto::Custom) -> Result<proto::Custom, substreams::errors::Error> {
+///     substreams::log::info!("{} started", SUBSTREAMS_MODULE_NAME);
+///     unimplemented!("do something");
 /// }
 /// ```
 pub use substreams_macro::map;
 
+#[doc(hidden)]
+pub fn assert_output_message<M: prost::Message>() {}
+
+// Called by the generated handlers to decode an `Option<T>` input, `arg_index` being the
+// index of the input pointer among the arguments of the exported function.
+#[doc(hidden)]
+pub fn input_present(arg_index: u32) -> bool {
+    unsafe { crate::externs::input_present(arg_index) == 1 }
+}
+
+// Called by the generated handlers for a `params: String` first argument, the parameters
+// of a module are text unless the handler asks for a message.
+#[doc(hidden)]
+pub fn params_string() -> String {
+    String::from_utf8(crate::module::params())
+        .unwrap_or_else(|e| panic!("module params are not valid UTF-8: {}", e))
+}
+
+// Called by the generated handlers for a `params` first argument of any other type, decoded
+// as a protobuf message.
+#[doc(hidden)]
+pub fn decode_params<T: Default + prost::Message>() -> T {
+    crate::proto::decode(&crate::module::params()).unwrap_or_else(|e| {
+        panic!(
+            "module params are not a valid {}: {}",
+            std::any::type_name::<T>(),
+            e
+        )
+    })
+}
+
+// Called by the generated handlers to decode a `substreams::pb::substreams::Clock` input,
+// the host always sends a valid clock so a failure is reported as a bad clock input.
+#[doc(hidden)]
+pub fn decode_clock(ptr: *mut u8, size: usize) -> crate::pb::substreams::Clock {
+    crate::proto::decode_ptr(ptr, size)
+        .unwrap_or_else(|e| panic!("clock input is not a valid sf.substreams.v1.Clock: {}", e))
+}
+
+// Started by the generated handlers of `timed` modules, logs the time elapsed since when
+// dropped at the end of the handler.
+#[doc(hidden)]
+pub struct Timer {
+    module: &'static str,
+    start: Option<u64>,
+}
+
+impl Timer {
+    pub fn start(module: &'static str) -> Self {
+        Timer {
+            module,
+            start: now_nanos(),
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let (Some(start), Some(end)) = (self.start, now_nanos()) {
+            let elapsed = std::time::Duration::from_nanos(end.saturating_sub(start));
+            crate::log::debug!("{} took {:?}", self.module, elapsed);
+        }
+    }
+}
+
+fn now_nanos() -> Option<u64> {
+    #[cfg(feature = "timer")]
+    return Some(unsafe { crate::externs::now_nanos() });
+
+    #[cfg(not(feature = "timer"))]
+    None
+}
+
+// Called by the generated handlers before anything else, resets the state kept by the
+// crate across calls so nothing leaks from one invocation to the next.
+#[doc(hidden)]
+pub fn begin_invocation(module: &'static str) {
+    crate::set_current_module(Some(module));
+    crate::log::reset_scopes();
+    crate::log::reset_samples();
+    crate::state::reset_read_cache();
+    crate::state::reset_write_buffer();
+    crate::store::reset_writer();
+
+    #[cfg(feature = "call-stats")]
+    crate::stats::reset();
+}
+
+// Exported to the host, which calls it before each call of an entrypoint of the module,
+// handlers exported by hand included, so a call can create the writable store again
+// whatever the previous call on the same instance did.
+#[doc(hidden)]
+#[no_mangle]
+pub extern "C" fn substreams_begin_call() {
+    crate::store::reset_writer();
+}
+
+// Bound to a variable by the generated handlers, the writes buffered by the handler
+// are sent to the host when it's dropped, however the handler returns.
+#[doc(hidden)]
+pub struct FlushGuard;
+
+impl Drop for FlushGuard {
+    fn drop(&mut self) {
+        crate::state::flush();
+    }
+}
+
 /// Marks function to setup substream store handler WASM boilerplate
+///
+/// The writable store argument (at most one) is not provided by the host, it's
+/// constructed by the generated code, so it can be declared at any position among
+/// the arguments. The other arguments must follow the order of the `inputs` defined
+/// for the module in the Manifest.
+///
 /// ## Usage
 ///
 ///
@@ -84,4 +377,377 @@ pub use substreams_macro::map;
 ///    }
 /// }
 /// ```
+///
+/// ## Store arguments
+///
+/// Stores are recognized by the name of their type, the last segment of its path, as the
+/// macro only sees the handler itself. A type alias of a store is not resolved, it's taken
+/// for a protobuf message and fails to compile, so declare stores with their own type:
+///
+/// ```ignore
+/// use substreams::store;
+/// # mod proto { pub type Custom = (); }
+///
+/// type Balances = store::StoreAddInt64;
+///
+/// #[substreams::handlers::store]
+/// fn store_balances(data: proto::Custom, s: Balances) {
+///     unimplemented!("do something");
+/// }
+/// ```
+///
+/// Stores are also taken by value, an `impl Trait` argument or a reference to a store is
+/// reported on the argument:
+///
+/// ```ignore
+/// # mod proto { pub type Custom = (); }
+/// trait Balances {}
+///
+/// #[substreams::handlers::store]
+/// fn store_balances(data: proto::Custom, s: &impl Balances) {
+///     unimplemented!("do something");
+/// }
+/// ```
+///
+/// A store handler returns nothing, its output is what it writes to the store, and a
+/// return type is reported on the `-> T` of the signature:
+///
+/// ```ignore
+/// use substreams::store;
+/// # mod proto { pub type Custom = (); }
+///
+/// #[substreams::handlers::store]
+/// fn store_balances(data: proto::Custom, s: store::StoreAddInt64) -> u64 {
+///     unimplemented!("do something");
+/// }
+/// ```
+///
+/// ## Attributes
+///
+/// Accepts the same attributes as [macro@map], and:
+///
+/// * `policy = "<policy>"`: the `updatePolicy` of the module in the Manifest, one of
+///   `set`, `set_if_not_exists`, `add`, `min`, `max` or `append`.
+/// * `value_type = "<type>"`: the `valueType` of the module in the Manifest.
+///
+/// Both are only checked against the writable store argument, so a handler not matching
+/// its Manifest fails to compile instead of failing on the host:
+///
+/// ```rust
+/// use substreams::store;
+/// # mod proto { pub type Custom = (); }
+///
+/// #[substreams::handlers::store(policy = "add", value_type = "bigint")]
+/// fn store_volumes(data: proto::Custom, s: store::StoreAddBigInt) {
+///     unimplemented!("do something");
+/// }
+/// ```
+///
+/// ```ignore
+/// use substreams::store;
+/// # mod proto { pub type Custom = (); }
+///
+/// #[substreams::handlers::store(policy = "add", value_type = "bigint")]
+/// fn store_volumes(data: proto::Custom, s: store::StoreAddInt64) {
+///     unimplemented!("do something");
+/// }
+/// ```
 pub use substreams_macro::store;
+
+/// Marks function as the shared extraction of entities, generating both a map
+/// handler emitting them and a store handler indexing them
+///
+/// The function is written like a map handler, it's kept as-is and two handlers
+/// calling it are generated, with the same arguments:
+///
+/// * `map_<name>`: a [macro@map] handler outputting the value returned by the function.
+/// * `store_<name>`: a [macro@store] handler writing each entity of the returned value
+///   to a [StoreSet](crate::store::StoreSet), encoded as protobuf. The store handler
+///   panics if the function returns an error.
+///
+/// ## Attributes
+///
+/// * `entities = "<field>"` (required): the repeated field of the returned value holding
+///   the entities.
+/// * `key = "<path>"` (required): the path of a `fn(&Entity) -> String` returning the key
+///   an entity is stored at.
+/// * `ordinal = "<field>"`: the field of an entity holding the ordinal it's stored at,
+///   entities are stored at ordinal 0 when not set.
+///
+/// ## Usage
+///
+/// ```rust
+/// # mod eth { pub type Block = substreams::pb::substreams::Clock; }
+/// # mod proto {
+/// #     #[derive(Clone, PartialEq, ::prost::Message)]
+/// #     pub struct Token {
+/// #         #[prost(string, tag = "1")]
+/// #         pub address: String,
+/// #         #[prost(uint64, tag = "2")]
+/// #         pub ordinal: u64,
+/// #     }
+/// #     #[derive(Clone, PartialEq, ::prost::Message)]
+/// #     pub struct Tokens {
+/// #         #[prost(message, repeated, tag = "1")]
+/// #         pub tokens: Vec<Token>,
+/// #     }
+/// # }
+/// // Generates `map_tokens(blk: eth::Block) -> Result<proto::Tokens, Error>` and
+/// // `store_tokens(blk: eth::Block, entities_store: StoreSet)`.
+/// #[substreams::handlers::entities(entities = "tokens", key = "token_key", ordinal = "ordinal")]
+/// fn tokens(blk: eth::Block) -> Result<proto::Tokens, substreams::errors::Error> {
+///     unimplemented!("do something");
+/// }
+///
+/// fn token_key(token: &proto::Token) -> String {
+///     format!("token:{}", token.address)
+/// }
+/// ```
+pub use substreams_macro::entities;
+
+/// Marks function as the handler of a single contract event, identified by its
+/// Solidity `signature`
+///
+/// The topic 0 of the event, the keccak-256 hash of the signature, is computed at
+/// compile time and exposed as a `<HANDLER_NAME>_TOPIC` constant. The handler takes
+/// a single log argument, any type with a `topics: Vec<Vec<u8>>` field, and must
+/// return an `Option`. The generated code returns `None` without running the body
+/// when the topic 0 of the log doesn't match, so handlers can be tried one after
+/// the other with [crate::handlers::dispatch_event], or over all the logs of a block
+/// with a dispatcher generated by [crate::handlers::event_dispatcher].
+///
+/// ## Usage
+///
+/// ```rust
+/// # mod eth { pub struct Log { pub topics: Vec<Vec<u8>>, pub data: Vec<u8> } }
+/// use substreams::handlers::{dispatch_event, event};
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Event {
+///     Transfer,
+///     Approval,
+/// }
+///
+/// #[event(signature = "Transfer(address,address,uint256)")]
+/// fn on_transfer(log: &eth::Log) -> Option<Event> {
+///     Some(Event::Transfer)
+/// }
+///
+/// #[event(signature = "Approval(address,address,uint256)")]
+/// fn on_approval(log: &eth::Log) -> Option<Event> {
+///     Some(Event::Approval)
+/// }
+///
+/// let log = eth::Log { topics: vec![ON_APPROVAL_TOPIC.to_vec()], data: vec![] };
+/// assert_eq!(dispatch_event!(&log => on_transfer, on_approval), Some(Event::Approval));
+///
+/// let unknown = eth::Log { topics: vec![vec![0u8; 32]], data: vec![] };
+/// assert_eq!(dispatch_event!(&unknown => on_transfer, on_approval), None);
+///
+/// assert_eq!(
+///     ON_TRANSFER_TOPIC,
+///     substreams::hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef")
+/// );
+/// ```
+pub use substreams_macro::event;
+
+/// Routes a log to the first of the given [macro@event] handlers matching its topic 0,
+/// returning the value of that handler or `None` if no handler matches.
+///
+/// All handlers must return the same `Option` type, use an enum when events are
+/// decoded to different types.
+///
+/// # Examples
+///
+/// See [macro@event].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! handlers_dispatch_event {
+    ($log:expr => $($handler:path),+ $(,)?) => {{
+        let log = $log;
+
+        None $(.or_else(|| $handler(log)))+
+    }};
+}
+
+pub use handlers_dispatch_event as dispatch_event;
+
+/// Generates a function routing every log of a block, in block order, to the first of
+/// the given [macro@event] handlers matching its topic 0, like [dispatch_event]. The
+/// function returns the values of the matching handlers, logs no handler matches are
+/// skipped.
+///
+/// The block must implement [crate::eth::Block], its logs are the ones the handlers
+/// take, see [crate::eth::Block::indexed_logs] for the order they are routed in.
+///
+/// # Examples
+///
+/// ```rust
+/// # mod eth {
+/// #     pub struct Block { pub transaction_traces: Vec<TransactionTrace> }
+/// #     pub struct TransactionTrace { pub logs: Vec<Log> }
+/// #     pub struct Log { pub topics: Vec<Vec<u8>>, pub block_index: u32 }
+/// # }
+/// # impl substreams::eth::Log for eth::Log {
+/// #     fn index(&self) -> u32 { 0 }
+/// #     fn block_index(&self) -> u32 { self.block_index }
+/// # }
+/// # impl substreams::eth::Transaction for eth::TransactionTrace {
+/// #     type Log = eth::Log;
+/// #     fn hash(&self) -> &[u8] { &[] }
+/// #     fn index(&self) -> u32 { 0 }
+/// #     fn to(&self) -> &[u8] { &[] }
+/// #     fn logs(&self) -> &[eth::Log] { &self.logs }
+/// # }
+/// # impl substreams::eth::Block for eth::Block {
+/// #     type Transaction = eth::TransactionTrace;
+/// #     fn transactions(&self) -> &[eth::TransactionTrace] { &self.transaction_traces }
+/// # }
+/// use substreams::handlers::{event, event_dispatcher};
+///
+/// #[event(signature = "Transfer(address,address,uint256)")]
+/// fn on_transfer(log: &eth::Log) -> Option<String> {
+///     Some(format!("transfer:{}", log.block_index))
+/// }
+///
+/// #[event(signature = "Approval(address,address,uint256)")]
+/// fn on_approval(log: &eth::Log) -> Option<String> {
+///     Some(format!("approval:{}", log.block_index))
+/// }
+///
+/// event_dispatcher! {
+///     fn dispatch(blk: &eth::Block) -> String {
+///         on_transfer,
+///         on_approval,
+///     }
+/// }
+///
+/// let log = |topic: &[u8], block_index| eth::Log { topics: vec![topic.to_vec()], block_index };
+/// let blk = eth::Block {
+///     transaction_traces: vec![eth::TransactionTrace {
+///         logs: vec![log(&ON_APPROVAL_TOPIC, 0), log(&[0u8; 32], 1), log(&ON_TRANSFER_TOPIC, 2)],
+///     }],
+/// };
+///
+/// assert_eq!(dispatch(&blk), vec!["approval:0", "transfer:2"]);
+/// ```
+#[doc(hidden)]
+#[macro_export]
+macro_rules! handlers_event_dispatcher {
+    (
+        $vis:vis fn $name:ident($blk:ident: &$block:ty) -> $output:ty {
+            $($handler:path),+ $(,)?
+        }
+    ) => {
+        $vis fn $name($blk: &$block) -> Vec<$output> {
+            $crate::eth::Block::indexed_logs($blk)
+                .into_iter()
+                .filter_map(|log| $crate::handlers_dispatch_event!(log.log => $($handler),+))
+                .collect()
+        }
+    };
+}
+
+pub use handlers_event_dispatcher as event_dispatcher;
+
+#[cfg(test)]
+mod tests {
+    use crate::eth::{Block, Log, Transaction};
+    use crate::handlers::{event, event_dispatcher};
+
+    struct TestLog {
+        topics: Vec<Vec<u8>>,
+        block_index: u32,
+    }
+
+    impl Log for TestLog {
+        fn index(&self) -> u32 {
+            0
+        }
+
+        fn block_index(&self) -> u32 {
+            self.block_index
+        }
+    }
+
+    struct TestTransaction(Vec<TestLog>);
+
+    impl Transaction for TestTransaction {
+        type Log = TestLog;
+
+        fn hash(&self) -> &[u8] {
+            &[]
+        }
+
+        fn index(&self) -> u32 {
+            0
+        }
+
+        fn to(&self) -> &[u8] {
+            &[]
+        }
+
+        fn logs(&self) -> &[TestLog] {
+            &self.0
+        }
+    }
+
+    struct TestBlock(Vec<TestTransaction>);
+
+    impl Block for TestBlock {
+        type Transaction = TestTransaction;
+
+        fn transactions(&self) -> &[TestTransaction] {
+            &self.0
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Transfer(u32),
+        Approval(u32),
+    }
+
+    #[event(signature = "Transfer(address,address,uint256)")]
+    fn on_transfer(log: &TestLog) -> Option<Event> {
+        Some(Event::Transfer(log.block_index))
+    }
+
+    #[event(signature = "Approval(address,address,uint256)")]
+    fn on_approval(log: &TestLog) -> Option<Event> {
+        Some(Event::Approval(log.block_index))
+    }
+
+    event_dispatcher! {
+        fn dispatch(blk: &TestBlock) -> Event {
+            on_transfer,
+            on_approval,
+        }
+    }
+
+    fn log(topics: &[&[u8]], block_index: u32) -> TestLog {
+        TestLog {
+            topics: topics.iter().map(|topic| topic.to_vec()).collect(),
+            block_index,
+        }
+    }
+
+    #[test]
+    fn it_dispatches_every_log_of_block() {
+        let blk = TestBlock(vec![
+            TestTransaction(vec![log(&[&ON_TRANSFER_TOPIC], 0), log(&[&[0u8; 32]], 1)]),
+            TestTransaction(vec![]),
+            TestTransaction(vec![
+                log(&[], 3),
+                log(&[&ON_APPROVAL_TOPIC, &ON_TRANSFER_TOPIC], 2),
+                log(&[&ON_TRANSFER_TOPIC], 4),
+            ]),
+        ]);
+
+        assert_eq!(
+            dispatch(&blk),
+            vec![Event::Transfer(0), Event::Approval(2), Event::Transfer(4)]
+        );
+        assert!(dispatch(&TestBlock(vec![])).is_empty());
+    }
+}
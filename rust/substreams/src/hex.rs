@@ -1,4 +1,7 @@
+use std::convert::TryFrom;
 use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
 
 /// Hex is a simple wrapper type that you can use to wrap your type so that it
 /// prints in lower hexadecimal format when use as a formatting argument.
@@ -21,17 +24,123 @@ use std::fmt;
 ///
 /// let encode = Hex::encode(&[0xabu8, 0xcdu8, 0xefu8]);
 /// ```
+///
+/// Or appended to an existing `String`, to build keys in a reused buffer without
+/// allocating a `String` for each encoded part:
+///
+/// ```
+/// use substreams::Hex;
+///
+/// let mut key = String::with_capacity(64);
+/// for holder in [[0x01u8; 20], [0x02u8; 20]] {
+///     key.clear();
+///     key.push_str("total:");
+///     Hex::encode_to(&holder, &mut key);
+///     // store.add(ord, &key, 1);
+/// }
+/// ```
+///
+/// And to decode an hexadecimal string, with or without its `0x` prefix, back to bytes:
+///
+/// ```
+/// use substreams::Hex;
+///
+/// assert_eq!(Hex::decode("0xABcdef").unwrap(), vec![0xabu8, 0xcdu8, 0xefu8]);
+/// ```
+///
+/// Or to parse it directly as an owned `Hex`:
+///
+/// ```
+/// use substreams::{Hex, HexError};
+///
+/// let address: Hex<Vec<u8>> = "0xbc4ca0eda7647a8ab7c2061c2e118a18a936f13d".parse()?;
+/// assert_eq!(address.0.len(), 20);
+/// # Ok::<(), HexError>(())
+/// ```
 pub struct Hex<T>(pub T);
 
+/// HexError is returned when decoding a string that is not valid hexadecimal
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum HexError {
+    #[error("invalid hex character {character:?} at position {index}")]
+    InvalidCharacter { character: char, index: usize },
+
+    #[error("hex string has an odd number of digits ({0}), each byte needs two")]
+    OddLength(usize),
+
+    #[error("hex string decodes to {actual} bytes but {expected} were expected")]
+    InvalidLength { expected: usize, actual: usize },
+}
+
+impl Hex<Vec<u8>> {
+    /// Decodes an hexadecimal string like [Hex::decode] into `out`, which must be exactly
+    /// the size of the decoded bytes, for fixed size values like a 20 bytes address.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use substreams::Hex;
+    ///
+    /// let mut address = [0u8; 20];
+    /// Hex::decode_into("0xbc4ca0eda7647a8ab7c2061c2e118a18a936f13d", &mut address).unwrap();
+    /// assert!(Hex::decode_into("0xbc4c", &mut address).is_err());
+    /// ```
+    pub fn decode_into(s: &str, out: &mut [u8]) -> Result<(), HexError> {
+        let (digits, offset) = strip_prefix(s);
+        check_even(digits)?;
+
+        if digits.len() / 2 != out.len() {
+            return Err(HexError::InvalidLength {
+                expected: out.len(),
+                actual: digits.len() / 2,
+            });
+        }
+
+        decode_digits(digits, offset, out)
+    }
+}
+
+impl FromStr for Hex<Vec<u8>> {
+    type Err = HexError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Hex::decode(s).map(Hex)
+    }
+}
+
+impl TryFrom<&str> for Hex<Vec<u8>> {
+    type Error = HexError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
 impl<T: AsRef<[u8]>> Hex<T> {
-    pub fn decode(data: T) -> Result<Vec<u8>, hex::FromHexError> {
-        ::hex::decode(data)
+    /// Decodes an hexadecimal string, or its bytes, to bytes. The string may start with a
+    /// `0x` or `0X` prefix and digits may be in upper or lower case.
+    pub fn decode(data: T) -> Result<Vec<u8>, HexError> {
+        let s = as_str(data.as_ref())?;
+        let (digits, offset) = strip_prefix(s);
+        check_even(digits)?;
+
+        let mut bytes = vec![0u8; digits.len() / 2];
+        decode_digits(digits, offset, &mut bytes)?;
+        Ok(bytes)
     }
 
     pub fn encode(input: T) -> String {
         encode_lower_hex(input.as_ref())
     }
 
+    /// Appends the lower hexadecimal encoding of `input` to `out`, like [Hex::encode]
+    /// without allocating a new `String`. `out` is not cleared first.
+    pub fn encode_to(input: T, out: &mut String) {
+        let bytes = input.as_ref();
+        out.reserve(bytes.len() * 2);
+        write_hex(out, bytes).expect("writing to a String never fails");
+    }
+
     pub fn to_string(&self) -> String {
         encode_lower_hex(self.0.as_ref())
     }
@@ -74,6 +183,81 @@ const LOWER_HEX_BYTES: [&str; 256] = [
     "f0", "f1", "f2", "f3", "f4", "f5", "f6", "f7", "f8", "f9", "fa", "fb", "fc", "fd", "fe", "ff",
 ];
 
+/// Returns `data` as a string, hexadecimal is ASCII so bytes that are not valid UTF-8 are
+/// reported as an invalid character at the position of the first of them.
+fn as_str(data: &[u8]) -> Result<&str, HexError> {
+    std::str::from_utf8(data).map_err(|e| HexError::InvalidCharacter {
+        character: char::REPLACEMENT_CHARACTER,
+        index: e.valid_up_to(),
+    })
+}
+
+fn strip_prefix(s: &str) -> (&str, usize) {
+    if s.starts_with("0x") || s.starts_with("0X") {
+        return (&s[2..], 2);
+    }
+
+    (s, 0)
+}
+
+fn check_even(digits: &str) -> Result<(), HexError> {
+    if digits.len() % 2 != 0 {
+        return Err(HexError::OddLength(digits.len()));
+    }
+
+    Ok(())
+}
+
+/// Decodes `digits` into `out`, `offset` being the position of `digits` in the original
+/// string so errors point at the right character.
+fn decode_digits(digits: &str, offset: usize, out: &mut [u8]) -> Result<(), HexError> {
+    let nibble = |index: usize| -> Result<u8, HexError> {
+        let digit = digits.as_bytes()[index];
+        match digit {
+            b'0'..=b'9' => Ok(digit - b'0'),
+            b'a'..=b'f' => Ok(digit - b'a' + 10),
+            b'A'..=b'F' => Ok(digit - b'A' + 10),
+            _ => {
+                // `index` may fall inside a multi-byte character, report the whole of it
+                let (start, character) = digits
+                    .char_indices()
+                    .take_while(|(start, _)| *start <= index)
+                    .last()
+                    .expect("index is within digits");
+
+                Err(HexError::InvalidCharacter {
+                    character,
+                    index: offset + start,
+                })
+            }
+        }
+    };
+
+    for (i, byte) in out.iter_mut().enumerate() {
+        *byte = (nibble(2 * i)? << 4) | nibble(2 * i + 1)?;
+    }
+
+    Ok(())
+}
+
+/// Writes the lower hexadecimal encoding of `bytes` to `w`, a `String` or a
+/// `fmt::Formatter`, without any intermediate allocation.
+///
+/// # Examples
+///
+/// ```
+/// use std::fmt::Write;
+/// use substreams::write_hex;
+///
+/// let mut key = String::new();
+/// write!(key, "owner:").unwrap();
+/// write_hex(&mut key, &[0xab, 0xcd]).unwrap();
+/// assert_eq!(key, "owner:abcd");
+/// ```
+pub fn write_hex(w: &mut impl fmt::Write, bytes: &[u8]) -> fmt::Result {
+    write_lower_hex(bytes, w)
+}
+
 fn write_lower_hex(input: &[u8], mut w: impl std::fmt::Write) -> fmt::Result {
     for byte in input {
         w.write_str(LOWER_HEX_BYTES[*byte as usize])?;
@@ -89,14 +273,15 @@ fn encode_lower_hex<T: AsRef<[u8]>>(input: T) -> String {
         return String::new();
     }
 
-    let mut buffer = String::with_capacity(bytes.len());
+    let mut buffer = String::with_capacity(bytes.len() * 2);
     write_lower_hex(bytes, &mut buffer).expect("non-faillible pre-allocated buffer");
     buffer
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::hex::encode_lower_hex;
+    use crate::hex::{encode_lower_hex, write_hex, Hex, HexError};
+    use std::convert::TryFrom;
 
     #[test]
     fn it_encode_lower_hex_correctly() {
@@ -104,4 +289,165 @@ mod tests {
         assert_eq!(encode_lower_hex(&[0x01u8]), "01");
         assert_eq!(encode_lower_hex(&[0xa1u8, 0xc3u8]), "a1c3");
     }
+
+    #[test]
+    fn it_encode_to_matches_encode() {
+        for input in [
+            &[] as &[u8],
+            &[0x01u8],
+            &[0xa1u8, 0xc3u8],
+            &[0xffu8; 20],
+            &(0..=255).collect::<Vec<u8>>()[..],
+        ] {
+            let mut out = String::from("key:");
+            Hex::encode_to(input, &mut out);
+            assert_eq!(out, format!("key:{}", Hex::encode(input)));
+
+            let mut written = String::new();
+            write_hex(&mut written, input).unwrap();
+            assert_eq!(written, Hex::encode(input));
+        }
+    }
+
+    #[test]
+    fn it_encode_to_reuses_buffer() {
+        let holders: Vec<[u8; 20]> = (0..10_000u32)
+            .map(|i| {
+                let mut holder = [0u8; 20];
+                holder[16..].copy_from_slice(&i.to_be_bytes());
+                holder
+            })
+            .collect();
+
+        let mut key = String::with_capacity(6 + 40);
+        let buffer = key.as_ptr();
+        for holder in &holders {
+            key.clear();
+            key.push_str("total:");
+            Hex::encode_to(holder, &mut key);
+
+            assert_eq!(key.len(), 6 + 40);
+        }
+        assert_eq!(key.as_ptr(), buffer, "key buffer was reallocated");
+        assert!(key.ends_with(&Hex::encode(&holders[9_999])));
+    }
+
+    #[test]
+    fn it_decode_with_and_without_prefix() {
+        assert_eq!(Hex::decode("a1c3"), Ok(vec![0xa1u8, 0xc3u8]));
+        assert_eq!(Hex::decode("0xa1c3"), Ok(vec![0xa1u8, 0xc3u8]));
+        assert_eq!(Hex::decode("0XA1C3"), Ok(vec![0xa1u8, 0xc3u8]));
+        assert_eq!(Hex::decode("0xA1c3"), Ok(vec![0xa1u8, 0xc3u8]));
+        assert_eq!(Hex::decode("0x"), Ok(vec![]));
+        assert_eq!(Hex::decode(""), Ok(vec![]));
+    }
+
+    #[test]
+    fn it_decode_bytes() {
+        assert_eq!(Hex::decode(b"0xa1c3"), Ok(vec![0xa1u8, 0xc3u8]));
+        assert_eq!(Hex::decode(b"a1c3".to_vec()), Ok(vec![0xa1u8, 0xc3u8]));
+        assert_eq!(Hex::decode(&b"a1c3"[..]), Ok(vec![0xa1u8, 0xc3u8]));
+        assert_eq!(Hex::decode(String::from("a1c3")), Ok(vec![0xa1u8, 0xc3u8]));
+        assert_eq!(
+            Hex::decode(vec![b'a', b'1', 0xff, b'3']),
+            Err(HexError::InvalidCharacter {
+                character: char::REPLACEMENT_CHARACTER,
+                index: 2
+            })
+        );
+    }
+
+    #[test]
+    fn it_decode_rejects_odd_length() {
+        assert_eq!(Hex::decode("a1c"), Err(HexError::OddLength(3)));
+        assert_eq!(Hex::decode("0x1"), Err(HexError::OddLength(1)));
+        assert_eq!(
+            Hex::decode("0x1").unwrap_err().to_string(),
+            "hex string has an odd number of digits (1), each byte needs two"
+        );
+    }
+
+    #[test]
+    fn it_decode_rejects_invalid_characters() {
+        assert_eq!(
+            Hex::decode("0xa1zz"),
+            Err(HexError::InvalidCharacter {
+                character: 'z',
+                index: 4
+            })
+        );
+        assert_eq!(
+            Hex::decode("0x0x"),
+            Err(HexError::InvalidCharacter {
+                character: 'x',
+                index: 3
+            })
+        );
+        assert_eq!(
+            Hex::decode("a1é"),
+            Err(HexError::InvalidCharacter {
+                character: 'é',
+                index: 2
+            })
+        );
+        assert_eq!(
+            Hex::decode("0é0"),
+            Err(HexError::InvalidCharacter {
+                character: 'é',
+                index: 1
+            })
+        );
+        assert_eq!(
+            Hex::decode("a1 3").unwrap_err().to_string(),
+            "invalid hex character ' ' at position 2"
+        );
+    }
+
+    #[test]
+    fn it_parse_round_trips() {
+        for input in [
+            "",
+            "0x",
+            "a1c3",
+            "0xA1c3",
+            "0XBC4CA0EDA7647A8AB7C2061C2E118A18A936F13D",
+        ] {
+            let parsed: Hex<Vec<u8>> = input.parse().unwrap();
+            let expected = input.trim_start_matches("0x").trim_start_matches("0X");
+
+            assert_eq!(Hex::encode(&parsed.0), expected.to_lowercase());
+            assert_eq!(Hex::try_from(input).unwrap().0, parsed.0);
+        }
+    }
+
+    #[test]
+    fn it_parse_fails_like_decode() {
+        assert_eq!(
+            "0xa1c".parse::<Hex<Vec<u8>>>().unwrap_err(),
+            HexError::OddLength(3)
+        );
+        assert_eq!(
+            Hex::try_from("0xa1zz").unwrap_err(),
+            Hex::decode("0xa1zz").unwrap_err()
+        );
+    }
+
+    #[test]
+    fn it_decode_into_fixed_size() {
+        let mut out = [0u8; 2];
+        assert_eq!(Hex::decode_into("0xa1c3", &mut out), Ok(()));
+        assert_eq!(out, [0xa1u8, 0xc3u8]);
+
+        assert_eq!(
+            Hex::decode_into("a1c3ff", &mut out),
+            Err(HexError::InvalidLength {
+                expected: 2,
+                actual: 3
+            })
+        );
+        assert_eq!(
+            Hex::decode_into("a1c", &mut out),
+            Err(HexError::OddLength(3))
+        );
+    }
 }
@@ -70,19 +70,35 @@
 //!```
 extern crate core;
 
+mod bytes;
+mod dedup;
+pub mod deltas;
+mod each;
 pub mod errors;
+pub mod eth;
 mod externs;
+pub mod guard;
 pub mod handlers;
 mod hex;
 pub mod log;
 pub mod memory;
+pub mod module;
 
 /// Protobuf generated Substream models
 pub mod pb;
+pub mod pipeline;
 pub mod proto;
 mod state;
+#[cfg(feature = "call-stats")]
+pub mod stats;
 pub mod store;
-pub use crate::hex::Hex;
+pub mod time;
+pub use crate::bytes::{bytes_to_bigint_be, bytes_to_u128_be, bytes_to_u64_be, OverflowError};
+pub use crate::dedup::dedup_by_key;
+pub use crate::each::try_each;
+pub use crate::guard::require;
+pub use crate::hex::{write_hex, Hex, HexError};
+pub use crate::pipeline::pipeline;
 pub use hex_literal::hex;
 
 pub fn output<M: prost::Message>(msg: M) {
@@ -100,7 +116,93 @@ pub fn output_raw(data: Vec<u8>) {
     unsafe { externs::output(data.as_ptr(), data.len() as u32) }
 }
 
-/// Registers a Substreams custom panic hook. The panic hook is invoked when then handler panics
+/// Emits `data` on the debug channel of the host, next to the output of the module, to
+/// inspect intermediate payloads during development. It never replaces what is emitted
+/// with [output] or [output_raw], and the host is free to drop it, so it must not be
+/// relied on for the output of the module: debug payloads aren't part of the deterministic
+/// output and aren't cached.
+///
+/// The host binding is only used when the `debug-output` feature is enabled, otherwise
+/// this is a no-op, which is what production builds should do.
+pub fn output_debug(data: Vec<u8>) {
+    #[cfg(feature = "debug-output")]
+    unsafe {
+        externs::output_debug(data.as_ptr(), data.len() as u32)
+    }
+
+    #[cfg(not(feature = "debug-output"))]
+    let _ = data;
+}
+
+/// Emits `value` encoded as JSON on the debug channel of the host, like [output_debug], to
+/// read what a module produces while developing it without decoding its protobuf output.
+/// The payload comes in addition to [output], which stays the only output of the module.
+///
+/// Only available with the `json` feature, and the payload only reaches the host with the
+/// `debug-output` feature, otherwise the serialized value is dropped. Derive
+/// `serde::Serialize` on the output types and call `output_json` behind the same feature,
+/// with `#[cfg_attr(feature = "json", derive(serde::Serialize))]` and
+/// `#[cfg(feature = "json")]`, to keep production builds free of `serde`.
+///
+/// # Panics
+///
+/// Panics if `value` can't be serialized to JSON, like a map with non string keys.
+#[cfg(feature = "json")]
+pub fn output_json<T: serde::Serialize>(value: &T) {
+    output_debug(json_payload(value))
+}
+
+#[cfg(feature = "json")]
+fn json_payload<T: serde::Serialize>(value: &T) -> Vec<u8> {
+    serde_json::to_vec(value).unwrap_or_else(|e| panic!("value can't be serialized: {}", e))
+}
+
+/// Reports the progress of a long running handler, `fraction` being the completed part
+/// of the work, between 0 and 1, and `note` a short description of the current step.
+///
+/// Progress is advisory only, the host may ignore it, and the call must stay cheap so
+/// report at most every few thousand items of a big loop. The host binding is only used
+/// when the `progress` feature is enabled, otherwise this is a no-op so modules can call
+/// it unconditionally on hosts that don't support it.
+pub fn progress(fraction: f32, note: &str) {
+    #[cfg(feature = "progress")]
+    unsafe {
+        externs::progress(fraction.max(0.0).min(1.0), note.as_ptr(), note.len() as u32)
+    }
+
+    #[cfg(not(feature = "progress"))]
+    let _ = (fraction, note);
+}
+
+thread_local! {
+    static CURRENT_MODULE: std::cell::Cell<Option<&'static str>> = std::cell::Cell::new(None);
+}
+
+/// Returns the name of the module whose handler is running, the name of the handler
+/// function, or `None` outside of a handler generated by the [handlers] macros.
+pub fn current_module() -> Option<&'static str> {
+    CURRENT_MODULE.with(|module| module.get())
+}
+
+pub(crate) fn set_current_module(module: Option<&'static str>) {
+    CURRENT_MODULE.with(|current| current.set(module));
+}
+
+/// Reports the error returned by a handler to the host, the handler then returns without
+/// output. Unlike a panic, which traps the module, the host can tell that the module ran to
+/// completion and produced an error. This is what the `map` handler macro calls when the
+/// handler returns an `Err`.
+#[doc(hidden)]
+pub fn set_error<E: std::fmt::Debug>(err: E) {
+    let message = format!("{:?}", err);
+    unsafe { externs::set_error(message.as_ptr(), message.len() as u32) }
+}
+
+/// Registers a Substreams custom panic hook. The panic hook is invoked when then handler panics,
+/// it reports the panic to the host once, with the message
+/// `panicked file=<file> line=<line> column=<column>: <message>`, so the location is in the
+/// error even when the host only surfaces its message. Within a handler the name of the module
+/// is part of the message too, as `panicked module=<module> file=<file> ...`.
 pub fn register_panic_hook() {
     use std::sync::Once;
     static SET_HOOK: Once = Once::new();
@@ -110,12 +212,7 @@ pub fn register_panic_hook() {
 }
 
 fn hook(info: &std::panic::PanicInfo<'_>) {
-    let error_msg = info
-        .payload()
-        .downcast_ref::<String>()
-        .map(String::as_str)
-        .or_else(|| info.payload().downcast_ref::<&'static str>().copied())
-        .unwrap_or("");
+    let error_msg = describe_panic(info);
     let location = info.location();
 
     unsafe {
@@ -145,3 +242,121 @@ fn hook(info: &std::panic::PanicInfo<'_>) {
         };
     }
 }
+
+fn panic_payload<'a>(info: &'a std::panic::PanicInfo<'_>) -> &'a str {
+    info.payload()
+        .downcast_ref::<String>()
+        .map(String::as_str)
+        .or_else(|| info.payload().downcast_ref::<&'static str>().copied())
+        .unwrap_or("")
+}
+
+fn describe_panic(info: &std::panic::PanicInfo<'_>) -> String {
+    let module = current_module()
+        .map(|module| format!(" module={}", module))
+        .unwrap_or_default();
+    match info.location() {
+        Some(loc) => format!(
+            "panicked{} file={} line={} column={}: {}",
+            module,
+            loc.file(),
+            loc.line(),
+            loc.column(),
+            panic_payload(info)
+        ),
+        None => format!("panicked{}: {}", module, panic_payload(info)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::sync::Once;
+
+    thread_local! {
+        static CAPTURING: Cell<bool> = Cell::new(false);
+        static PANIC: RefCell<Option<String>> = RefCell::new(None);
+    }
+
+    // Returns the description of the panic of `f`. The panic hook is global to the process,
+    // so it's installed once for all the tests instead of being swapped by each of them, and
+    // panics of the threads not capturing one go to the previous hook.
+    fn capture_panic<F: FnOnce() + std::panic::UnwindSafe>(f: F) -> Option<String> {
+        static INSTALL: Once = Once::new();
+        INSTALL.call_once(|| {
+            let previous = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                if CAPTURING.with(Cell::get) {
+                    PANIC.with(|panic| *panic.borrow_mut() = Some(crate::describe_panic(info)))
+                } else {
+                    previous(info)
+                }
+            }));
+        });
+
+        CAPTURING.with(|capturing| capturing.set(true));
+        let result = std::panic::catch_unwind(f);
+        CAPTURING.with(|capturing| capturing.set(false));
+
+        assert!(result.is_err());
+        PANIC.with(|panic| panic.borrow_mut().take())
+    }
+
+    #[test]
+    fn it_describes_panic_with_location() {
+        let line = line!() + 1;
+        let described = capture_panic(|| panic!("invalid transfer {}", 42)).unwrap();
+
+        assert!(described.starts_with(&format!("panicked file={} line={} ", file!(), line)));
+        assert!(described.ends_with(": invalid transfer 42"));
+    }
+
+    #[test]
+    fn it_describes_panic_with_module() {
+        crate::set_current_module(Some("map_transfers"));
+        let described = capture_panic(|| panic!("invalid transfer")).unwrap();
+        crate::set_current_module(None);
+
+        assert!(described.starts_with(&format!("panicked module=map_transfers file={} ", file!())));
+    }
+
+    #[cfg(not(feature = "debug-output"))]
+    #[test]
+    fn it_ignores_debug_output_without_binding() {
+        crate::output_debug(b"intermediate".to_vec());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn it_encodes_json_payload() {
+        #[derive(serde::Serialize)]
+        struct Transfer {
+            from: String,
+            token_id: u64,
+            tags: Vec<&'static str>,
+        }
+
+        let payload = crate::json_payload(&Transfer {
+            from: "bc4ca0eda7647a8ab7c2061c2e118a18a936f13d".to_owned(),
+            token_id: 42,
+            tags: vec!["mint"],
+        });
+
+        let value: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "from": "bc4ca0eda7647a8ab7c2061c2e118a18a936f13d",
+                "token_id": 42,
+                "tags": ["mint"],
+            })
+        );
+    }
+
+    #[cfg(not(feature = "progress"))]
+    #[test]
+    fn it_ignores_progress_without_binding() {
+        crate::progress(0.5, "halfway");
+        crate::progress(f32::NAN, "");
+    }
+}
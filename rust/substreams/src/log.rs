@@ -5,6 +5,21 @@
 //!
 
 use crate::externs;
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+
+thread_local! {
+    static MODULE_PREFIX: Cell<bool> = Cell::new(false);
+    static SCOPES: RefCell<Vec<String>> = RefCell::new(Vec::new());
+    // Bumped each time the scopes are reset, so a guard outliving its invocation doesn't
+    // end the scopes of the next one when dropped
+    static SCOPES_GENERATION: Cell<u64> = Cell::new(0);
+    static SAMPLES: RefCell<HashMap<CallSite, u64>> = RefCell::new(HashMap::new());
+}
+
+#[doc(hidden)]
+pub type CallSite = (&'static str, u32, u32);
 
 /// Logs a message at INFO level on the logger of the current substream using interpolation of
 /// runtime expressions.
@@ -17,6 +32,9 @@ use crate::externs;
 /// This indicates an incorrect implementation
 /// since `fmt::Write for String` never returns an error itself.
 ///
+/// A leading `target: "name"` tags the message with the subsystem it's about, see
+/// [println_target], so logs can be filtered by target.
+///
 /// # Examples
 ///
 /// ```no_run
@@ -25,6 +43,7 @@ use crate::externs;
 /// log::info!("test");
 /// log::info!("hello {}", "world!");
 /// log::info!("x = {}, y = {y}", 10, y = 30);
+/// log::info!(target: "transfers", "found {}", 3);
 /// ```
 #[doc(hidden)]
 #[macro_export]
@@ -32,6 +51,16 @@ macro_rules! log_info {
     // We have a special case when matching an expression directly to forward directly to `println`. This is to avoid
     // any allocation and pass directly the literal to `println` which is able to deal with. However, I'm wondering if
     // this will cause WTF moment for some cases.
+    (target: $target:expr, $msg:expr) => {
+        $crate::log::println_target($target, $msg);
+    };
+
+    (target: $target:expr, $($arg:tt)+) => {{
+        let message = std::fmt::format(format_args!($($arg)+));
+
+        $crate::log::println_target($target, message);
+    }};
+
     ($msg:expr) => {
         $crate::log::println($msg);
     };
@@ -62,6 +91,7 @@ macro_rules! log_info {
 /// log::debug!("test");
 /// log::debug!("hello {}", "world!");
 /// log::debug!("x = {}, y = {y}", 10, y = 30);
+/// log::debug!(target: "transfers", "found {}", 3);
 /// ```
 #[doc(hidden)]
 #[macro_export]
@@ -69,6 +99,16 @@ macro_rules! log_debug {
     // We have a special case when matching an expression directly to forward directly to `println`. This is to avoid
     // any allocation and pass directly the literal to `println` which is able to deal with. However, I'm wondering if
     // this will cause WTF moment for some cases.
+    (target: $target:expr, $msg:expr) => {
+        $crate::log::println_target($target, $msg);
+    };
+
+    (target: $target:expr, $($arg:tt)+) => {{
+        let message = std::fmt::format(format_args!($($arg)+));
+
+        $crate::log::println_target($target, message);
+    }};
+
     ($msg:expr) => {
         $crate::log::println($msg);
     };
@@ -80,13 +120,334 @@ macro_rules! log_debug {
     }}
 }
 
+/// Logs a message like [info!] but only the first time this line is reached in the current
+/// handler invocation, the following calls are silently dropped. See [every_n] to log
+/// periodically with a count of the dropped lines instead.
+///
+/// # Examples
+///
+/// ```no_run
+/// use substreams::log;
+///
+/// # let transfers: Vec<u64> = vec![];
+/// for transfer in transfers {
+///     log::once!("first transfer is {}", transfer);
+/// }
+/// ```
+#[doc(hidden)]
+#[macro_export]
+macro_rules! log_once {
+    ($($arg:tt)*) => {
+        $crate::log::sample_at((file!(), line!(), column!()), u64::MAX, || {
+            std::fmt::format(format_args!($($arg)*))
+        });
+    };
+}
+
 pub use log_debug as debug;
 pub use log_info as info;
+pub use log_once as once;
 
 pub fn println<T: AsRef<str>>(msg: T) {
-    let reference = msg.as_ref();
+    emit(None, msg.as_ref())
+}
+
+/// Logs `msg` tagged with `target`, the message is emitted as `target=<target> <msg>` so
+/// tooling can filter the logs of a subsystem. This is what `info!(target: ..., ...)` and
+/// `debug!(target: ..., ...)` call.
+pub fn println_target<T: AsRef<str>>(target: &str, msg: T) {
+    emit(Some(target), msg.as_ref())
+}
+
+fn emit(target: Option<&str>, msg: &str) {
+    let message = prefixed(target, msg);
+    let reference = message.as_ref();
+
+    #[cfg(feature = "call-stats")]
+    crate::stats::record("println");
 
     unsafe {
         externs::println(reference.as_ptr(), reference.len());
     }
 }
+
+/// Logs the message built by `message` on the 1st, (n+1)th, (2n+1)th... call from the same
+/// line within the current handler invocation, to keep a log in a hot loop from flooding
+/// the host. `message` is only called when the line is logged, and the line ends with the
+/// number of calls suppressed since the previous one, if any. An `n` of 0 or 1 logs every
+/// call, with no suppressed count.
+///
+/// # Examples
+///
+/// ```no_run
+/// use substreams::log;
+///
+/// # let transactions: Vec<String> = vec![];
+/// for hash in transactions {
+///     // logs "trx 0x..." then "trx 0x... (999 suppressed)" every 1000 transactions
+///     log::every_n(1000, || format!("trx {}", hash));
+/// }
+/// ```
+#[track_caller]
+pub fn every_n<F: FnOnce() -> String>(n: u64, message: F) {
+    let caller = std::panic::Location::caller();
+    sample_at((caller.file(), caller.line(), caller.column()), n, message)
+}
+
+#[doc(hidden)]
+pub fn sample_at<F: FnOnce() -> String>(site: CallSite, n: u64, message: F) {
+    if let Some(line) = sample(site, n, message) {
+        println(line)
+    }
+}
+
+#[doc(hidden)]
+pub fn reset_samples() {
+    SAMPLES.with(|samples| samples.borrow_mut().clear());
+}
+
+fn sample<F: FnOnce() -> String>(site: CallSite, n: u64, message: F) -> Option<String> {
+    let calls = SAMPLES.with(|samples| {
+        let mut samples = samples.borrow_mut();
+        let calls = samples.entry(site).or_insert(0);
+        *calls += 1;
+        *calls
+    });
+
+    let n = n.max(1);
+    if (calls - 1) % n != 0 {
+        return None;
+    }
+
+    if calls == 1 || n == 1 {
+        return Some(message());
+    }
+    Some(format!("{} ({} suppressed)", message(), n - 1))
+}
+
+/// Prefixes every line logged while the returned guard is alive with `[name]`, scopes
+/// nest so a line logged from within `decode` then `topics` reads `[decode] [topics] ...`.
+///
+/// The scope ends when the guard is dropped, dropping an outer guard also ends all the
+/// scopes opened after it. Scopes never leak from one handler invocation to the next,
+/// the stack is reset when the handler starts, and dropping a guard kept from a previous
+/// invocation leaves the scopes of the current one untouched.
+///
+/// # Examples
+///
+/// ```no_run
+/// use substreams::log;
+///
+/// let _scope = log::scope("decode");
+/// log::info!("{} transfers", 10); // logs "[decode] 10 transfers"
+/// ```
+pub fn scope<T: Into<String>>(name: T) -> Scope {
+    let depth = SCOPES.with(|scopes| {
+        let mut scopes = scopes.borrow_mut();
+        scopes.push(format!("[{}]", name.into()));
+        scopes.len() - 1
+    });
+
+    Scope {
+        depth,
+        generation: SCOPES_GENERATION.with(Cell::get),
+    }
+}
+
+/// Guard returned by [scope], the scope ends when it's dropped.
+#[must_use = "the scope ends as soon as the guard is dropped"]
+pub struct Scope {
+    depth: usize,
+    generation: u64,
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        if SCOPES_GENERATION.with(Cell::get) != self.generation {
+            return;
+        }
+        SCOPES.with(|scopes| scopes.borrow_mut().truncate(self.depth));
+    }
+}
+
+#[doc(hidden)]
+pub fn reset_scopes() {
+    SCOPES.with(|scopes| scopes.borrow_mut().clear());
+    SCOPES_GENERATION.with(|generation| generation.set(generation.get() + 1));
+}
+
+/// Prefixes the logs emitted from now on with `module=<name>`, the name of the running
+/// handler as returned by [crate::current_module], so the logs of the modules of a pipeline
+/// can be told apart once merged. The prefix comes before the target and scopes of the line
+/// and stays enabled until disabled. It has no effect outside of a handler.
+pub fn prefix_module(enabled: bool) {
+    MODULE_PREFIX.with(|prefix| prefix.set(enabled));
+}
+
+fn prefixed<'a>(target: Option<&str>, msg: &'a str) -> Cow<'a, str> {
+    let module = if MODULE_PREFIX.with(Cell::get) {
+        crate::current_module()
+    } else {
+        None
+    };
+
+    SCOPES.with(|scopes| {
+        let scopes = scopes.borrow();
+        if module.is_none() && target.is_none() && scopes.is_empty() {
+            return Cow::Borrowed(msg);
+        }
+
+        let mut parts = Vec::with_capacity(scopes.len() + 3);
+        if let Some(module) = module {
+            parts.push(format!("module={}", module));
+        }
+        if let Some(target) = target {
+            parts.push(format!("target={}", target));
+        }
+        parts.extend(scopes.iter().cloned());
+        parts.push(msg.to_owned());
+        Cow::Owned(parts.join(" "))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn it_prefixes_logs_within_scope() {
+        reset_scopes();
+        assert_eq!(prefixed(None, "start"), "start");
+
+        let decode = scope("decode");
+        assert_eq!(prefixed(None, "10 transfers"), "[decode] 10 transfers");
+        {
+            let _topics = scope("topics");
+            assert_eq!(prefixed(None, "2 topics"), "[decode] [topics] 2 topics");
+        }
+        assert_eq!(prefixed(None, "done"), "[decode] done");
+
+        drop(decode);
+        assert_eq!(prefixed(None, "end"), "end");
+    }
+
+    #[test]
+    fn it_prefixes_logs_with_module() {
+        reset_scopes();
+        crate::set_current_module(Some("map_transfers"));
+        assert_eq!(prefixed(None, "start"), "start");
+
+        prefix_module(true);
+        assert_eq!(prefixed(None, "start"), "module=map_transfers start");
+        let _decode = scope("decode");
+        assert_eq!(
+            prefixed(Some("rpc"), "call"),
+            "module=map_transfers target=rpc [decode] call"
+        );
+
+        crate::set_current_module(None);
+        assert_eq!(prefixed(None, "done"), "[decode] done");
+        prefix_module(false);
+    }
+
+    #[test]
+    fn it_ends_inner_scopes_with_outer_one() {
+        reset_scopes();
+        let outer = scope("outer");
+        let inner = scope("inner");
+
+        drop(outer);
+        assert_eq!(prefixed(None, "msg"), "msg");
+
+        drop(inner);
+        assert_eq!(prefixed(None, "msg"), "msg");
+    }
+
+    #[test]
+    fn it_resets_scopes() {
+        let scope = scope("leaked");
+        reset_scopes();
+        assert_eq!(prefixed(None, "msg"), "msg");
+
+        drop(scope);
+        assert_eq!(prefixed(None, "msg"), "msg");
+    }
+
+    #[test]
+    fn it_keeps_scopes_of_next_invocation_when_dropping_leaked_scope() {
+        reset_scopes();
+        let leaked = scope("leaked");
+        reset_scopes();
+
+        let _current = scope("current");
+        drop(leaked);
+        assert_eq!(prefixed(None, "msg"), "[current] msg");
+    }
+
+    #[test]
+    fn it_logs_every_n_calls() {
+        reset_samples();
+        let site = ("lib.rs", 10, 5);
+
+        let lines: Vec<String> = (0..25)
+            .filter_map(|i| sample(site, 10, || format!("call {}", i)))
+            .collect();
+        assert_eq!(
+            lines,
+            vec!["call 0", "call 10 (9 suppressed)", "call 20 (9 suppressed)"]
+        );
+
+        assert_eq!(
+            sample(("lib.rs", 11, 5), 10, || "other".to_string()),
+            Some("other".to_string())
+        );
+    }
+
+    #[test]
+    fn it_logs_every_call_without_suppressed_count() {
+        reset_samples();
+
+        for n in [0, 1] {
+            let site = ("lib.rs", 15, n as u32);
+            let lines: Vec<String> = (0..3)
+                .filter_map(|i| sample(site, n, || format!("call {}", i)))
+                .collect();
+            assert_eq!(lines, vec!["call 0", "call 1", "call 2"], "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn it_logs_once_per_invocation() {
+        reset_samples();
+        let site = ("lib.rs", 20, 5);
+
+        assert_eq!(
+            sample(site, u64::MAX, || "first".to_string()),
+            Some("first".to_string())
+        );
+        assert_eq!(sample(site, u64::MAX, || unreachable!()), None);
+
+        reset_samples();
+        assert_eq!(
+            sample(site, u64::MAX, || "again".to_string()),
+            Some("again".to_string())
+        );
+    }
+
+    #[test]
+    fn it_tags_logs_with_target() {
+        reset_scopes();
+        assert_eq!(
+            prefixed(Some("transfers"), "found 3"),
+            "target=transfers found 3"
+        );
+        assert!(!prefixed(None, "found 3").contains("target="));
+
+        let _decode = scope("decode");
+        assert_eq!(
+            prefixed(Some("transfers"), "found 3"),
+            "target=transfers [decode] found 3"
+        );
+        assert_eq!(prefixed(None, "found 3"), "[decode] found 3");
+    }
+}
@@ -80,6 +80,24 @@ pub fn read_u32_from_heap(output_ptr: *mut u8, len: usize) -> u32 {
     }
 }
 
+/// Returns a view over the raw bytes of a handler input, `ptr` and `len` being the
+/// ones received from the host for this input. Handlers declaring an argument of type
+/// `&[u8]` get this view generated for them by the handler macros.
+///
+/// # Safety
+///
+/// `ptr` and `len` must come from the host for an input of the current handler
+/// invocation. The returned slice is only valid for the duration of that invocation
+/// and must not be kept around after the handler returns. An empty input is read as an
+/// empty slice whatever `ptr` is, the host may pass a null pointer for it.
+pub unsafe fn input_as_slice<'a>(ptr: *mut u8, len: usize) -> &'a [u8] {
+    // `from_raw_parts` requires a non-null pointer even for an empty slice
+    if len == 0 {
+        return &[];
+    }
+    slice::from_raw_parts(ptr, len)
+}
+
 pub fn get_output_data(output_ptr: *mut u8) -> Vec<u8> {
     unsafe {
         let value_ptr: u32 = read_u32_from_heap(output_ptr, 4);
@@ -94,3 +112,21 @@ pub fn get_output_data(output_ptr: *mut u8) -> Vec<u8> {
         ret
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::memory::input_as_slice;
+
+    #[test]
+    fn it_reads_empty_input_from_null_pointer() {
+        let input = unsafe { input_as_slice(std::ptr::null_mut(), 0) };
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn it_reads_input() {
+        let mut data = vec![0xbc, 0x4c];
+        let input = unsafe { input_as_slice(data.as_mut_ptr(), data.len()) };
+        assert_eq!(input, &[0xbc, 0x4c]);
+    }
+}
@@ -0,0 +1,27 @@
+//! Module helpers for Substreams.
+//!
+//! This crate offers helpers to introspect the module being executed,
+//! as declared in the Manifest.
+//!
+
+use crate::{externs, memory};
+
+/// Returns the initial block of the module, the `initialBlock` declared for it in the
+/// Manifest, or inherited from its inputs when not declared. Returns 0 when no initial
+/// block is set for the module nor any of its inputs.
+pub fn start_block() -> u64 {
+    unsafe { externs::start_block() }
+}
+
+/// Returns the raw parameters given to the module by the host, so the same compiled module
+/// can be run with different settings, like the contract it tracks. Empty when the module
+/// has no parameters, which is always the case when run by the pipeline for now. A handler
+/// usually takes them decoded as its first `params` argument instead, see
+/// [crate::handlers::map].
+pub fn params() -> Vec<u8> {
+    unsafe {
+        let output_ptr = memory::alloc(8);
+        externs::params(output_ptr as u32);
+        memory::get_output_data(output_ptr)
+    }
+}
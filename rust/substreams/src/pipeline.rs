@@ -0,0 +1,159 @@
+//! Pipeline helpers for Substreams.
+//!
+//! This crate offers a small combinator API to express multi-stage transformations
+//! over the decoded input of a handler. Each stage is a thin layer over the
+//! standard library iterator adapters, items flow through lazily and the first
+//! error stops the whole pipeline when collecting.
+//!
+//! # Examples
+//!
+//! ```
+//! use substreams::errors::Error;
+//!
+//! let even_squares: Result<Vec<u64>, Error> = substreams::pipeline(vec![1u64, 2, 3, 4])
+//!     .filter(|v| v % 2 == 0)
+//!     .map(|v| v * v)
+//!     .collect();
+//!
+//! assert_eq!(even_squares.unwrap(), vec![4, 16]);
+//! ```
+
+use crate::errors::Error;
+use std::iter::FromIterator;
+
+/// Pipeline wraps an iterator of `Result<T, Error>` items, created through
+/// [pipeline]. Values already in error are carried as-is through every stage
+/// until [Pipeline::collect] is called.
+pub struct Pipeline<I> {
+    iter: I,
+}
+
+/// Starts a new pipeline over the given input.
+pub fn pipeline<T: IntoIterator>(
+    input: T,
+) -> Pipeline<impl Iterator<Item = Result<T::Item, Error>>> {
+    Pipeline {
+        iter: input.into_iter().map(Ok),
+    }
+}
+
+impl<T, I> Pipeline<I>
+where
+    I: Iterator<Item = Result<T, Error>>,
+{
+    /// Transforms each value with `f`.
+    pub fn map<B, F>(self, mut f: F) -> Pipeline<impl Iterator<Item = Result<B, Error>>>
+    where
+        F: FnMut(T) -> B,
+    {
+        Pipeline {
+            iter: self.iter.map(move |item| item.map(&mut f)),
+        }
+    }
+
+    /// Transforms each value with the fallible `f`, an error returned by `f` is
+    /// carried down the pipeline.
+    pub fn try_map<B, F>(self, mut f: F) -> Pipeline<impl Iterator<Item = Result<B, Error>>>
+    where
+        F: FnMut(T) -> Result<B, Error>,
+    {
+        Pipeline {
+            iter: self.iter.map(move |item| item.and_then(&mut f)),
+        }
+    }
+
+    /// Keeps only the values for which `predicate` returns `true`.
+    pub fn filter<P>(self, mut predicate: P) -> Pipeline<impl Iterator<Item = Result<T, Error>>>
+    where
+        P: FnMut(&T) -> bool,
+    {
+        Pipeline {
+            iter: self.iter.filter(move |item| match item {
+                Ok(value) => predicate(value),
+                Err(_) => true,
+            }),
+        }
+    }
+
+    /// Transforms and filters values in a single stage, values for which `f`
+    /// returns `None` are dropped.
+    pub fn filter_map<B, F>(self, mut f: F) -> Pipeline<impl Iterator<Item = Result<B, Error>>>
+    where
+        F: FnMut(T) -> Option<B>,
+    {
+        Pipeline {
+            iter: self.iter.filter_map(move |item| match item {
+                Ok(value) => f(value).map(Ok),
+                Err(e) => Some(Err(e)),
+            }),
+        }
+    }
+
+    /// Consumes the pipeline, returning the first error encountered or the
+    /// collection of all values.
+    pub fn collect<B: FromIterator<T>>(self) -> Result<B, Error> {
+        self.iter.collect()
+    }
+}
+
+impl<T, I> Iterator for Pipeline<I>
+where
+    I: Iterator<Item = Result<T, Error>>,
+{
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::Error;
+    use crate::pipeline::pipeline;
+
+    #[test]
+    fn it_filters_and_maps() {
+        let out: Vec<String> = pipeline(vec![1, 2, 3, 4, 5])
+            .filter(|v| v % 2 == 1)
+            .map(|v| format!("item:{}", v))
+            .collect()
+            .unwrap();
+
+        assert_eq!(out, vec!["item:1", "item:3", "item:5"]);
+    }
+
+    #[test]
+    fn it_filter_maps() {
+        let out: Vec<u8> = pipeline(vec!["1", "x", "3"])
+            .filter_map(|v| v.parse::<u8>().ok())
+            .collect()
+            .unwrap();
+
+        assert_eq!(out, vec![1, 3]);
+    }
+
+    #[test]
+    fn it_propagates_first_error() {
+        let mut seen = vec![];
+        let out: Result<Vec<u32>, Error> = pipeline(vec![1, 2, 3, 4])
+            .map(|v| {
+                seen.push(v);
+                v
+            })
+            .try_map(|v| {
+                if v == 2 {
+                    return Err(Error::Unexpected(format!("bad value {}", v)));
+                }
+                Ok(v * 10)
+            })
+            .filter(|v| *v > 0)
+            .collect();
+
+        match out {
+            Err(Error::Unexpected(msg)) => assert_eq!(msg, "bad value 2"),
+            other => panic!("expected error, got {:?}", other),
+        }
+        assert_eq!(seen, vec![1, 2]);
+    }
+}
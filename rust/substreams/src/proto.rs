@@ -4,13 +4,56 @@
 //! are used across Substreams
 //!
 
+use prost::encoding::{decode_key, decode_varint, WireType};
 use prost::{DecodeError, EncodeError};
+use std::marker::PhantomData;
 
 /// Given an array of bytes, it will decode data in a Protobuf Message
 pub fn decode<T: std::default::Default + prost::Message>(buf: &Vec<u8>) -> Result<T, DecodeError> {
     ::prost::Message::decode(&buf[..])
 }
 
+/// Given an array of bytes, it will decode data in an existing Protobuf Message, reusing
+/// its allocations. `msg` is cleared first, with `prost::Message::clear`, so no field of
+/// its previous value is kept. On error, `msg` is left in an unspecified state.
+///
+/// Decoding each item of a hot loop into the same message saves allocating a new message,
+/// and its repeated fields, for every item.
+///
+/// # Examples
+///
+/// ```no_run
+/// # mod pb { pub type Transfer = prost_types::Any; }
+/// # let encoded_transfers: Vec<Vec<u8>> = vec![];
+/// let mut transfer = pb::Transfer::default();
+/// for encoded in encoded_transfers {
+///     substreams::proto::decode_into(&encoded, &mut transfer).unwrap();
+///     // use transfer
+/// }
+/// ```
+pub fn decode_into<T: prost::Message>(buf: &[u8], msg: &mut T) -> Result<(), DecodeError> {
+    msg.clear();
+    msg.merge(buf)
+}
+
+/// Same as [decode_into], kept for existing callers.
+pub fn decode_reuse<T: prost::Message>(buf: &[u8], into: &mut T) -> Result<(), DecodeError> {
+    decode_into(buf, into)
+}
+
+/// Given an array of bytes starting with a length-delimited Protobuf message, like the
+/// ones written by `prost::Message::encode_length_delimited`, it will decode that first
+/// message and return it along with the number of bytes consumed, length prefix included.
+/// The rest of the buffer is left untouched, the next message starts at the returned offset.
+pub fn decode_prefix<T: std::default::Default + prost::Message>(
+    buf: &[u8],
+) -> Result<(T, usize), DecodeError> {
+    let mut remaining = buf;
+    let msg = T::decode_length_delimited(&mut remaining)?;
+
+    Ok((msg, buf.len() - remaining.len()))
+}
+
 /// Given a pointer to a byte array, it will read and decode the data in a Protobuf message.
 pub fn decode_ptr<T: std::default::Default + prost::Message>(
     ptr: *mut u8,
@@ -24,6 +67,17 @@ pub fn decode_ptr<T: std::default::Default + prost::Message>(
     }
 }
 
+/// Given a pointer to a byte array, it will read and decode the data in an existing Protobuf
+/// message like [decode_into] does, `msg` is cleared first.
+pub fn decode_ptr_into<T: prost::Message>(
+    ptr: *mut u8,
+    size: usize,
+    msg: &mut T,
+) -> Result<(), DecodeError> {
+    let input_data = unsafe { crate::memory::input_as_slice(ptr, size) };
+    decode_into(input_data, msg)
+}
+
 /// Given a Protobuf message it will encode it and return the byte array.
 pub fn encode<M: prost::Message>(msg: &M) -> Result<Vec<u8>, EncodeError> {
     let mut buf = Vec::new();
@@ -37,6 +91,29 @@ pub fn encode<M: prost::Message>(msg: &M) -> Result<Vec<u8>, EncodeError> {
     }
 }
 
+/// Given a Protobuf message it will encode it in `buf`, which is cleared first, like
+/// [decode_into] it allows reusing the same buffer to encode many messages, for example to
+/// write each of them to a [StoreSet](crate::store::StoreSet).
+///
+/// # Examples
+///
+/// ```no_run
+/// # mod pb { pub type Transfer = prost_types::Any; }
+/// # let transfers: Vec<pb::Transfer> = vec![];
+/// # let store = substreams::store::StoreSet::new();
+/// let mut buf = Vec::new();
+/// for (i, transfer) in transfers.iter().enumerate() {
+///     substreams::proto::encode_to(transfer, &mut buf).unwrap();
+///     store.set(0, format!("transfer:{}", i), &buf);
+/// }
+/// ```
+pub fn encode_to<M: prost::Message>(msg: &M, buf: &mut Vec<u8>) -> Result<(), EncodeError> {
+    buf.clear();
+    buf.reserve(msg.encoded_len());
+
+    msg.encode(buf)
+}
+
 /// Given a Protobuf message it will encode it and return a pointer to the byte array
 pub fn encode_to_ptr<M: prost::Message>(
     msg: &M,
@@ -46,3 +123,347 @@ pub fn encode_to_ptr<M: prost::Message>(
         Err(e) => Err(e),
     }
 }
+
+/// RepeatedBuilder incrementally builds the encoded bytes of a message made of a single
+/// repeated message field (like a `Transfers` message wrapping `repeated Transfer`).
+///
+/// Each entry is encoded as soon as it's pushed, so the entries don't need to be collected
+/// in an intermediate `Vec` before building the output message. Only the encoded bytes are
+/// kept in memory, which is usually much smaller than the decoded entries. The resulting
+/// bytes can be emitted with [crate::output_raw].
+///
+/// # Examples
+///
+/// ```no_run
+/// # mod pb { pub type Transfer = (); }
+/// let mut transfers = substreams::proto::RepeatedBuilder::<pb::Transfer>::new(1);
+/// for _ in 0..3 {
+///     transfers.push(&());
+/// }
+///
+/// substreams::output_raw(transfers.finish());
+/// ```
+pub struct RepeatedBuilder<M> {
+    tag: u32,
+    buf: Vec<u8>,
+    len: usize,
+    entry: PhantomData<M>,
+}
+
+impl<M: prost::Message> RepeatedBuilder<M> {
+    /// Returns a builder for the repeated field with protobuf tag `tag`.
+    pub fn new(tag: u32) -> RepeatedBuilder<M> {
+        RepeatedBuilder::with_capacity(tag, 0)
+    }
+
+    /// Returns a builder for the repeated field with protobuf tag `tag` with an
+    /// encoding buffer pre-allocated to `capacity` bytes.
+    pub fn with_capacity(tag: u32, capacity: usize) -> RepeatedBuilder<M> {
+        RepeatedBuilder {
+            tag,
+            buf: Vec::with_capacity(capacity),
+            len: 0,
+            entry: PhantomData,
+        }
+    }
+
+    /// Encodes and appends `entry` to the repeated field.
+    pub fn push(&mut self, entry: &M) {
+        prost::encoding::message::encode(self.tag, entry, &mut self.buf);
+        self.len += 1;
+    }
+
+    /// Returns the number of entries pushed so far.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no entry was pushed yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the encoded message.
+    pub fn finish(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// FieldReader reads individual top level fields of an encoded Protobuf message without
+/// decoding the whole message.
+///
+/// The buffer is scanned on each read and only the requested field is decoded, the other
+/// fields are skipped over without being decoded nor allocated. It's meant for handlers
+/// touching one or two fields of a very large input, like only the `transaction_traces`
+/// of a block taken as raw bytes (a `&[u8]` handler input). When most of the fields are
+/// needed, a plain `decode` is cheaper as each read scans the whole buffer again.
+///
+/// # Examples
+///
+/// ```no_run
+/// # mod eth { pub type TransactionTrace = prost_types::Any; }
+/// # let block_raw: &[u8] = &[];
+/// let block = substreams::proto::FieldReader::new(block_raw);
+/// let transaction_traces: Vec<eth::TransactionTrace> = block.repeated(10).unwrap();
+/// ```
+pub struct FieldReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> FieldReader<'a> {
+    /// Returns a reader over the encoded message `buf`.
+    pub fn new(buf: &'a [u8]) -> FieldReader<'a> {
+        FieldReader { buf }
+    }
+
+    /// Returns the payload of each occurrence of the length-delimited field `tag` (a
+    /// string, bytes, message or packed repeated field), in encoding order, without copy.
+    pub fn bytes(&self, tag: u32) -> Result<Vec<&'a [u8]>, DecodeError> {
+        let mut values = Vec::new();
+        self.for_each_field(|field_tag, wire_type, value| {
+            if field_tag == tag {
+                if wire_type != WireType::LengthDelimited {
+                    return Err(DecodeError::new(format!(
+                        "field {} is not length-delimited",
+                        tag
+                    )));
+                }
+                values.push(value);
+            }
+            Ok(())
+        })?;
+
+        Ok(values)
+    }
+
+    /// Decodes the message field `tag`, `None` if the field is absent. Like a full decode
+    /// would, multiple occurrences of the field are merged together.
+    pub fn message<T: Default + prost::Message>(&self, tag: u32) -> Result<Option<T>, DecodeError> {
+        let occurrences = self.bytes(tag)?;
+        if occurrences.is_empty() {
+            return Ok(None);
+        }
+
+        let mut msg = T::default();
+        for value in occurrences {
+            msg.merge(value)?;
+        }
+
+        Ok(Some(msg))
+    }
+
+    /// Decodes each entry of the repeated message field `tag`, in order.
+    pub fn repeated<T: Default + prost::Message>(&self, tag: u32) -> Result<Vec<T>, DecodeError> {
+        self.bytes(tag)?.into_iter().map(T::decode).collect()
+    }
+
+    fn for_each_field<F>(&self, mut f: F) -> Result<(), DecodeError>
+    where
+        F: FnMut(u32, WireType, &'a [u8]) -> Result<(), DecodeError>,
+    {
+        let mut buf = self.buf;
+        while !buf.is_empty() {
+            let (tag, wire_type) = decode_key(&mut buf)?;
+            let len = match wire_type {
+                WireType::Varint => match buf.iter().take(10).position(|b| b & 0x80 == 0) {
+                    Some(last) => last + 1,
+                    None => return Err(DecodeError::new("invalid varint")),
+                },
+                WireType::SixtyFourBit => 8,
+                WireType::ThirtyTwoBit => 4,
+                WireType::LengthDelimited => decode_varint(&mut buf)? as usize,
+                WireType::StartGroup | WireType::EndGroup => {
+                    return Err(DecodeError::new("groups are not supported"))
+                }
+            };
+            if len > buf.len() {
+                return Err(DecodeError::new("buffer underflow"));
+            }
+
+            let (value, rest) = buf.split_at(len);
+            f(tag, wire_type, value)?;
+            buf = rest;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::proto;
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    struct Transfer {
+        #[prost(uint64, tag = "1")]
+        token_id: u64,
+        #[prost(bytes = "vec", tag = "2")]
+        to: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    struct Transfers {
+        #[prost(message, repeated, tag = "1")]
+        transfers: Vec<Transfer>,
+    }
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    struct Block {
+        #[prost(uint64, tag = "1")]
+        number: u64,
+        #[prost(fixed64, tag = "2")]
+        timestamp: u64,
+        #[prost(message, optional, tag = "3")]
+        header: Option<Transfer>,
+        #[prost(message, repeated, tag = "4")]
+        transfers: Vec<Transfer>,
+        #[prost(fixed32, tag = "5")]
+        size: u32,
+        #[prost(string, tag = "6")]
+        hash: String,
+    }
+
+    fn transfer(token_id: u64) -> Transfer {
+        Transfer {
+            token_id,
+            to: vec![(token_id % 256) as u8; 20],
+        }
+    }
+
+    #[test]
+    fn it_builds_repeated_field_incrementally() {
+        let mut builder = proto::RepeatedBuilder::<Transfer>::new(1);
+        for i in 0..50_000 {
+            builder.push(&transfer(i));
+        }
+        assert_eq!(builder.len(), 50_000);
+
+        let expected = Transfers {
+            transfers: (0..50_000).map(transfer).collect(),
+        };
+        let encoded = builder.finish();
+        assert_eq!(encoded, proto::encode(&expected).unwrap());
+        assert_eq!(proto::decode::<Transfers>(&encoded).unwrap(), expected);
+    }
+
+    #[test]
+    fn it_reads_single_field() {
+        let block = Block {
+            number: 300,
+            timestamp: 1_650_000_000,
+            header: Some(transfer(1)),
+            transfers: (0..1_000).map(transfer).collect(),
+            size: 42,
+            hash: "0xabcd".to_owned(),
+        };
+        let encoded = proto::encode(&block).unwrap();
+        let reader = proto::FieldReader::new(&encoded);
+
+        assert_eq!(reader.repeated::<Transfer>(4).unwrap(), block.transfers);
+        assert_eq!(reader.message::<Transfer>(3).unwrap(), block.header);
+        assert_eq!(reader.bytes(6).unwrap(), vec![b"0xabcd".as_ref()]);
+        assert_eq!(reader.message::<Transfer>(7).unwrap(), None);
+        assert!(reader.repeated::<Transfer>(7).unwrap().is_empty());
+        assert!(reader.bytes(1).is_err());
+    }
+
+    #[test]
+    fn it_rejects_truncated_message() {
+        let encoded = proto::encode(&Block {
+            transfers: vec![transfer(1)],
+            hash: "0xabcd".to_owned(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let reader = proto::FieldReader::new(&encoded[..encoded.len() - 1]);
+        assert!(reader.repeated::<Transfer>(4).is_err());
+    }
+
+    #[test]
+    fn it_decodes_reusing_instance() {
+        let encoded: Vec<Vec<u8>> = (0..1_000u64)
+            .map(|i| {
+                proto::encode(&Transfers {
+                    transfers: (0..(i % 4)).map(transfer).collect(),
+                })
+                .unwrap()
+            })
+            .collect();
+
+        let mut reused = Transfers::default();
+        for (i, buf) in encoded.iter().enumerate() {
+            proto::decode_reuse(buf, &mut reused).unwrap();
+            assert_eq!(reused.transfers.len(), i % 4);
+            assert_eq!(reused, proto::decode::<Transfers>(buf).unwrap());
+        }
+    }
+
+    #[test]
+    fn it_decodes_into_same_message_many_times() {
+        let block = Block {
+            number: 300,
+            header: Some(transfer(1)),
+            transfers: (0..100).map(transfer).collect(),
+            hash: "0xabcd".to_owned(),
+            ..Default::default()
+        };
+        let mut encoded = proto::encode(&block).unwrap();
+
+        let mut reused = Block::default();
+        for _ in 0..10_000 {
+            proto::decode_into(&encoded, &mut reused).unwrap();
+        }
+        assert_eq!(reused, block);
+
+        reused.transfers.push(transfer(500));
+        proto::decode_ptr_into(encoded.as_mut_ptr(), encoded.len(), &mut reused).unwrap();
+        assert_eq!(reused, block);
+
+        proto::decode_into(&[], &mut reused).unwrap();
+        assert_eq!(reused, Block::default());
+    }
+
+    #[test]
+    fn it_decodes_empty_input_from_null_pointer() {
+        let mut reused = Block {
+            number: 300,
+            ..Default::default()
+        };
+
+        proto::decode_ptr_into(std::ptr::null_mut(), 0, &mut reused).unwrap();
+        assert_eq!(reused, Block::default());
+    }
+
+    #[test]
+    fn it_round_trips_encoded_message() {
+        let mut buf = vec![0xff; 64];
+        for i in 0..100 {
+            proto::encode_to(&transfer(i), &mut buf).unwrap();
+            assert_eq!(buf, proto::encode(&transfer(i)).unwrap());
+            assert_eq!(proto::decode::<Transfer>(&buf).unwrap(), transfer(i));
+        }
+
+        proto::encode_to(&Transfer::default(), &mut buf).unwrap();
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn it_decodes_prefix_of_concatenated_messages() {
+        let mut buf = Vec::new();
+        for i in 1..=3 {
+            prost::Message::encode_length_delimited(&transfer(i), &mut buf).unwrap();
+        }
+
+        let (first, offset) = proto::decode_prefix::<Transfer>(&buf).unwrap();
+        assert_eq!(first, transfer(1));
+        // 1 byte length prefix, 2 bytes token_id, 22 bytes to
+        assert_eq!(offset, 25);
+
+        let (second, second_len) = proto::decode_prefix::<Transfer>(&buf[offset..]).unwrap();
+        assert_eq!(second, transfer(2));
+        assert_eq!(offset + second_len, 50);
+
+        assert!(proto::decode_prefix::<Transfer>(&buf[..10]).is_err());
+    }
+}
@@ -1,9 +1,217 @@
 use crate::externs;
 use crate::memory;
+use crate::pb;
+use crate::proto;
+use crate::store::Ordinal;
 use bigdecimal::BigDecimal;
 use num_bigint::BigInt;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+macro_rules! count_call {
+    ($name:expr) => {
+        #[cfg(feature = "call-stats")]
+        crate::stats::record($name);
+    };
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum CachedRead {
+    First,
+    Last,
+}
+
+type ReadCache = HashMap<(CachedRead, u32, String), Option<Vec<u8>>>;
+
+thread_local! {
+    // `None` when the cache is disabled
+    static READ_CACHE: RefCell<Option<ReadCache>> = RefCell::new(None);
+}
+
+/// Enables or disables the read cache of `get_last` and `get_first`: once enabled, the
+/// first read of a key of a store goes to the host and the following identical reads
+/// return the same value without a host call. The cache only lives for the current
+/// handler invocation, it's emptied when a handler starts, and it stays enabled until
+/// disabled. Reads at an ordinal, like `get_at`, are never cached.
+///
+/// Input stores don't change while a handler runs, so the cache is only worth it for
+/// handlers reading the same keys many times, like the decimals of a token for each of
+/// its transfers: every read value is kept in memory until the handler returns.
+pub fn with_read_cache(enabled: bool) {
+    READ_CACHE.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        match (enabled, cache.is_some()) {
+            (true, false) => *cache = Some(HashMap::new()),
+            (false, true) => *cache = None,
+            _ => {}
+        }
+    });
+}
+
+pub(crate) fn reset_read_cache() {
+    READ_CACHE.with(|cache| {
+        if let Some(cache) = cache.borrow_mut().as_mut() {
+            cache.clear();
+        }
+    });
+}
+
+fn cached_read<F: FnOnce() -> Option<Vec<u8>>>(
+    read: CachedRead,
+    store_idx: u32,
+    key: &str,
+    fetch: F,
+) -> Option<Vec<u8>> {
+    let cache_key = (read, store_idx, key.to_owned());
+    let cached = READ_CACHE.with(|cache| {
+        cache
+            .borrow()
+            .as_ref()
+            .map(|cache| cache.get(&cache_key).cloned())
+    });
+
+    match cached {
+        // cache disabled
+        None => fetch(),
+        Some(Some(value)) => value,
+        Some(None) => {
+            let value = fetch();
+            READ_CACHE.with(|cache| {
+                if let Some(cache) = cache.borrow_mut().as_mut() {
+                    cache.insert(cache_key, value.clone());
+                }
+            });
+            value
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum BufferedOp {
+    Set,
+    AddInt64,
+    AddBigInt,
+}
+
+struct BufferedWrite {
+    op: BufferedOp,
+    ord: u64,
+    key: String,
+    value: Vec<u8>,
+}
+
+thread_local! {
+    // `None` when writes are sent to the host right away
+    static WRITE_BUFFER: RefCell<Option<Vec<BufferedWrite>>> = RefCell::new(None);
+}
+
+/// Enables or disables the buffering of the writes of the output store: once enabled, the
+/// `set` of a `set` store and the `add` of `int64` and `bigint` stores are queued, and sent
+/// to the host with a single batch host call per run of consecutive writes of the same
+/// kind, instead of one host call each. The queue is sent by [flush], which the handler
+/// macros call when the handler body returns, before a `map` output is emitted, and
+/// before any other write or read of the output store so the host applies all the writes
+/// in the order they were made.
+/// Buffering stays enabled until disabled, disabling it flushes the queue.
+///
+/// Buffered writes are only worth it for handlers making thousands of writes per block,
+/// they're kept in memory until flushed.
+pub fn set_buffered(enabled: bool) {
+    if !enabled {
+        flush();
+    }
+    toggle_write_buffer(enabled);
+}
+
+/// Sends the writes queued since buffering was enabled with [set_buffered] to the host,
+/// in the order they were made. Does nothing when writes are not buffered.
+pub fn flush() {
+    flush_with(send_batch);
+}
+
+fn toggle_write_buffer(enabled: bool) {
+    WRITE_BUFFER.with(|buffer| {
+        let mut buffer = buffer.borrow_mut();
+        match (enabled, buffer.is_some()) {
+            (true, false) => *buffer = Some(vec![]),
+            (false, true) => *buffer = None,
+            _ => {}
+        }
+    });
+}
+
+// Drops the writes queued by a previous invocation, they can't be applied to the output
+// store of the current one.
+pub(crate) fn reset_write_buffer() {
+    WRITE_BUFFER.with(|buffer| {
+        if let Some(writes) = buffer.borrow_mut().as_mut() {
+            writes.clear();
+        }
+    });
+}
+
+// Queues a write and returns `true` when writes are buffered, the caller sends the write
+// itself otherwise.
+fn buffer_write<F: FnOnce() -> Vec<u8>>(op: BufferedOp, ord: i64, key: &str, value: F) -> bool {
+    WRITE_BUFFER.with(|buffer| match buffer.borrow_mut().as_mut() {
+        Some(writes) => {
+            writes.push(BufferedWrite {
+                op,
+                ord: ord as u64,
+                key: key.to_owned(),
+                value: value(),
+            });
+            true
+        }
+        None => false,
+    })
+}
+
+fn flush_with<F: FnMut(BufferedOp, &[BufferedWrite])>(mut send: F) {
+    let writes = WRITE_BUFFER.with(|buffer| {
+        buffer
+            .borrow_mut()
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    });
+
+    let mut start = 0;
+    for end in 1..=writes.len() {
+        if end == writes.len() || writes[end].op != writes[start].op {
+            send(writes[start].op, &writes[start..end]);
+            start = end;
+        }
+    }
+}
+
+fn send_batch(op: BufferedOp, writes: &[BufferedWrite]) {
+    let data = encode_batch(
+        writes
+            .iter()
+            .map(|write| (write.ord, write.key.as_str(), write.value.clone())),
+    );
+
+    unsafe {
+        match op {
+            BufferedOp::Set => {
+                count_call!("set_batch");
+                externs::state::set_batch(data.as_ptr(), data.len() as u32)
+            }
+            BufferedOp::AddInt64 => {
+                count_call!("add_int64_batch");
+                externs::state::add_int64_batch(data.as_ptr(), data.len() as u32)
+            }
+            BufferedOp::AddBigInt => {
+                count_call!("add_bigint_batch");
+                externs::state::add_bigint_batch(data.as_ptr(), data.len() as u32)
+            }
+        }
+    }
+}
 
 pub fn get_at<K: AsRef<str>>(store_idx: u32, ord: i64, key: K) -> Option<Vec<u8>> {
+    count_call!("get_at");
     let key = key.as_ref();
 
     unsafe {
@@ -23,8 +231,58 @@ pub fn get_at<K: AsRef<str>>(store_idx: u32, ord: i64, key: K) -> Option<Vec<u8>
         };
     }
 }
+pub fn get_at_relative<K: AsRef<str>>(store_idx: u32, back: u64, key: K) -> Option<Vec<u8>> {
+    count_call!("get_at_relative");
+    let key = key.as_ref();
+
+    unsafe {
+        let key_bytes = key.as_bytes();
+        let output_ptr = memory::alloc(8);
+        let found = externs::state::get_at_relative(
+            store_idx,
+            back,
+            key_bytes.as_ptr(),
+            key_bytes.len() as u32,
+            output_ptr as u32,
+        );
+        return if found == 1 {
+            Some(memory::get_output_data(output_ptr))
+        } else {
+            None
+        };
+    }
+}
+
+pub fn get_at_block<K: AsRef<str>>(store_idx: u32, block_num: u64, key: K) -> Option<Vec<u8>> {
+    count_call!("get_at_block");
+    let key = key.as_ref();
+
+    unsafe {
+        let key_bytes = key.as_bytes();
+        let output_ptr = memory::alloc(8);
+        let found = externs::state::get_at_block(
+            store_idx,
+            block_num,
+            key_bytes.as_ptr(),
+            key_bytes.len() as u32,
+            output_ptr as u32,
+        );
+        return if found == 1 {
+            Some(memory::get_output_data(output_ptr))
+        } else {
+            None
+        };
+    }
+}
 pub fn get_last<K: AsRef<str>>(store_idx: u32, key: K) -> Option<Vec<u8>> {
     let key = key.as_ref();
+    cached_read(CachedRead::Last, store_idx, key, || {
+        fetch_last(store_idx, key)
+    })
+}
+
+fn fetch_last(store_idx: u32, key: &str) -> Option<Vec<u8>> {
+    count_call!("get_last");
 
     unsafe {
         let key_bytes = key.as_bytes();
@@ -43,8 +301,88 @@ pub fn get_last<K: AsRef<str>>(store_idx: u32, key: K) -> Option<Vec<u8>> {
         };
     }
 }
+
+/// Returns the value of a key like `get_last` along with the ordinal it was written at in
+/// the current block, 0 when it was written in a previous block.
+pub fn get_last_with_ord<K: AsRef<str>>(store_idx: u32, key: K) -> Option<(u64, Vec<u8>)> {
+    count_call!("get_last_with_ord");
+    let key = key.as_ref();
+
+    unsafe {
+        let key_bytes = key.as_bytes();
+        let output_ptr = memory::alloc(8);
+        let ord = externs::state::get_last_with_ord(
+            store_idx,
+            key_bytes.as_ptr(),
+            key_bytes.len() as u32,
+            output_ptr as u32,
+        );
+
+        return if ord >= 0 {
+            Some((ord as u64, memory::get_output_data(output_ptr)))
+        } else {
+            None
+        };
+    }
+}
+/// Returns the length of the value of a key as `get_last` would read it, without sending
+/// the value back, or `None` if the key is absent.
+pub fn get_last_len<K: AsRef<str>>(store_idx: u32, key: K) -> Option<u64> {
+    count_call!("get_last_len");
+    let key = key.as_ref();
+
+    let len = unsafe { externs::state::get_last_len(store_idx, key.as_ptr(), key.len() as u32) };
+    if len < 0 {
+        return None;
+    }
+    Some(len as u64)
+}
+
+/// Returns at most `len` bytes of the value of a key starting at `offset`, or `None` if
+/// the key is absent or `offset` is past the end of its value.
+pub fn get_last_range<K: AsRef<str>>(
+    store_idx: u32,
+    key: K,
+    offset: u64,
+    len: u64,
+) -> Option<Vec<u8>> {
+    count_call!("get_last_range");
+    let key = key.as_ref();
+
+    unsafe {
+        let key_bytes = key.as_bytes();
+        let output_ptr = memory::alloc(8);
+        let found = externs::state::get_last_range(
+            store_idx,
+            key_bytes.as_ptr(),
+            key_bytes.len() as u32,
+            offset,
+            len,
+            output_ptr as u32,
+        );
+
+        return if found == 1 {
+            Some(memory::get_output_data(output_ptr))
+        } else {
+            None
+        };
+    }
+}
+
+pub fn is_store_bound(store_idx: u32) -> bool {
+    count_call!("store_bound");
+    unsafe { externs::state::store_bound(store_idx) == 1 }
+}
+
 pub fn get_first<K: AsRef<str>>(store_idx: u32, key: K) -> Option<Vec<u8>> {
     let key = key.as_ref();
+    cached_read(CachedRead::First, store_idx, key, || {
+        fetch_first(store_idx, key)
+    })
+}
+
+fn fetch_first(store_idx: u32, key: &str) -> Option<Vec<u8>> {
+    count_call!("get_first");
 
     unsafe {
         let key_bytes = key.as_bytes();
@@ -63,8 +401,156 @@ pub fn get_first<K: AsRef<str>>(store_idx: u32, key: K) -> Option<Vec<u8>> {
         };
     }
 }
+
+// Keys are sent as `StoreDeltas` with only the key set, the host sends back the keys found
+// with their value, in the same format.
+pub fn get_last_many<K: AsRef<str>>(store_idx: u32, keys: &[K]) -> HashMap<String, Vec<u8>> {
+    count_call!("get_last_many");
+    let data = encode_batch(keys.iter().map(|key| (0, key.as_ref(), vec![])));
+
+    unsafe {
+        let output_ptr = memory::alloc(8);
+        let found = externs::state::get_last_many(
+            store_idx,
+            data.as_ptr(),
+            data.len() as u32,
+            output_ptr as u32,
+        );
+        if found != 1 {
+            return HashMap::new();
+        }
+
+        let entries: pb::substreams::StoreDeltas =
+            proto::decode(&memory::get_output_data(output_ptr))
+                .expect("host sent invalid get_last_many entries");
+        entries
+            .deltas
+            .into_iter()
+            .map(|entry| (entry.key, entry.new_value))
+            .collect()
+    }
+}
+
+/// Reads a key of the module's own output store as it was at the end of the
+/// previous block.
+pub fn get_previous_block<K: AsRef<str>>(key: K) -> Option<Vec<u8>> {
+    count_call!("get_previous_block");
+    let key = key.as_ref();
+
+    unsafe {
+        let key_bytes = key.as_bytes();
+        let output_ptr = memory::alloc(8);
+        let found = externs::state::get_previous_block(
+            key_bytes.as_ptr(),
+            key_bytes.len() as u32,
+            output_ptr as u32,
+        );
+
+        return if found == 1 {
+            Some(memory::get_output_data(output_ptr))
+        } else {
+            None
+        };
+    }
+}
+
+/// Returns the value of a key in the output store of the module as of ordinal `ord`,
+/// including the writes made at `ord` earlier in the block.
+pub fn get_output_at<K: AsRef<str>>(ord: i64, key: K) -> Option<Vec<u8>> {
+    count_call!("get_output_at");
+    flush();
+    let key = key.as_ref();
+
+    unsafe {
+        let key_bytes = key.as_bytes();
+        let output_ptr = memory::alloc(8);
+        let found = externs::state::get_output_at(
+            ord,
+            key_bytes.as_ptr(),
+            key_bytes.len() as u32,
+            output_ptr as u32,
+        );
+
+        return if found == 1 {
+            Some(memory::get_output_data(output_ptr))
+        } else {
+            None
+        };
+    }
+}
+
+/// Returns the length of the value of a key of the output store as of ordinal `ord`, or
+/// `None` if the key is absent.
+pub fn get_output_len<K: AsRef<str>>(ord: i64, key: K) -> Option<u64> {
+    count_call!("get_output_len");
+    flush();
+    let key = key.as_ref();
+
+    let len = unsafe { externs::state::get_output_len(ord, key.as_ptr(), key.len() as u32) };
+    if len < 0 {
+        return None;
+    }
+    Some(len as u64)
+}
+
+/// Returns the key/value pairs, sorted lexicographically by key, of all the keys
+/// starting with `prefix` as of ordinal `ord`. Use `i64::MAX` to scan the last state
+/// of the store.
+pub fn scan_prefix<K: AsRef<str>>(store_idx: u32, ord: i64, prefix: K) -> Vec<(String, Vec<u8>)> {
+    count_call!("scan_prefix");
+    let prefix = prefix.as_ref();
+
+    unsafe {
+        let output_ptr = memory::alloc(8);
+        let found = externs::state::scan_prefix(
+            store_idx,
+            ord,
+            prefix.as_ptr(),
+            prefix.len() as u32,
+            output_ptr as u32,
+        );
+        if found != 1 {
+            return vec![];
+        }
+
+        let entries: pb::substreams::StoreDeltas =
+            proto::decode(&memory::get_output_data(output_ptr))
+                .expect("host sent invalid scan_prefix entries");
+        entries
+            .deltas
+            .into_iter()
+            .map(|entry| (entry.key, entry.new_value))
+            .collect()
+    }
+}
+
+pub fn count_prefix<K: AsRef<str>>(store_idx: u32, prefix: K) -> u64 {
+    count_call!("count_prefix");
+    let prefix = prefix.as_ref();
+
+    unsafe { externs::state::count_prefix(store_idx, prefix.as_ptr(), prefix.len() as u32) }
+}
+
+pub fn has_at<K: AsRef<str>>(store_idx: u32, ord: i64, key: K) -> bool {
+    count_call!("has_at");
+    let key = key.as_ref();
+
+    unsafe { externs::state::has_at(store_idx, ord, key.as_ptr(), key.len() as u32) == 1 }
+}
+
+pub fn has_last<K: AsRef<str>>(store_idx: u32, key: K) -> bool {
+    count_call!("has_last");
+    let key = key.as_ref();
+
+    unsafe { externs::state::has_last(store_idx, key.as_ptr(), key.len() as u32) == 1 }
+}
+
 pub fn set<K: AsRef<str>>(ord: i64, key: K, value: &Vec<u8>) {
     let key = key.as_ref();
+    if buffer_write(BufferedOp::Set, ord, key, || value.clone()) {
+        return;
+    }
+    count_call!("set");
 
     unsafe {
         externs::state::set(
@@ -77,6 +563,8 @@ pub fn set<K: AsRef<str>>(ord: i64, key: K, value: &Vec<u8>) {
     }
 }
 pub fn set_if_not_exists<K: AsRef<str>>(ord: i64, key: K, value: &Vec<u8>) {
+    count_call!("set_if_not_exists");
+    flush();
     let key = key.as_ref();
 
     unsafe {
@@ -89,8 +577,26 @@ pub fn set_if_not_exists<K: AsRef<str>>(ord: i64, key: K, value: &Vec<u8>) {
         )
     }
 }
+pub fn set_if_not_exists_reporting<K: AsRef<str>>(ord: i64, key: K, value: &Vec<u8>) -> bool {
+    count_call!("set_if_not_exists_reporting");
+    flush();
+    let key = key.as_ref();
+
+    unsafe {
+        let created = externs::state::set_if_not_exists_reporting(
+            ord,
+            key.as_ptr(),
+            key.len() as u32,
+            value.as_ptr(),
+            value.len() as u32,
+        );
+        created == 1
+    }
+}
 
 pub fn append<K: AsRef<str>>(ord: i64, key: K, value: &Vec<u8>) {
+    count_call!("append");
+    flush();
     let key = key.as_ref();
 
     unsafe {
@@ -104,14 +610,45 @@ pub fn append<K: AsRef<str>>(ord: i64, key: K, value: &Vec<u8>) {
     }
 }
 
+pub fn set_batch<O: Into<Ordinal> + Copy, K: AsRef<str>>(ops: &[(O, K, Vec<u8>)]) {
+    count_call!("set_batch");
+    flush();
+    let data = encode_batch(
+        ops.iter()
+            .map(|(ord, key, value)| ((*ord).into().value(), key.as_ref(), value.clone())),
+    );
+
+    unsafe { externs::state::set_batch(data.as_ptr(), data.len() as u32) }
+}
+
+pub fn delete<K: AsRef<str>>(ord: i64, key: K) {
+    count_call!("delete");
+    flush();
+    let key = key.as_ref();
+
+    unsafe { externs::state::delete(ord, key.as_ptr(), key.len() as u32) }
+}
+
 pub fn delete_prefix<K: AsRef<str>>(ord: i64, prefix: K) {
+    count_call!("delete_prefix");
+    flush();
     let prefix = prefix.as_ref();
 
     unsafe { externs::state::delete_prefix(ord, prefix.as_ptr(), prefix.len() as u32) }
 }
 
+pub fn clear(ord: i64) {
+    count_call!("clear");
+    flush();
+    unsafe { externs::state::clear(ord) }
+}
+
 pub fn add_bigint<K: AsRef<str>>(ord: i64, key: K, value: &BigInt)  {
     let key = key.as_ref();
+    if buffer_write(BufferedOp::AddBigInt, ord, key, || value.to_string().into_bytes()) {
+        return;
+    }
+    count_call!("add_bigint");
     let data = value.to_string();
 
     unsafe {
@@ -124,19 +661,108 @@ pub fn add_bigint<K: AsRef<str>>(ord: i64, key: K, value: &BigInt)  {
         )
     }
 }
+pub fn add_bigint_returning<K: AsRef<str>>(ord: i64, key: K, value: &BigInt) -> BigInt {
+    count_call!("add_bigint_returning");
+    flush();
+    let key = key.as_ref();
+    let data = value.to_string();
+
+    unsafe {
+        let output_ptr = memory::alloc(8);
+        externs::state::add_bigint_returning(
+            ord,
+            key.as_ptr(),
+            key.len() as u32,
+            data.as_ptr(),
+            data.len() as u32,
+            output_ptr as u32,
+        );
+
+        let total = memory::get_output_data(output_ptr);
+        match BigInt::parse_bytes(&total, 10) {
+            Some(total) => total,
+            None => panic!("total of key {} is not a valid bigint", key),
+        }
+    }
+}
+
+pub fn add_bigint_batch<O: Into<Ordinal> + Copy, K: AsRef<str>>(ops: &[(O, K, BigInt)]) {
+    count_call!("add_bigint_batch");
+    flush();
+    let data = encode_batch(ops.iter().map(|(ord, key, value)| {
+        (
+            (*ord).into().value(),
+            key.as_ref(),
+            value.to_string().into_bytes(),
+        )
+    }));
+
+    unsafe { externs::state::add_bigint_batch(data.as_ptr(), data.len() as u32) }
+}
+
 pub fn add_int64<K: AsRef<str>>(ord: i64, key: K, value: i64) {
     let key = key.as_ref();
+    if buffer_write(BufferedOp::AddInt64, ord, key, || value.to_string().into_bytes()) {
+        return;
+    }
+    count_call!("add_int64");
 
     unsafe { externs::state::add_int64(ord, key.as_ptr(), key.len() as u32, value) }
 }
 
+pub fn add_int64_batch<O: Into<Ordinal> + Copy, K: AsRef<str>>(ops: &[(O, K, i64)]) {
+    count_call!("add_int64_batch");
+    flush();
+    let data = encode_batch(ops.iter().map(|(ord, key, value)| {
+        (
+            (*ord).into().value(),
+            key.as_ref(),
+            value.to_string().into_bytes(),
+        )
+    }));
+
+    unsafe { externs::state::add_int64_batch(data.as_ptr(), data.len() as u32) }
+}
+
+pub fn set_sum_int64<K: AsRef<str>>(ord: i64, key: K, value: i64, sum: bool) {
+    count_call!("set_sum_int64");
+    flush();
+    let key = key.as_ref();
+
+    unsafe {
+        externs::state::set_sum_int64(ord, key.as_ptr(), key.len() as u32, value, sum as u32)
+    }
+}
+
+pub fn set_sum_bigint<K: AsRef<str>>(ord: i64, key: K, value: &BigInt, sum: bool) {
+    count_call!("set_sum_bigint");
+    flush();
+    let key = key.as_ref();
+    let data = value.to_string();
+
+    unsafe {
+        externs::state::set_sum_bigint(
+            ord,
+            key.as_ptr(),
+            key.len() as u32,
+            data.as_ptr(),
+            data.len() as u32,
+            sum as u32,
+        )
+    }
+}
+
 pub fn add_float64<K: AsRef<str>>(ord: i64, key: K, value: f64) {
+    count_call!("add_float64");
+    flush();
     let key = key.as_ref();
 
     unsafe { externs::state::add_float64(ord, key.as_ptr(), key.len() as u32, value) }
 }
 
 pub fn add_bigfloat<K: AsRef<str>>(ord: i64, key: K, value: &BigDecimal) {
+    count_call!("add_bigfloat");
+    flush();
     let key = key.as_ref();
     let data = value.to_string();
 
@@ -151,13 +777,35 @@ pub fn add_bigfloat<K: AsRef<str>>(ord: i64, key: K, value: &BigDecimal) {
     }
 }
 
+pub fn add_bigfloat_scaled<K: AsRef<str>>(ord: i64, key: K, value: &BigDecimal, scale: u32) {
+    count_call!("add_bigfloat_scaled");
+    flush();
+    let key = key.as_ref();
+    let data = value.to_string();
+
+    unsafe {
+        externs::state::add_bigfloat_scaled(
+            ord,
+            key.as_ptr(),
+            key.len() as u32,
+            data.as_ptr(),
+            data.len() as u32,
+            scale,
+        )
+    }
+}
+
 pub fn set_min_int64<K: AsRef<str>>(ord: i64, key: K, value: i64) {
+    count_call!("set_min_int64");
+    flush();
     let key = key.as_ref();
 
     unsafe { externs::state::set_min_int64(ord, key.as_ptr(), key.len() as u32, value) }
 }
 
 pub fn set_min_bigint<K: AsRef<str>>(ord: i64, key: K, value: &BigInt) {
+    count_call!("set_min_bigint");
+    flush();
     let key = key.as_ref();
     let data = value.to_string();
 
@@ -173,12 +821,16 @@ pub fn set_min_bigint<K: AsRef<str>>(ord: i64, key: K, value: &BigInt) {
 }
 
 pub fn set_min_float64<K: AsRef<str>>(ord: i64, key: K, value: f64) {
+    count_call!("set_min_float64");
+    flush();
     let key = key.as_ref();
 
     unsafe { externs::state::set_min_float64(ord, key.as_ptr(), key.len() as u32, value) }
 }
 
 pub fn set_min_bigfloat<K: AsRef<str>>(ord: i64, key: K, value: &BigDecimal) {
+    count_call!("set_min_bigfloat");
+    flush();
     let key = key.as_ref();
     let data = value.to_string();
 
@@ -194,12 +846,16 @@ pub fn set_min_bigfloat<K: AsRef<str>>(ord: i64, key: K, value: &BigDecimal) {
 }
 
 pub fn set_max_int64<K: AsRef<str>>(ord: i64, key: K, value: i64) {
+    count_call!("set_max_int64");
+    flush();
     let key = key.as_ref();
 
     unsafe { externs::state::set_max_int64(ord, key.as_ptr(), key.len() as u32, value) }
 }
 
 pub fn set_max_bigint<K: AsRef<str>>(ord: i64, key: K, value: &BigInt) {
+    count_call!("set_max_bigint");
+    flush();
     let key = key.as_ref();
     let data = value.to_string();
 
@@ -215,12 +871,16 @@ pub fn set_max_bigint<K: AsRef<str>>(ord: i64, key: K, value: &BigInt) {
 }
 
 pub fn set_max_float64<K: AsRef<str>>(ord: i64, key: K, value: f64) {
+    count_call!("set_max_float64");
+    flush();
     let key = key.as_ref();
 
     unsafe { externs::state::set_max_float64(ord, key.as_ptr(), key.len() as u32, value) }
 }
 
 pub fn set_max_bigfloat<K: AsRef<str>>(ord: i64, key: K, value: &BigDecimal) {
+    count_call!("set_max_bigfloat");
+    flush();
     let key = key.as_ref();
     let data = value.to_string();
 
@@ -234,3 +894,162 @@ pub fn set_max_bigfloat<K: AsRef<str>>(ord: i64, key: K, value: &BigDecimal) {
         )
     }
 }
+
+// Batched operations are sent to the host as `StoreDeltas`, one delta per operation
+// holding its ordinal, key and value, in the order they must be applied.
+fn encode_batch<'a, I: Iterator<Item = (u64, &'a str, Vec<u8>)>>(ops: I) -> Vec<u8> {
+    let batch = pb::substreams::StoreDeltas {
+        deltas: ops
+            .map(|(ordinal, key, new_value)| pb::substreams::StoreDelta {
+                ordinal,
+                key: key.to_owned(),
+                new_value,
+                ..Default::default()
+            })
+            .collect(),
+    };
+
+    proto::encode(&batch).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::state::{
+        buffer_write, cached_read, flush_with, reset_read_cache, reset_write_buffer,
+        toggle_write_buffer, with_read_cache, BufferedOp, CachedRead,
+    };
+    use std::cell::Cell;
+    use std::collections::BTreeMap;
+
+    // Applies writes like the host does, one write or one batch of writes per host call
+    #[derive(Default)]
+    struct MockHost {
+        values: BTreeMap<String, Vec<u8>>,
+        ordinals: Vec<u64>,
+        calls: usize,
+    }
+
+    impl MockHost {
+        fn apply(&mut self, op: BufferedOp, ord: u64, key: &str, value: &[u8]) {
+            let value = match op {
+                BufferedOp::Set => value.to_vec(),
+                BufferedOp::AddInt64 | BufferedOp::AddBigInt => {
+                    let current = self.values.get(key).map(|current| decimal(current));
+                    let total = current.unwrap_or(0) + decimal(value);
+                    total.to_string().into_bytes()
+                }
+            };
+            self.values.insert(key.to_owned(), value);
+            self.ordinals.push(ord);
+        }
+    }
+
+    fn decimal(bytes: &[u8]) -> i128 {
+        std::str::from_utf8(bytes).unwrap().parse().unwrap()
+    }
+
+    const WRITES: [(BufferedOp, u64, &str, &str); 6] = [
+        (BufferedOp::Set, 1, "owner", "alice"),
+        (BufferedOp::AddInt64, 2, "transfers", "3"),
+        (BufferedOp::AddInt64, 2, "transfers", "-1"),
+        (BufferedOp::Set, 3, "owner", "bob"),
+        (BufferedOp::AddBigInt, 4, "volume", "100000000000000000000"),
+        (BufferedOp::Set, 5, "owner", "carol"),
+    ];
+
+    #[test]
+    fn it_flushes_buffered_writes_like_unbuffered_ones() {
+        let mut unbuffered = MockHost::default();
+        for (op, ord, key, value) in WRITES {
+            assert!(!buffer_write(op, ord as i64, key, || value.into()));
+            unbuffered.apply(op, ord, key, value.as_bytes());
+            unbuffered.calls += 1;
+        }
+
+        let mut buffered = MockHost::default();
+        toggle_write_buffer(true);
+        for (op, ord, key, value) in WRITES {
+            assert!(buffer_write(op, ord as i64, key, || value.into()));
+        }
+        flush_with(|op, writes| {
+            buffered.calls += 1;
+            for write in writes {
+                buffered.apply(op, write.ord, &write.key, &write.value);
+            }
+        });
+        toggle_write_buffer(false);
+
+        assert_eq!(buffered.values, unbuffered.values);
+        assert_eq!(buffered.values.get("owner"), Some(&b"carol".to_vec()));
+        assert_eq!(buffered.values.get("transfers"), Some(&b"2".to_vec()));
+        assert_eq!(buffered.ordinals, unbuffered.ordinals);
+        assert_eq!((buffered.calls, unbuffered.calls), (5, 6));
+
+        flush_with(|_, _| panic!("nothing is buffered"));
+    }
+
+    #[test]
+    fn it_drops_writes_buffered_by_previous_invocation() {
+        toggle_write_buffer(true);
+        assert!(buffer_write(BufferedOp::Set, 1, "owner", || b"alice".to_vec()));
+        reset_write_buffer();
+        flush_with(|_, _| panic!("writes of the previous invocation are sent"));
+
+        assert!(buffer_write(BufferedOp::Set, 1, "owner", || b"bob".to_vec()));
+        let mut sent = vec![];
+        flush_with(|op, writes| sent.extend(writes.iter().map(|write| (op, write.value.clone()))));
+        assert_eq!(sent, vec![(BufferedOp::Set, b"bob".to_vec())]);
+        toggle_write_buffer(false);
+    }
+
+    #[test]
+    fn it_caches_identical_reads() {
+        let fetches = Cell::new(0);
+        let fetch = || {
+            fetches.set(fetches.get() + 1);
+            Some(b"18".to_vec())
+        };
+
+        with_read_cache(true);
+        reset_read_cache();
+        assert_eq!(
+            cached_read(CachedRead::Last, 0, "decimals", fetch),
+            Some(b"18".to_vec())
+        );
+        assert_eq!(
+            cached_read(CachedRead::Last, 0, "decimals", fetch),
+            Some(b"18".to_vec())
+        );
+        assert_eq!(fetches.get(), 1);
+
+        cached_read(CachedRead::First, 0, "decimals", fetch);
+        cached_read(CachedRead::Last, 1, "decimals", fetch);
+        cached_read(CachedRead::Last, 0, "symbol", fetch);
+        assert_eq!(fetches.get(), 4);
+
+        reset_read_cache();
+        cached_read(CachedRead::Last, 0, "decimals", fetch);
+        assert_eq!(fetches.get(), 5);
+
+        with_read_cache(false);
+        cached_read(CachedRead::Last, 0, "decimals", fetch);
+        cached_read(CachedRead::Last, 0, "decimals", fetch);
+        assert_eq!(fetches.get(), 7);
+    }
+
+    #[test]
+    fn it_caches_absent_keys() {
+        let fetches = Cell::new(0);
+        let fetch = || {
+            fetches.set(fetches.get() + 1);
+            None
+        };
+
+        with_read_cache(true);
+        reset_read_cache();
+        assert_eq!(cached_read(CachedRead::Last, 0, "missing", fetch), None);
+        assert_eq!(cached_read(CachedRead::Last, 0, "missing", fetch), None);
+        assert_eq!(fetches.get(), 1);
+        with_read_cache(false);
+    }
+}
@@ -0,0 +1,68 @@
+//! Host Call Statistics for Substreams.
+//!
+//! This module, available with the `call-stats` feature, counts the calls made to each
+//! host function by the handler being invoked, like the `state` reads and writes and the
+//! logs, to find out where a handler spends its host round-trips. Counters are reset when
+//! a handler starts so a snapshot only covers the current invocation.
+//!
+//! Counting is a map update on each host call, it's cheap but not free, so only enable
+//! the feature while profiling a module.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # let store = substreams::store::StoreGet::new(0);
+//! store.get_first("owner:1");
+//! store.get_first("owner:2");
+//!
+//! let calls = substreams::stats::snapshot();
+//! substreams::log::info!("host calls: {:?}", calls); // {"get_first": 2}
+//! ```
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+thread_local! {
+    static CALLS: RefCell<BTreeMap<&'static str, u64>> = RefCell::new(BTreeMap::new());
+}
+
+/// Returns the number of calls made to each host function since the handler started,
+/// keyed by host function name. Host functions never called are absent.
+pub fn snapshot() -> BTreeMap<&'static str, u64> {
+    CALLS.with(|calls| calls.borrow().clone())
+}
+
+pub(crate) fn record(name: &'static str) {
+    CALLS.with(|calls| *calls.borrow_mut().entry(name).or_insert(0) += 1);
+}
+
+pub(crate) fn reset() {
+    CALLS.with(|calls| calls.borrow_mut().clear());
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats::{record, reset, snapshot};
+
+    #[test]
+    fn it_counts_calls_per_host_function() {
+        reset();
+        assert!(snapshot().is_empty());
+
+        record("get_first");
+        record("set");
+        record("get_first");
+
+        let calls = snapshot();
+        assert_eq!(calls.get("get_first"), Some(&2));
+        assert_eq!(calls.get("set"), Some(&1));
+        assert_eq!(calls.get("get_last"), None);
+
+        record("set");
+        assert_eq!(snapshot().get("set"), Some(&2));
+        assert_eq!(calls.get("set"), Some(&1));
+
+        reset();
+        assert!(snapshot().is_empty());
+    }
+}
@@ -4,14 +4,219 @@
 //! handlers.
 //!
 
+use crate::deltas::{DeltaValue, Untyped};
+use crate::errors::Error;
+use crate::hex::Hex;
 use crate::pb;
 use crate::state;
+pub use crate::state::{flush, set_buffered, with_read_cache};
 use bigdecimal::BigDecimal;
 use num_bigint::BigInt;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::rc::Rc;
 use substreams_macro::StoreWriter;
 
+pub use substreams_macro::StoreKey;
+
 /// Delta is a struct that defined StoreDeltas
-pub type Deltas = Vec<pb::substreams::StoreDelta>;
+///
+/// Untyped, `Deltas` is the list of the raw [pb::substreams::StoreDelta]. With the type of
+/// the values of the store, `Deltas<T>` is the list of the keys along with their delta
+/// decoded as a [DeltaDecoded](crate::deltas::DeltaDecoded), like a handler input
+/// `deltas: store::Deltas<pb::Token>`.
+pub type Deltas<T = Untyped> = Vec<<T as DeltaValue>::Delta>;
+
+/// StoreKey is implemented by structs that are turned into a store key and parsed
+/// back from it, usually with `#[derive(StoreKey)]`.
+///
+/// The derived key is made of an optional prefix, set with `#[store_key(prefix = "...")]`,
+/// followed by each field in declaration order, all separated by `:`. `Vec<u8>` fields
+/// are encoded as lower hexadecimal without `0x`, integer fields in base 10 and `String`
+/// fields as-is. Only the last field may contain a `:`, parsing a key with a `:` in
+/// another `String` field doesn't give back the same value.
+///
+/// # Examples
+///
+/// ```
+/// use substreams::store::StoreKey;
+///
+/// #[derive(StoreKey, Debug, PartialEq)]
+/// #[store_key(prefix = "total")]
+/// struct TransferKey {
+///     holder: Vec<u8>,
+///     contract: Vec<u8>,
+///     token_id: u64,
+/// }
+///
+/// let key = TransferKey { holder: vec![0xab], contract: vec![0x01, 0xcd], token_id: 42 };
+/// assert_eq!(key.to_key(), "total:ab:01cd:42");
+/// assert_eq!(TransferKey::parse_key("total:ab:01cd:42").unwrap(), key);
+/// assert!(TransferKey::parse_key("volume:ab:01cd:42").is_err());
+/// assert!(TransferKey::parse_key("total:ab:01cd").is_err());
+///
+/// #[derive(StoreKey, Debug, PartialEq)]
+/// struct PairKey {
+///     index: i32,
+///     name: String,
+/// }
+///
+/// let key = PairKey { index: -1, name: "usdc:weth".to_owned() };
+/// assert_eq!(key.to_key(), "-1:usdc:weth");
+/// assert_eq!(PairKey::parse_key(&key.to_key()).unwrap(), key);
+/// ```
+pub trait StoreKey: Sized {
+    /// Returns the store key of the value
+    fn to_key(&self) -> String;
+
+    /// Parses a store key built by [StoreKey::to_key] back into a value
+    fn parse_key(key: &str) -> Result<Self, Error>;
+}
+
+/// KeyField is a field type supported by `#[derive(StoreKey)]`
+#[doc(hidden)]
+pub trait KeyField: Sized {
+    fn to_key_field(&self) -> String;
+
+    fn parse_key_field(field: &str) -> Option<Self>;
+}
+
+impl KeyField for Vec<u8> {
+    fn to_key_field(&self) -> String {
+        Hex::encode(self)
+    }
+
+    // Fields are encoded without the `0x` prefix `Hex::decode` allows, a prefixed field
+    // is not one of ours
+    fn parse_key_field(field: &str) -> Option<Self> {
+        if field.starts_with("0x") || field.starts_with("0X") {
+            return None;
+        }
+        Hex::decode(field).ok()
+    }
+}
+
+impl KeyField for String {
+    fn to_key_field(&self) -> String {
+        self.clone()
+    }
+
+    fn parse_key_field(field: &str) -> Option<Self> {
+        Some(field.to_owned())
+    }
+}
+
+macro_rules! impl_integer_key_field {
+    ($($t:ty),*) => {
+        $(
+            impl KeyField for $t {
+                fn to_key_field(&self) -> String {
+                    self.to_string()
+                }
+
+                fn parse_key_field(field: &str) -> Option<Self> {
+                    field.parse().ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_key_field!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+/// Ordinal is the position of a write within the block, writes to a key are applied by
+/// ascending ordinal and reading a store at an ordinal sees the writes made before it.
+///
+/// Writers accept anything converting into an `Ordinal`, a bare `u64` included, but
+/// ordinals of a block must be globally ordered, across transactions and across the
+/// kinds of items a module writes from. Prefer building them with helpers deriving the
+/// ordinal from the chain data, [Ordinal::from_log] and [Ordinal::from_call], rather than
+/// from a counter local to the handler.
+///
+/// The helpers order items by the block level index of the logs: the high 32 bits of the
+/// ordinal hold the index of a log within the block, the low 32 bits the position of the
+/// item at that index, the calls of a transaction by call index and then its first log.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Ordinal(u64);
+
+// Position of a log among the items sharing its block level index, calls come before.
+const LOG_POSITION: u64 = 1 << 31;
+
+impl Ordinal {
+    pub const fn new(value: u64) -> Ordinal {
+        Ordinal(value)
+    }
+
+    pub const fn value(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the ordinal of a log, ordered by its index within the block, so writes
+    /// follow the order of the logs across transactions.
+    pub fn from_log<L: crate::eth::Log>(log: &L) -> Ordinal {
+        Ordinal((log.block_index() as u64) << 32 | LOG_POSITION)
+    }
+
+    /// Returns the ordinal of the call at index `idx` of a transaction, ordered after the
+    /// logs of the previous transactions and before the logs of the transaction, calls of
+    /// a transaction being ordered by call index.
+    ///
+    /// Returns `None` if the transaction has no logs: its position among the logs of the
+    /// block is not known from the transaction alone.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `idx` is greater than 2^31 - 1.
+    pub fn from_call<T: crate::eth::Transaction>(trace: &T, idx: u32) -> Option<Ordinal> {
+        assert!((idx as u64) < LOG_POSITION, "call index {} is too big", idx);
+
+        let first_log = trace
+            .logs()
+            .iter()
+            .map(crate::eth::Log::block_index)
+            .min()?;
+        Some(Ordinal((first_log as u64) << 32 | idx as u64))
+    }
+
+    fn as_i64(self) -> i64 {
+        self.0 as i64
+    }
+}
+
+impl From<u64> for Ordinal {
+    fn from(value: u64) -> Self {
+        Ordinal(value)
+    }
+}
+
+impl From<Ordinal> for u64 {
+    fn from(ordinal: Ordinal) -> Self {
+        ordinal.0
+    }
+}
+
+thread_local! {
+    static WRITER_CREATED: Cell<bool> = Cell::new(false);
+}
+
+// Called by the `new()` of each writable store, a module writes a single store so a second
+// writer within the same call would be another handle over that same store. The claim is
+// released by the host before each call, see `handlers::substreams_begin_call`.
+fn claim_writer(name: &str) {
+    if WRITER_CREATED.with(|created| created.replace(true)) {
+        panic!(
+            "{} can't be created, a writable store was already created by this module run, \
+             use the store given to the handler instead",
+            name
+        );
+    }
+}
+
+pub(crate) fn reset_writer() {
+    WRITER_CREATED.with(|created| created.set(false));
+}
 
 /// StoreSet is a struct representing a `store` with
 /// `updatePolicy` equal to `set`
@@ -19,16 +224,66 @@ pub type Deltas = Vec<pb::substreams::StoreDelta>;
 pub struct StoreSet {}
 impl StoreSet {
     /// Set a given key to a given value, if the key existed before, it will be replaced.
-    pub fn set<K: AsRef<str>>(&self, ord: u64, key: K, value: &Vec<u8>) {
-        state::set(ord as i64, key, value);
+    pub fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &Vec<u8>) {
+        state::set(ord.into().as_i64(), key, value);
     }
 
     /// Set many keys to a given values, if the key existed before, it will be replaced.
-    pub fn set_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &Vec<u8>) {
+    pub fn set_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &Vec<u8>) {
+        let ord = ord.into().as_i64();
         for key in keys {
-            state::set(ord as i64, key, value);
+            state::set(ord, key, value);
         }
     }
+
+    /// Applies all the `(ord, key, value)` operations in a single host call. Operations
+    /// are applied in slice order, the store ends up in the same state as calling `set`
+    /// for each of them in turn.
+    pub fn set_batch<O: Into<Ordinal> + Copy, K: AsRef<str>>(&self, ops: &[(O, K, Vec<u8>)]) {
+        state::set_batch(ops);
+    }
+
+    /// Reads the value of a key decoded as a `T`, or `T::default()` if the key is not set,
+    /// applies `f` to it and writes it back at `ord`, to update several fields of a
+    /// structured value together instead of keeping each of them in its own key.
+    ///
+    /// The value is read as of `ord`, so it includes the writes made earlier in the block
+    /// at the same or a lower ordinal. Modify a given key with non-decreasing ordinals:
+    /// modifying it at an ordinal lower than one it was already written at in this block
+    /// reads a value missing that later write, which is then lost when writing back.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the current value can't be decoded as a `T`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use substreams::store::StoreSet;
+    /// # #[derive(Clone, PartialEq, ::prost::Message)]
+    /// # struct Account {
+    /// #     #[prost(uint64, tag = "1")]
+    /// #     balance: u64,
+    /// #     #[prost(uint64, tag = "2")]
+    /// #     transfers: u64,
+    /// # }
+    /// # let store = StoreSet::new();
+    /// store.modify_proto(12, "account:1", |account: &mut Account| {
+    ///     account.balance += 100;
+    ///     account.transfers += 1;
+    /// });
+    /// ```
+    pub fn modify_proto<T, K, F>(&self, ord: impl Into<Ordinal>, key: K, f: F)
+    where
+        T: Default + prost::Message,
+        K: AsRef<str>,
+        F: FnOnce(&mut T),
+    {
+        let ord = ord.into().as_i64();
+        let key = key.as_ref();
+        let value = modify_value(key, state::get_output_at(ord, key), f);
+        state::set(ord, key, &value);
+    }
 }
 
 /// StoreSetIfNotExists is a struct representing a `store` module with
@@ -37,14 +292,40 @@ impl StoreSet {
 pub struct StoreSetIfNotExists {}
 impl StoreSetIfNotExists {
     /// Set a given key to a given value, if the key existed before, it will be ignored and not set.
-    pub fn set_if_not_exists<K: AsRef<str>>(&self, ord: u64, key: K, value: &Vec<u8>) {
-        state::set_if_not_exists(ord as i64, key, value);
+    pub fn set_if_not_exists<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        key: K,
+        value: &Vec<u8>,
+    ) {
+        state::set_if_not_exists(ord.into().as_i64(), key, value);
+    }
+
+    /// Like `set_if_not_exists`, and returns `true` if the key was created by this call, or
+    /// `false` if it already existed and was left untouched, to trigger one time side
+    /// effects when an entity is first seen.
+    ///
+    /// The existence check and the write are performed by the host in a single call, no
+    /// other write to the key can happen in between.
+    pub fn set_if_not_exists_reporting<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        key: K,
+        value: &Vec<u8>,
+    ) -> bool {
+        state::set_if_not_exists_reporting(ord.into().as_i64(), key, value)
     }
 
     /// Set given keys to given values, if the key existed before, it will be ignored and not set.
-    pub fn set_if_not_exists_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &Vec<u8>) {
+    pub fn set_if_not_exists_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &Vec<u8>,
+    ) {
+        let ord = ord.into().as_i64();
         for key in keys {
-            state::set_if_not_exists(ord as i64, key, value);
+            state::set_if_not_exists(ord, key, value);
         }
     }
 }
@@ -56,35 +337,58 @@ pub struct StoreAddInt64 {}
 impl StoreAddInt64 {
     /// Will add the value to the already present value at the key (or default to
     /// zero if the key was not set)
-    pub fn add<K: AsRef<str>>(&self, ord: u64, key: K, value: i64) {
-        state::add_int64(ord as i64, key, value);
+    pub fn add<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: i64) {
+        state::add_int64(ord.into().as_i64(), key, value);
     }
 
     /// Will add the value to the already present value of the keys (or default to
     /// zero if the key was not set)
-    pub fn add_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: i64) {
+    pub fn add_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: i64) {
+        let ord = ord.into().as_i64();
         for key in keys {
-            state::add_int64(ord as i64, key, value);
+            state::add_int64(ord, key, value);
         }
     }
+
+    /// Applies all the `(ord, key, value)` additions in a single host call instead of one
+    /// call per addition. Additions are applied in slice order, the store ends up in the
+    /// same state as calling `add` for each of them in turn.
+    pub fn add_batch<O: Into<Ordinal> + Copy, K: AsRef<str>>(&self, ops: &[(O, K, i64)]) {
+        state::add_int64_batch(ops);
+    }
 }
 
 /// StoreAddFloat64 is a struct representing a `store` module with
 /// `updatePolicy` equal to `add` and a valueType of `float64`
+///
+/// `float64` values are approximate, the result of a sum depends on the order of
+/// the additions, use a `bigfloat` store for exact values. NaN and infinite values
+/// are rejected as a single one would permanently poison the key.
 #[derive(StoreWriter)]
 pub struct StoreAddFloat64 {}
 impl StoreAddFloat64 {
     /// Will add the value to the already present value at the key (or default to
     /// zero if the key was not set)
-    pub fn add<K: AsRef<str>>(&self, ord: u64, key: K, value: f64) {
-        state::add_float64(ord as i64, key, value);
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is NaN or infinite.
+    pub fn add<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: f64) {
+        assert_finite(key.as_ref(), value);
+        state::add_float64(ord.into().as_i64(), key, value);
     }
 
     /// Will add the value to the already present value of the keys (or default to
     /// zero if the key was not set)
-    pub fn add_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: f64) {
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is NaN or infinite.
+    pub fn add_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: f64) {
+        let ord = ord.into().as_i64();
         for key in keys {
-            state::add_float64(ord as i64, key, value);
+            assert_finite(key.as_ref(), value);
+            state::add_float64(ord, key, value);
         }
     }
 }
@@ -96,17 +400,69 @@ pub struct StoreAddBigFloat {}
 impl StoreAddBigFloat {
     /// Will add the value to the already present value at the key (or default to
     /// zero if the key was not set)
-    pub fn add<K: AsRef<str>>(&self, ord: u64, key: K, value: &BigDecimal) {
-        state::add_bigfloat(ord as i64, key, value);
+    pub fn add<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &BigDecimal) {
+        state::add_bigfloat(ord.into().as_i64(), key, value);
     }
 
     /// Will add the value to the already present value of the keys (or default to
     /// zero if the key was not set)
-    pub fn add_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &BigDecimal) {
+    pub fn add_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &BigDecimal,
+    ) {
+        let ord = ord.into().as_i64();
         for key in keys {
-            state::add_bigfloat(ord as i64, key, value);
+            state::add_bigfloat(ord, key, value);
         }
     }
+
+    /// Returns a writer on the same store which rounds the total of each key it adds to,
+    /// to `scale` decimal places, so repeated additions don't grow the number of digits
+    /// stored for the key.
+    ///
+    /// The rounding is done by the host once the value is added, on the decimal value of
+    /// the total, to the nearest with ties to even (banker's rounding) rather than half up,
+    /// so that rounding many totals doesn't drift in one direction: `2.675` is stored as
+    /// `2.68` and `2.665` as `2.66`. The stored value always has exactly `scale` decimals,
+    /// `1.5` added with a scale of 2 is stored as `1.50`. The host rejects a `scale` above
+    /// 100, the precision of `bigfloat` values.
+    pub fn with_scale(&self, scale: u32) -> StoreAddBigFloatWithScale {
+        StoreAddBigFloatWithScale { scale }
+    }
+}
+
+/// StoreAddBigFloatWithScale is a [StoreAddBigFloat] rounding the totals it writes to a
+/// fixed number of decimals, see [StoreAddBigFloat::with_scale].
+pub struct StoreAddBigFloatWithScale {
+    scale: u32,
+}
+impl StoreAddBigFloatWithScale {
+    /// Will add the value to the already present value at the key (or default to
+    /// zero if the key was not set) and round the total to the scale of the writer
+    pub fn add<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &BigDecimal) {
+        state::add_bigfloat_scaled(ord.into().as_i64(), key, value, self.scale);
+    }
+
+    /// Will add the value to the already present value of the keys (or default to
+    /// zero if the key was not set) and round the totals to the scale of the writer
+    pub fn add_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &BigDecimal,
+    ) {
+        let ord = ord.into().as_i64();
+        for key in keys {
+            state::add_bigfloat_scaled(ord, key, value, self.scale);
+        }
+    }
+
+    /// Returns the number of decimals the totals are rounded to
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
 }
 
 /// StoreAddBigInt is a struct representing a `store` module with
@@ -116,17 +472,76 @@ pub struct StoreAddBigInt {}
 impl StoreAddBigInt {
     /// Will add the value to the already present value of the keys (or default to
     /// zero if the key was not set)
-    pub fn add<K: AsRef<str>>(&self, ord: u64, key: K, value: &BigInt) {
-        state::add_bigint(ord as i64, key, value);
+    pub fn add<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &BigInt) {
+        state::add_bigint(ord.into().as_i64(), key, value);
+    }
+
+    /// Will add the value to the already present value of the key (or default to
+    /// zero if the key was not set), like `add`, and return the new total of the key.
+    /// The total is computed by the host and sent back in the canonical `bigint`
+    /// encoding of the store, a base 10 string with a leading `-` when negative.
+    pub fn add_returning<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        key: K,
+        value: &BigInt,
+    ) -> BigInt {
+        state::add_bigint_returning(ord.into().as_i64(), key, value)
     }
 
     /// Will add the value to the already present value of the keys (or default to
     /// zero if the key was not set)
-    pub fn add_many<K: AsRef<str>>(&self, ord: u64, keys: &Vec<K>, value: &BigInt) {
+    pub fn add_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &BigInt) {
+        let ord = ord.into().as_i64();
         for key in keys {
-            state::add_bigint(ord as i64, key, value);
+            state::add_bigint(ord, key, value);
         }
     }
+
+    /// Applies all the `(ord, key, value)` additions in a single host call, in slice
+    /// order, see [`StoreAddInt64::add_batch`].
+    pub fn add_batch<O: Into<Ordinal> + Copy, K: AsRef<str>>(&self, ops: &[(O, K, BigInt)]) {
+        state::add_bigint_batch(ops);
+    }
+}
+
+/// StoreSetSumInt64 is a struct representing a `store` with `updatePolicy` equal to `set`
+/// and `valueType` equal to `set_sum_int64`, where each key is a total that can be both
+/// summed and reset
+///
+/// A `set` at an ordinal establishes the baseline of a key, the `sum`s at the following
+/// ordinals accumulate on top of it, until the next `set`. A `sum` on a key never set
+/// starts from 0. Values are stored as plain decimal strings, so the store can be read
+/// with [StoreGetInt64].
+#[derive(StoreWriter)]
+pub struct StoreSetSumInt64 {}
+impl StoreSetSumInt64 {
+    /// Sets the value of the key to `value`, discarding what was summed before `ord`
+    pub fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: i64) {
+        state::set_sum_int64(ord.into().as_i64(), key, value, false);
+    }
+
+    /// Adds `value` to the value of the key as of `ord`
+    pub fn sum<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: i64) {
+        state::set_sum_int64(ord.into().as_i64(), key, value, true);
+    }
+}
+
+/// StoreSetSumBigInt is a struct representing a `store` with `updatePolicy` equal to `set`
+/// and `valueType` equal to `set_sum_bigint`, where each key is a total that can be both
+/// summed and reset, like [StoreSetSumInt64], read with [StoreGetBigInt]
+#[derive(StoreWriter)]
+pub struct StoreSetSumBigInt {}
+impl StoreSetSumBigInt {
+    /// Sets the value of the key to `value`, discarding what was summed before `ord`
+    pub fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &BigInt) {
+        state::set_sum_bigint(ord.into().as_i64(), key, value, false);
+    }
+
+    /// Adds `value` to the value of the key as of `ord`
+    pub fn sum<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &BigInt) {
+        state::set_sum_bigint(ord.into().as_i64(), key, value, true);
+    }
 }
 
 /// StoreMaxInt64 is a struct representing a `store` module with
@@ -137,8 +552,18 @@ impl StoreMaxInt64 {
     /// max will set the provided key in the store only if the value received in
     /// parameter is bigger than the one already present in the store, with
     /// a default of the zero value when the key is absent.
-    pub fn max<K: AsRef<str>>(&self, ord: u64, key: K, value: i64) {
-        state::set_max_int64(ord as i64, key, value);
+    pub fn max<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: i64) {
+        state::set_max_int64(ord.into().as_i64(), key, value);
+    }
+
+    /// Will set each of the keys like `max` does, to the value received in parameter
+    /// when it's bigger than the one already present for the key, with a default of the
+    /// zero value when the key is absent.
+    pub fn max_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: i64) {
+        let ord = ord.into().as_i64();
+        for key in keys {
+            state::set_max_int64(ord, key, value);
+        }
     }
 }
 
@@ -150,21 +575,54 @@ impl StoreMaxBigInt {
     /// Will set the provided key in the store only if the value received in
     /// parameter is bigger than the one already present in the store, with
     /// a default of the zero value when the key is absent.
-    pub fn max<K: AsRef<str>>(&self, ord: u64, key: K, value: &BigInt) {
-        state::set_max_bigint(ord as i64, key, value);
+    pub fn max<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &BigInt) {
+        state::set_max_bigint(ord.into().as_i64(), key, value);
+    }
+
+    /// Will set each of the keys like `max` does, to the value received in parameter
+    /// when it's bigger than the one already present for the key, with a default of the
+    /// zero value when the key is absent.
+    pub fn max_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &BigInt) {
+        let ord = ord.into().as_i64();
+        for key in keys {
+            state::set_max_bigint(ord, key, value);
+        }
     }
 }
 
 /// StoreMaxFloat64 is a struct representing a `store` module with
 /// `updatePolicy` equal to `max` and a valueType of `float64`
+///
+/// Like [StoreAddFloat64], values are approximate and NaN or infinite values are
+/// rejected, use a `bigfloat` store for exact values.
 #[derive(StoreWriter)]
 pub struct StoreMaxFloat64 {}
 impl StoreMaxFloat64 {
     /// Will set the provided key in the store only if the value received in
     /// parameter is bigger than the one already present in the store, with
     /// a default of the zero value when the key is absent.
-    pub fn max<K: AsRef<str>>(&self, ord: u64, key: K, value: f64) {
-        state::set_max_float64(ord as i64, key, value);
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is NaN or infinite.
+    pub fn max<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: f64) {
+        assert_finite(key.as_ref(), value);
+        state::set_max_float64(ord.into().as_i64(), key, value);
+    }
+
+    /// Will set each of the keys like `max` does, to the value received in parameter
+    /// when it's bigger than the one already present for the key, with a default of the
+    /// zero value when the key is absent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is NaN or infinite.
+    pub fn max_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: f64) {
+        let ord = ord.into().as_i64();
+        for key in keys {
+            assert_finite(key.as_ref(), value);
+            state::set_max_float64(ord, key, value);
+        }
     }
 }
 
@@ -176,8 +634,23 @@ impl StoreMaxBigFloat {
     /// Will set the provided key in the store only if the value received in
     /// parameter is bigger than the one already present in the store, with
     /// a default of the zero value when the key is absent.
-    pub fn max<K: AsRef<str>>(&self, ord: u64, key: K, value: &BigDecimal) {
-        state::set_max_bigfloat(ord as i64, key, value);
+    pub fn max<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &BigDecimal) {
+        state::set_max_bigfloat(ord.into().as_i64(), key, value);
+    }
+
+    /// Will set each of the keys like `max` does, to the value received in parameter
+    /// when it's bigger than the one already present for the key, with a default of the
+    /// zero value when the key is absent.
+    pub fn max_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &BigDecimal,
+    ) {
+        let ord = ord.into().as_i64();
+        for key in keys {
+            state::set_max_bigfloat(ord, key, value);
+        }
     }
 }
 
@@ -189,8 +662,18 @@ impl StoreMinInt64 {
     /// Will set the provided key in the store only if the value received in
     /// parameter is smaller than the one already present in the store, with
     /// a default of the zero value when the key is absent.
-    pub fn min<K: AsRef<str>>(&self, ord: u64, key: K, value: i64) {
-        state::set_min_int64(ord as i64, key, value);
+    pub fn min<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: i64) {
+        state::set_min_int64(ord.into().as_i64(), key, value);
+    }
+
+    /// Will set each of the keys like `min` does, to the value received in parameter
+    /// when it's smaller than the one already present for the key, with a default of the
+    /// zero value when the key is absent.
+    pub fn min_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: i64) {
+        let ord = ord.into().as_i64();
+        for key in keys {
+            state::set_min_int64(ord, key, value);
+        }
     }
 }
 
@@ -202,21 +685,54 @@ impl StoreMinBigInt {
     /// Will set the provided key in the store only if the value received in
     /// parameter is smaller than the one already present in the store, with
     /// a default of the zero value when the key is absent.
-    pub fn min<K: AsRef<str>>(&self, ord: u64, key: K, value: &BigInt) {
-        state::set_min_bigint(ord as i64, key, value);
+    pub fn min<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &BigInt) {
+        state::set_min_bigint(ord.into().as_i64(), key, value);
+    }
+
+    /// Will set each of the keys like `min` does, to the value received in parameter
+    /// when it's smaller than the one already present for the key, with a default of the
+    /// zero value when the key is absent.
+    pub fn min_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: &BigInt) {
+        let ord = ord.into().as_i64();
+        for key in keys {
+            state::set_min_bigint(ord, key, value);
+        }
     }
 }
 
 /// StoreMinFloat64 is a struct representing a `store` module with
 /// `updatePolicy` equal to `min` and a valueType of `float64`
+///
+/// Like [StoreAddFloat64], values are approximate and NaN or infinite values are
+/// rejected, use a `bigfloat` store for exact values.
 #[derive(StoreWriter)]
 pub struct StoreMinFloat64 {}
 impl StoreMinFloat64 {
     /// Will set the provided key in the store only if the value received in
     /// parameter is smaller than the one already present in the store, with
     /// a default of the zero value when the key is absent.
-    pub fn min<K: AsRef<str>>(&self, ord: u64, key: K, value: f64) {
-        state::set_min_float64(ord as i64, key, value);
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is NaN or infinite.
+    pub fn min<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: f64) {
+        assert_finite(key.as_ref(), value);
+        state::set_min_float64(ord.into().as_i64(), key, value);
+    }
+
+    /// Will set each of the keys like `min` does, to the value received in parameter
+    /// when it's smaller than the one already present for the key, with a default of the
+    /// zero value when the key is absent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is NaN or infinite.
+    pub fn min_many<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, keys: &Vec<K>, value: f64) {
+        let ord = ord.into().as_i64();
+        for key in keys {
+            assert_finite(key.as_ref(), value);
+            state::set_min_float64(ord, key, value);
+        }
     }
 }
 
@@ -228,8 +744,23 @@ impl StoreMinBigFloat {
     /// Will set the provided key in the store only if the value received in
     /// parameter is smaller than the one already present in the store, with
     /// a default of the zero value when the key is absent.
-    pub fn min<K: AsRef<str>>(&self, ord: u64, key: K, value: &BigDecimal) {
-        state::set_min_bigfloat(ord as i64, key, value);
+    pub fn min<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &BigDecimal) {
+        state::set_min_bigfloat(ord.into().as_i64(), key, value);
+    }
+
+    /// Will set each of the keys like `min` does, to the value received in parameter
+    /// when it's smaller than the one already present for the key, with a default of the
+    /// zero value when the key is absent.
+    pub fn min_many<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        keys: &Vec<K>,
+        value: &BigDecimal,
+    ) {
+        let ord = ord.into().as_i64();
+        for key in keys {
+            state::set_min_bigfloat(ord, key, value);
+        }
     }
 }
 
@@ -239,25 +770,130 @@ impl StoreMinBigFloat {
 pub struct StoreAppend {}
 impl StoreAppend {
     /// Concatenates a given value at the end of the key's current value
-    pub fn append<K: AsRef<str>>(&self, ord: u64, key: K, value: &String) {
-        state::append(ord as i64, key, &value.as_bytes().to_vec());
+    pub fn append<K: AsRef<str>, V: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: V) {
+        state::append(
+            ord.into().as_i64(),
+            key,
+            &value.as_ref().as_bytes().to_vec(),
+        );
     }
 
     /// Concatenates a given value at the end of the key's current value
-    pub fn append_bytes<K: AsRef<str>>(&self, ord: u64, key: K, value: &Vec<u8>) {
-        state::append(ord as i64, key, value);
+    pub fn append_bytes<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K, value: &Vec<u8>) {
+        state::append(ord.into().as_i64(), key, value);
+    }
+
+    /// Concatenates a given value, followed by `sep`, at the end of the key's current
+    /// value, building a list that can be read back with [StoreGet::get_last_split]
+    pub fn append_delimited<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        key: K,
+        value: &str,
+        sep: &str,
+    ) {
+        state::append(
+            ord.into().as_i64(),
+            key,
+            &format!("{}{}", value, sep).into_bytes(),
+        );
+    }
+
+    /// Concatenates `sep` then the given value at the end of the key's current value,
+    /// the separator is left out when the current value is absent or empty so the list
+    /// never starts with a separator. Appending an empty value changes nothing.
+    ///
+    /// Unlike [StoreAppend::append_delimited] the list has no trailing separator, it can
+    /// be read back with [StoreGet::get_last_split] as well. The length of the current
+    /// value is read from the host on each call.
+    pub fn append_with_sep<K: AsRef<str>>(
+        &self,
+        ord: impl Into<Ordinal>,
+        key: K,
+        value: &str,
+        sep: &str,
+    ) {
+        let ord = ord.into().as_i64();
+        let key = key.as_ref();
+        if value.is_empty() {
+            return;
+        }
+
+        // the value is appended to the last value of the key whatever the ordinal
+        let current_len = state::get_output_len(i64::MAX, key).unwrap_or(0);
+        state::append(ord, key, &with_separator(current_len, value, sep));
+    }
+}
+
+fn with_separator(current_len: u64, value: &str, sep: &str) -> Vec<u8> {
+    if current_len == 0 {
+        return value.as_bytes().to_vec();
+    }
+    format!("{}{}", sep, value).into_bytes()
+}
+
+/// StoreSortedSet is a struct representing a `store` with
+/// `updatePolicy` equal to `set` and `valueType` equal to `int64`, where each
+/// key is a member and its value the member's score
+///
+/// Scores are encoded as decimal `int64` strings, like a `StoreAddInt64` store, so
+/// setting a member again replaces its score. Members are read back ordered by
+/// score with [StoreGetSortedSet].
+#[derive(StoreWriter)]
+pub struct StoreSortedSet {}
+impl StoreSortedSet {
+    /// Sets the score of a member, replacing its previous score if any
+    pub fn set<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, member: K, score: i64) {
+        state::set(ord.into().as_i64(), member, &score.to_string().into_bytes());
+    }
+}
+
+/// StoreDelete is a struct representing a `store` whose handler only removes keys,
+/// one exact key at a time, whatever its `updatePolicy`
+#[derive(StoreWriter)]
+pub struct StoreDelete {}
+impl StoreDelete {
+    /// Deletes a single key, the keys sharing a prefix with it are kept. The delete takes
+    /// effect at ordinal `ord`: reads at a lower ordinal within the block still see the
+    /// key. Deleting an absent key does nothing and emits no delta.
+    pub fn delete<K: AsRef<str>>(&self, ord: impl Into<Ordinal>, key: K) {
+        state::delete(ord.into().as_i64(), key);
     }
 }
 
 /// StoreGet is a struct representing a read only store `store`
+///
+/// On its first read the store index is checked with the host, a handler wired to a store
+/// which is not one of its inputs panics with `store index N is not bound to this module`
+/// instead of reading every key as absent. The typed readers, like [StoreGetInt64], are
+/// checked the same way.
 pub struct StoreGet {
     idx: u32,
+    default: Option<Vec<u8>>,
+    bound: Cell<bool>,
 }
 
 impl StoreGet {
     /// Return a StoreGet object with a store index set
     pub fn new(idx: u32) -> StoreGet {
-        StoreGet { idx }
+        StoreGet {
+            idx,
+            default: None,
+            bound: Cell::new(false),
+        }
+    }
+
+    /// Return a StoreGet object with a store index set whose reads of an absent key
+    /// return `default` instead of `None`. `get_last`, `get_first`, `get_at` and
+    /// `get_at_relative` all apply the default, and so do the helpers built on them
+    /// like `map_last` or `sum_int64`. The default is only known to this reader, the
+    /// store itself is unchanged.
+    pub fn with_default(idx: u32, default: Vec<u8>) -> StoreGet {
+        StoreGet {
+            idx,
+            default: Some(default),
+            bound: Cell::new(false),
+        }
     }
 
     /// Allows you to read a single key from the store. The type
@@ -266,7 +902,42 @@ impl StoreGet {
     /// to go query a key that might have changed mid-block by
     /// the store module that built it.
     pub fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<Vec<u8>> {
-        return state::get_at(self.idx, ord as i64, key);
+        self.or_default(state::get_at(self.idx(), ord as i64, key))
+    }
+
+    /// Reads a key like `get_at`, at an ordinal `back` before the highest ordinal
+    /// written by the store within the current block. Ordinals are not contiguous, so
+    /// this goes back `back` ordinals, not `back` writes. When `back` is bigger than
+    /// the highest ordinal, the key is read at ordinal 0, before any change of the block,
+    /// and if the store has no change in the block it's the same as `get_last`.
+    pub fn get_at_relative<K: AsRef<str>>(&self, back: u64, key: K) -> Option<Vec<u8>> {
+        self.or_default(state::get_at_relative(self.idx(), back, key))
+    }
+
+    /// Reads a key as of the end of block `block_num`, which can be a block before the one
+    /// being processed. Returns `None` when the key is absent at that block, and when
+    /// `block_num` predates the initial block of the store. The default of the store
+    /// doesn't apply, so an absent key and a block before the store started can't be
+    /// confused with a value.
+    ///
+    /// This is much more expensive than the other reads: the host keeps the store only at
+    /// the state of the current block, an older block is resolved by loading the full
+    /// snapshot of the store written at the end of its save interval. The host only keeps
+    /// the last snapshot loaded in memory, so reads alternating between save intervals
+    /// load a snapshot from storage each time. Only the previous block, the current block
+    /// (read like `get_last`) and the last block of each save interval can be read, any
+    /// other block makes the host fail the module.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let balances = substreams::store::StoreGet::new(0);
+    /// // with a save interval of 1000 blocks, block 11_999 ends a snapshot
+    /// let before = balances.get_at_block(11_999, "balance:0xbc4c");
+    /// let now = balances.get_last("balance:0xbc4c");
+    /// ```
+    pub fn get_at_block<K: AsRef<str>>(&self, block_num: u64, key: K) -> Option<Vec<u8>> {
+        state::get_at_block(self.idx(), block_num, key)
     }
 
     /// Retrieves a key from the store, like `get_at`, but querying the state of
@@ -274,7 +945,77 @@ impl StoreGet {
     /// were applied within the current block. Tt does not need to rewind any changes
     /// in the middle of the block.
     pub fn get_last<K: AsRef<str>>(&self, key: K) -> Option<Vec<u8>> {
-        return state::get_last(self.idx, key);
+        self.or_default(state::get_last(self.idx(), key))
+    }
+
+    /// Retrieves a key like `get_last`, along with the ordinal at which its value was
+    /// written, to order reads across stores. The host only keeps the ordinals of the
+    /// changes of the current block: a value written in a previous block, which is also
+    /// the case of the store default, is returned with ordinal 0.
+    pub fn get_last_with_ord<K: AsRef<str>>(&self, key: K) -> Option<(u64, Vec<u8>)> {
+        match state::get_last_with_ord(self.idx(), key) {
+            Some(entry) => Some(entry),
+            None => self.default.clone().map(|default| (0, default)),
+        }
+    }
+
+    /// Returns the length of the value of a key as `get_last` would read it, or `None` if
+    /// the key is absent, without reading the value. Use it along with `get_last_range` to
+    /// read a big value, like a long list built with [StoreAppend], in bounded chunks.
+    pub fn get_last_len<K: AsRef<str>>(&self, key: K) -> Option<u64> {
+        match state::get_last_len(self.idx(), key) {
+            Some(len) => Some(len),
+            None => self.default.as_ref().map(|default| default.len() as u64),
+        }
+    }
+
+    /// Reads at most `len` bytes of the value of a key as `get_last` would read it,
+    /// starting at byte `offset`. Returns `None` if the key is absent or `offset` is past
+    /// the end of the value, and fewer than `len` bytes when the value ends before, so an
+    /// empty vector is only returned for a zero `len` or an `offset` at the end of the
+    /// value.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let store = substreams::store::StoreGet::new(0);
+    /// const CHUNK: u64 = 64 * 1024;
+    ///
+    /// let mut offset = 0;
+    /// while let Some(chunk) = store.get_last_range("logs", offset, CHUNK) {
+    ///     if chunk.is_empty() {
+    ///         break;
+    ///     }
+    ///     offset += chunk.len() as u64;
+    ///     // process the chunk
+    /// }
+    /// ```
+    pub fn get_last_range<K: AsRef<str>>(&self, key: K, offset: u64, len: u64) -> Option<Vec<u8>> {
+        let key = key.as_ref();
+        match (
+            state::get_last_range(self.idx(), key, offset, len),
+            &self.default,
+        ) {
+            (Some(chunk), _) => Some(chunk),
+            // the key is present but `offset` is past the end of its value
+            (None, Some(_)) if state::get_last_len(self.idx(), key).is_some() => None,
+            (None, Some(default)) => value_range(default, offset, len),
+            (None, None) => None,
+        }
+    }
+
+    /// Reads `keys` like `get_last` with a single host call, instead of one call per key,
+    /// and returns their values in the order of `keys`. The result has one entry per key,
+    /// `None` for an absent key (or the store default), and a key given twice is
+    /// returned twice.
+    pub fn get_last_many<K: AsRef<str>>(&self, keys: &[K]) -> Vec<Option<Vec<u8>>> {
+        self.in_key_order(state::get_last_many(self.idx(), keys), keys)
+    }
+
+    /// Reads a key with `get_last` and transforms its value with `f`, returning `None`
+    /// when the key is absent.
+    pub fn map_last<K: AsRef<str>, R, F: FnOnce(Vec<u8>) -> R>(&self, key: K, f: F) -> Option<R> {
+        self.get_last(key).map(f)
     }
 
     /// Retrieves a key from the store, like `get_at`, but querying the state of
@@ -282,6 +1023,1470 @@ impl StoreGet {
     /// were applied within the current block. However, it needs to unwind any keys that
     /// would have changed mid-block, so will be slightly less performant.
     pub fn get_first<K: AsRef<str>>(&self, key: K) -> Option<Vec<u8>> {
-        return state::get_first(self.idx, key);
+        self.or_default(state::get_first(self.idx(), key))
+    }
+
+    /// Returns whether a key is set in the store as of ordinal `ord`, like `get_at`
+    /// finding it, without sending its value back. The default of a store built with
+    /// [StoreGet::with_default] is not taken into account, an absent key is never set.
+    pub fn has_at<K: AsRef<str>>(&self, ord: u64, key: K) -> bool {
+        state::has_at(self.idx(), ord as i64, key)
+    }
+
+    /// Returns whether a key is set in the store, like `get_last` finding it, without
+    /// sending its value back. The default of the store is not taken into account.
+    pub fn has_last<K: AsRef<str>>(&self, key: K) -> bool {
+        state::has_last(self.idx(), key)
+    }
+
+    /// Returns the number of keys starting with `prefix` in the store, as of the state
+    /// `get_last` reads. The keys are counted by the host without sending any value back,
+    /// so it's much cheaper than a full scan of the prefix, but it still goes over every
+    /// key of the store.
+    pub fn count_prefix<K: AsRef<str>>(&self, prefix: K) -> u64 {
+        state::count_prefix(self.idx(), prefix)
+    }
+
+    /// Reads a key with `get_last` and splits its value on `sep`, like a list built
+    /// with [StoreAppend::append_delimited]. An absent or empty value returns an empty
+    /// list, a single trailing separator is ignored while empty entries in the middle
+    /// of the value are kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is not valid UTF-8.
+    pub fn get_last_split<K: AsRef<str>>(&self, key: K, sep: &str) -> Vec<String> {
+        match self.get_last(&key) {
+            Some(bytes) => split_value(&bytes, sep)
+                .unwrap_or_else(|| panic!("value of key {} is not valid UTF-8", key.as_ref())),
+            None => vec![],
+        }
+    }
+
+    /// Returns the key/value pairs of all the keys starting with `prefix`, as of ordinal
+    /// `ord` like `get_at` reads them, sorted lexicographically by key. An empty prefix
+    /// scans the whole store. Use `u64::MAX` to scan the state `get_last` reads.
+    ///
+    /// The host scans every key of the store and sends back all the matching values in a
+    /// single call, the store default doesn't apply to the scan.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let balances = substreams::store::StoreGet::new(0);
+    /// for (key, value) in balances.scan_prefix(u64::MAX, "balance:0xbc4c") {
+    ///     substreams::log::info!("{} has {} bytes", key, value.len());
+    /// }
+    /// ```
+    pub fn scan_prefix<K: AsRef<str>>(&self, ord: u64, prefix: K) -> Vec<(String, Vec<u8>)> {
+        state::scan_prefix(self.idx(), ord.min(i64::MAX as u64) as i64, prefix)
+    }
+
+    /// Decodes every value whose key starts with `prefix` as a `T` and returns them in a
+    /// map keyed by `key_fn` of each decoded value, to load all the entities of a kind
+    /// for random access.
+    ///
+    /// The host scans every key of the store and sends back all the matching values, as
+    /// of the state `get_last` reads, and each of them is decoded so the cost grows with
+    /// both the store and the prefix, load a prefix once per block and reuse the map.
+    /// Iteration order of a `HashMap` is not deterministic, sort its entries before
+    /// deriving any output from them. Values are visited in key order so when `key_fn`
+    /// returns the same key for two values, the one with the greatest store key is kept.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a value can't be decoded as a `T`.
+    pub fn get_prefix_map<T, K, F>(&self, prefix: &str, key_fn: F) -> HashMap<K, T>
+    where
+        T: Default + prost::Message,
+        K: Eq + Hash,
+        F: FnMut(&T) -> K,
+    {
+        entries_to_map(state::scan_prefix(self.idx(), i64::MAX, prefix), key_fn)
+    }
+
+    fn idx(&self) -> u32 {
+        self.ensure_bound(state::is_store_bound);
+        self.idx
+    }
+
+    fn ensure_bound<F: FnOnce(u32) -> bool>(&self, is_bound: F) {
+        if self.bound.get() {
+            return;
+        }
+        if !is_bound(self.idx) {
+            panic!("store index {} is not bound to this module", self.idx);
+        }
+        self.bound.set(true);
+    }
+
+    fn or_default(&self, value: Option<Vec<u8>>) -> Option<Vec<u8>> {
+        value.or_else(|| self.default.clone())
+    }
+
+    fn in_key_order<K: AsRef<str>>(
+        &self,
+        values: HashMap<String, Vec<u8>>,
+        keys: &[K],
+    ) -> Vec<Option<Vec<u8>>> {
+        keys.iter()
+            .map(|key| self.or_default(values.get(key.as_ref()).cloned()))
+            .collect()
+    }
+
+    /// Reads each key with `get_last` and returns the sum of their values, decoded
+    /// as `int64` like a `StoreAddInt64` store encodes them. Absent keys are treated
+    /// as zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a present value is not a valid `int64`.
+    pub fn sum_int64<K: AsRef<str>>(&self, keys: &[K]) -> i64 {
+        keys.iter()
+            .filter_map(|key| self.get_last(key).map(|bytes| (key, bytes)))
+            .map(|(key, bytes)| {
+                decode_int64(&bytes)
+                    .unwrap_or_else(|| panic!("value of key {} is not a valid int64", key.as_ref()))
+            })
+            .sum()
+    }
+
+    /// Reads each key with `get_last` and returns the sum of their values, decoded
+    /// as `bigint` like a `StoreAddBigInt` store encodes them. Absent keys are treated
+    /// as zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a present value is not a valid `bigint`.
+    pub fn sum_bigint<K: AsRef<str>>(&self, keys: &[K]) -> BigInt {
+        keys.iter()
+            .filter_map(|key| self.get_last(key).map(|bytes| (key, bytes)))
+            .map(|(key, bytes)| {
+                decode_bigint(&bytes).unwrap_or_else(|| {
+                    panic!("value of key {} is not a valid bigint", key.as_ref())
+                })
+            })
+            .sum()
+    }
+
+    /// Reads a key with `get_last` and decodes its value as a `T`, see [FromStoreBytes]
+    /// for the built-in types. A value that can't be decoded is logged and reads as
+    /// `None`, like an absent key, use `try_get_last_as` to get an error instead.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # let store = substreams::store::StoreGet::new(0);
+    /// let transfers: Option<i64> = store.get_last_as("transfers:0xbc4c");
+    /// let volume = store.get_last_as::<bigdecimal::BigDecimal, _>("volume:0xbc4c");
+    /// ```
+    pub fn get_last_as<T: FromStoreBytes, K: AsRef<str>>(&self, key: K) -> Option<T> {
+        or_log(self.try_get_last_as(key))
+    }
+
+    /// Same as `get_last_as` but returns an error when the value can't be decoded as a `T`
+    pub fn try_get_last_as<T: FromStoreBytes, K: AsRef<str>>(
+        &self,
+        key: K,
+    ) -> Result<Option<T>, Error> {
+        let key = key.as_ref();
+        decode_value(key, self.get_last(key))
+    }
+}
+
+/// StoreGetBigInt is a struct representing a read only store `store` holding `bigint`
+/// values, like the ones written by [StoreAddBigInt], reads return the decoded values
+///
+/// A key whose value is empty reads as `None`, like an absent key. A value that is not
+/// a valid `bigint` is logged and also reads as `None`, use the `try_get_*` variants to
+/// get an error instead.
+pub struct StoreGetBigInt {
+    store: StoreGet,
+}
+
+impl StoreGetBigInt {
+    /// Return a StoreGetBigInt object with a store index set
+    pub fn new(idx: u32) -> StoreGetBigInt {
+        StoreGetBigInt {
+            store: StoreGet::new(idx),
+        }
+    }
+
+    /// Reads a key like [StoreGet::get_at] and decodes its value
+    pub fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<BigInt> {
+        or_log(self.try_get_at(ord, key))
+    }
+
+    /// Reads a key like [StoreGet::get_last] and decodes its value
+    pub fn get_last<K: AsRef<str>>(&self, key: K) -> Option<BigInt> {
+        or_log(self.try_get_last(key))
+    }
+
+    /// Reads a key like [StoreGet::get_first] and decodes its value
+    pub fn get_first<K: AsRef<str>>(&self, key: K) -> Option<BigInt> {
+        or_log(self.try_get_first(key))
+    }
+
+    /// Same as `get_at` but returns an error when the value is not a valid `bigint`
+    pub fn try_get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Result<Option<BigInt>, Error> {
+        let key = key.as_ref();
+        parse_bigint_value(key, state::get_at(self.store.idx(), ord as i64, key))
+    }
+
+    /// Same as `get_last` but returns an error when the value is not a valid `bigint`
+    pub fn try_get_last<K: AsRef<str>>(&self, key: K) -> Result<Option<BigInt>, Error> {
+        let key = key.as_ref();
+        parse_bigint_value(key, state::get_last(self.store.idx(), key))
+    }
+
+    /// Same as `get_first` but returns an error when the value is not a valid `bigint`
+    pub fn try_get_first<K: AsRef<str>>(&self, key: K) -> Result<Option<BigInt>, Error> {
+        let key = key.as_ref();
+        parse_bigint_value(key, state::get_first(self.store.idx(), key))
+    }
+}
+
+/// StoreGetSortedSet is a struct representing a read only store `store` built with
+/// [StoreSortedSet], whose members are read back ordered by score
+///
+/// Each read scans the whole store, as of the state `get_last` reads, and sorts its
+/// members within the module, which is expensive on big stores.
+pub struct StoreGetSortedSet {
+    store: StoreGet,
+}
+
+impl StoreGetSortedSet {
+    /// Return a StoreGetSortedSet object with a store index set
+    pub fn new(idx: u32) -> StoreGetSortedSet {
+        StoreGetSortedSet {
+            store: StoreGet::new(idx),
+        }
+    }
+
+    /// Returns the members whose score is between `min` and `max`, both inclusive, with
+    /// their score. Members are ordered by ascending score, members with the same score
+    /// by ascending key.
+    ///
+    /// Returns an error if a value is not a valid `int64`.
+    pub fn range(&self, min: i64, max: i64) -> Result<Vec<(String, i64)>, Error> {
+        Ok(score_range(self.scores()?, min, max))
+    }
+
+    /// Returns the `n` members with the highest score, with their score. Members are
+    /// ordered by descending score, members with the same score by ascending key.
+    ///
+    /// Returns an error if a value is not a valid `int64`.
+    pub fn top_n(&self, n: usize) -> Result<Vec<(String, i64)>, Error> {
+        Ok(score_top_n(self.scores()?, n))
+    }
+
+    fn scores(&self) -> Result<Vec<(String, i64)>, Error> {
+        parse_scores(state::scan_prefix(self.store.idx(), i64::MAX, ""))
+    }
+}
+
+/// StoreGetInt64 is a struct representing a read only store `store` holding `int64`
+/// values, like the ones written by [StoreAddInt64], reads return the decoded values
+///
+/// A key whose value is empty reads as `None`, like an absent key. A value that is not
+/// a valid `int64` is logged and also reads as `None`, use the `try_get_*` variants to
+/// get an error instead.
+pub struct StoreGetInt64 {
+    store: StoreGet,
+}
+
+impl StoreGetInt64 {
+    /// Return a StoreGetInt64 object with a store index set
+    pub fn new(idx: u32) -> StoreGetInt64 {
+        StoreGetInt64 {
+            store: StoreGet::new(idx),
+        }
+    }
+
+    /// Reads a key like [StoreGet::get_at] and decodes its value
+    pub fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<i64> {
+        or_log(self.try_get_at(ord, key))
+    }
+
+    /// Reads a key like [StoreGet::get_last] and decodes its value
+    pub fn get_last<K: AsRef<str>>(&self, key: K) -> Option<i64> {
+        or_log(self.try_get_last(key))
+    }
+
+    /// Reads a key like [StoreGet::get_first] and decodes its value
+    pub fn get_first<K: AsRef<str>>(&self, key: K) -> Option<i64> {
+        or_log(self.try_get_first(key))
+    }
+
+    /// Same as `get_at` but returns an error when the value is not a valid `int64`
+    pub fn try_get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Result<Option<i64>, Error> {
+        let key = key.as_ref();
+        parse_number_value(
+            key,
+            state::get_at(self.store.idx(), ord as i64, key),
+            "int64",
+        )
+    }
+
+    /// Same as `get_last` but returns an error when the value is not a valid `int64`
+    pub fn try_get_last<K: AsRef<str>>(&self, key: K) -> Result<Option<i64>, Error> {
+        let key = key.as_ref();
+        parse_number_value(key, state::get_last(self.store.idx(), key), "int64")
+    }
+
+    /// Same as `get_first` but returns an error when the value is not a valid `int64`
+    pub fn try_get_first<K: AsRef<str>>(&self, key: K) -> Result<Option<i64>, Error> {
+        let key = key.as_ref();
+        parse_number_value(key, state::get_first(self.store.idx(), key), "int64")
+    }
+}
+
+/// StoreGetFloat64 is a struct representing a read only store `store` holding `float64`
+/// values, like the ones written by [StoreAddFloat64], reads return the decoded values
+///
+/// A key whose value is empty reads as `None`, like an absent key. A value that is not
+/// a valid `float64` is logged and also reads as `None`, use the `try_get_*` variants to
+/// get an error instead.
+pub struct StoreGetFloat64 {
+    store: StoreGet,
+}
+
+impl StoreGetFloat64 {
+    /// Return a StoreGetFloat64 object with a store index set
+    pub fn new(idx: u32) -> StoreGetFloat64 {
+        StoreGetFloat64 {
+            store: StoreGet::new(idx),
+        }
+    }
+
+    /// Reads a key like [StoreGet::get_at] and decodes its value
+    pub fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<f64> {
+        or_log(self.try_get_at(ord, key))
+    }
+
+    /// Reads a key like [StoreGet::get_last] and decodes its value
+    pub fn get_last<K: AsRef<str>>(&self, key: K) -> Option<f64> {
+        or_log(self.try_get_last(key))
+    }
+
+    /// Reads a key like [StoreGet::get_first] and decodes its value
+    pub fn get_first<K: AsRef<str>>(&self, key: K) -> Option<f64> {
+        or_log(self.try_get_first(key))
+    }
+
+    /// Same as `get_at` but returns an error when the value is not a valid `float64`
+    pub fn try_get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Result<Option<f64>, Error> {
+        let key = key.as_ref();
+        parse_number_value(
+            key,
+            state::get_at(self.store.idx(), ord as i64, key),
+            "float64",
+        )
+    }
+
+    /// Same as `get_last` but returns an error when the value is not a valid `float64`
+    pub fn try_get_last<K: AsRef<str>>(&self, key: K) -> Result<Option<f64>, Error> {
+        let key = key.as_ref();
+        parse_number_value(key, state::get_last(self.store.idx(), key), "float64")
+    }
+
+    /// Same as `get_first` but returns an error when the value is not a valid `float64`
+    pub fn try_get_first<K: AsRef<str>>(&self, key: K) -> Result<Option<f64>, Error> {
+        let key = key.as_ref();
+        parse_number_value(key, state::get_first(self.store.idx(), key), "float64")
+    }
+}
+
+/// StoreGetProto is a struct representing a read only store `store` holding protobuf
+/// values, encoded with [crate::proto::encode], reads return the values decoded as `T`
+///
+/// A key whose value is empty reads as `None`, like an absent key. A value that can't be
+/// decoded as a `T` is logged and also reads as `None`.
+pub struct StoreGetProto<T> {
+    store: StoreGet,
+    value_type: PhantomData<T>,
+}
+
+impl<T: Default + prost::Message> StoreGetProto<T> {
+    /// Return a StoreGetProto object with a store index set
+    pub fn new(idx: u32) -> StoreGetProto<T> {
+        StoreGetProto {
+            store: StoreGet::new(idx),
+            value_type: PhantomData,
+        }
+    }
+
+    /// Reads a key like [StoreGet::get_at] and decodes its value
+    pub fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<T> {
+        let key = key.as_ref();
+        or_log(parse_proto_value(
+            key,
+            state::get_at(self.store.idx(), ord as i64, key),
+        ))
+    }
+
+    /// Reads a key like [StoreGet::get_last] and decodes its value
+    pub fn get_last<K: AsRef<str>>(&self, key: K) -> Option<T> {
+        let key = key.as_ref();
+        or_log(parse_proto_value(
+            key,
+            state::get_last(self.store.idx(), key),
+        ))
+    }
+
+    /// Reads a key like [StoreGet::get_first] and decodes its value
+    pub fn get_first<K: AsRef<str>>(&self, key: K) -> Option<T> {
+        let key = key.as_ref();
+        or_log(parse_proto_value(
+            key,
+            state::get_first(self.store.idx(), key),
+        ))
+    }
+}
+
+/// FromStoreBytes decodes the value of a key read from a store, it's what
+/// [StoreGet::get_last_as] decodes values with
+///
+/// It's implemented for the values of the built-in stores, each following the encoding
+/// of the matching writer: `i64`, `f64`, [BigInt] and [BigDecimal] for `int64`, `float64`,
+/// `bigint` and `bigfloat` values, which are written as their decimal representation and
+/// read as `None` when empty, `String` for UTF-8 values and `Vec<u8>` for the raw bytes.
+/// Implement it to read the values of a custom encoding.
+///
+/// # Examples
+///
+/// ```
+/// use substreams::errors::Error;
+/// use substreams::store::FromStoreBytes;
+///
+/// // a `<price>@<block>` value
+/// struct Quote {
+///     price: u64,
+///     block: u64,
+/// }
+///
+/// impl FromStoreBytes for Quote {
+///     fn from_store_bytes(bytes: Vec<u8>) -> Result<Quote, Error> {
+///         let value = String::from_store_bytes(bytes)?;
+///         let (price, block) = value
+///             .split_once('@')
+///             .ok_or_else(|| Error::Decode(format!("{} is not a quote", value)))?;
+///         Ok(Quote {
+///             price: price.parse()?,
+///             block: block.parse()?,
+///         })
+///     }
+/// }
+/// ```
+pub trait FromStoreBytes: Sized {
+    /// Decodes the value of a key, an error is reported with the key it was read from
+    fn from_store_bytes(bytes: Vec<u8>) -> Result<Self, Error>;
+
+    /// Decodes the value read from `key`, `None` when the key is absent, with
+    /// `from_store_bytes` by default. The number types override it to read an empty
+    /// value as `None` too, like the typed readers such as [StoreGetInt64] do.
+    fn from_store_value(key: &str, value: Option<Vec<u8>>) -> Result<Option<Self>, Error> {
+        value
+            .map(|bytes| {
+                Self::from_store_bytes(bytes)
+                    .map_err(|e| e.context(format!("value of key {}", key)))
+            })
+            .transpose()
+    }
+}
+
+macro_rules! impl_decimal_from_store_bytes {
+    ($($t:ty => $value_type:expr),*) => {
+        $(
+            impl FromStoreBytes for $t {
+                fn from_store_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+                    std::str::from_utf8(&bytes)
+                        .ok()
+                        .and_then(|value| value.parse().ok())
+                        .ok_or_else(|| Error::Unexpected(format!("not a valid {}", $value_type)))
+                }
+
+                fn from_store_value(
+                    key: &str,
+                    value: Option<Vec<u8>>,
+                ) -> Result<Option<Self>, Error> {
+                    parse_number_value(key, value, $value_type)
+                }
+            }
+        )*
+    };
+}
+
+impl_decimal_from_store_bytes!(
+    i64 => "int64",
+    f64 => "float64",
+    BigDecimal => "bigfloat"
+);
+
+impl FromStoreBytes for BigInt {
+    fn from_store_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        decode_bigint(&bytes).ok_or_else(|| Error::Unexpected("not a valid bigint".to_string()))
+    }
+
+    fn from_store_value(key: &str, value: Option<Vec<u8>>) -> Result<Option<Self>, Error> {
+        parse_bigint_value(key, value)
+    }
+}
+
+impl FromStoreBytes for String {
+    fn from_store_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        String::from_utf8(bytes).map_err(|e| Error::Decode(e.to_string()))
+    }
+}
+
+impl FromStoreBytes for Vec<u8> {
+    fn from_store_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        Ok(bytes)
+    }
+}
+
+fn decode_value<T: FromStoreBytes>(key: &str, value: Option<Vec<u8>>) -> Result<Option<T>, Error> {
+    T::from_store_value(key, value)
+}
+
+fn parse_proto_value<T: Default + prost::Message>(
+    key: &str,
+    value: Option<Vec<u8>>,
+) -> Result<Option<T>, Error> {
+    match value {
+        None => Ok(None),
+        Some(bytes) if bytes.is_empty() => Ok(None),
+        Some(bytes) => crate::proto::decode(&bytes).map(Some).map_err(|e| {
+            Error::Unexpected(format!("value of key {} can't be decoded: {}", key, e))
+        }),
+    }
+}
+
+fn parse_bigint_value(key: &str, value: Option<Vec<u8>>) -> Result<Option<BigInt>, Error> {
+    match value {
+        None => Ok(None),
+        Some(bytes) if bytes.is_empty() => Ok(None),
+        Some(bytes) => decode_bigint(&bytes).map(Some).ok_or_else(|| {
+            Error::Unexpected(format!("value of key {} is not a valid bigint", key))
+        }),
+    }
+}
+
+// The host stores `int64` and `float64` values as their decimal representation
+fn parse_number_value<T: std::str::FromStr>(
+    key: &str,
+    value: Option<Vec<u8>>,
+    value_type: &str,
+) -> Result<Option<T>, Error> {
+    match value {
+        None => Ok(None),
+        Some(bytes) if bytes.is_empty() => Ok(None),
+        Some(bytes) => std::str::from_utf8(&bytes)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .map(Some)
+            .ok_or_else(|| {
+                Error::Unexpected(format!(
+                    "value of key {} is not a valid {}",
+                    key, value_type
+                ))
+            }),
+    }
+}
+
+fn or_log<T>(value: Result<Option<T>, Error>) -> Option<T> {
+    value.unwrap_or_else(|err| {
+        crate::log::info!("{}", err);
+        None
+    })
+}
+
+/// Reads `keys` from both `a` and `b` and returns, for each key, the key along with its
+/// value in `a` and its value in `b`, like `get_last` would return them. Each store is read
+/// with a single host call for all the keys, instead of one call per key and store.
+///
+/// The result follows the order of `keys`, a key given twice is returned twice. A key
+/// absent from a store has a `None` value for that store, or the store default when it
+/// was built with [StoreGet::with_default], and a key absent from both stores is still
+/// returned, with two `None` values.
+pub fn join<K: AsRef<str>>(
+    a: &StoreGet,
+    b: &StoreGet,
+    keys: &[K],
+) -> Vec<(String, Option<Vec<u8>>, Option<Vec<u8>>)> {
+    join_values(
+        (a, state::get_last_many(a.idx(), keys)),
+        (b, state::get_last_many(b.idx(), keys)),
+        keys,
+    )
+}
+
+fn join_values<K: AsRef<str>>(
+    (a, a_values): (&StoreGet, HashMap<String, Vec<u8>>),
+    (b, b_values): (&StoreGet, HashMap<String, Vec<u8>>),
+    keys: &[K],
+) -> Vec<(String, Option<Vec<u8>>, Option<Vec<u8>>)> {
+    keys.iter()
+        .map(|key| {
+            let key = key.as_ref();
+            (
+                key.to_owned(),
+                a.or_default(a_values.get(key).cloned()),
+                b.or_default(b_values.get(key).cloned()),
+            )
+        })
+        .collect()
+}
+
+/// Chained is a read only view over multiple [StoreGet], queried in priority order
+///
+/// Each read tries the stores one after the other and returns the value of the first
+/// store that has the key, the following stores are not queried.
+pub struct Chained {
+    stores: Vec<StoreGet>,
+}
+
+impl Chained {
+    /// Return a Chained object querying `stores` in the given order
+    pub fn new(stores: Vec<StoreGet>) -> Chained {
+        Chained { stores }
+    }
+
+    /// Reads a key like [StoreGet::get_at] from the first store that has it
+    pub fn get_at<K: AsRef<str>>(&self, ord: u64, key: K) -> Option<Vec<u8>> {
+        self.stores
+            .iter()
+            .find_map(|store| store.get_at(ord, key.as_ref()))
+    }
+
+    /// Reads a key like [StoreGet::get_last] from the first store that has it
+    pub fn get_last<K: AsRef<str>>(&self, key: K) -> Option<Vec<u8>> {
+        self.stores
+            .iter()
+            .find_map(|store| store.get_last(key.as_ref()))
+    }
+
+    /// Reads a key like [StoreGet::get_first] from the first store that has it
+    pub fn get_first<K: AsRef<str>>(&self, key: K) -> Option<Vec<u8>> {
+        self.stores
+            .iter()
+            .find_map(|store| store.get_first(key.as_ref()))
+    }
+}
+
+/// ProtoCache memoizes the values of a [StoreGet] read with `get_last`, decoded as `T`
+///
+/// Each key is read from the host and decoded only once, an absent key is remembered as
+/// absent. The values of `get_last` don't change while the block is processed, so the
+/// cache is meant to be created at the start of the handler and dropped at its end.
+/// Reads at an ordinal with `get_at` depend on the ordinal and are not cached, keep using
+/// the wrapped [StoreGet] for them.
+///
+/// Values are shared with an [Rc], so values of several keys can be held at the same
+/// time, reading a key only borrows the cache.
+pub struct ProtoCache<'a, T> {
+    fetch: Box<dyn Fn(&str) -> Option<Vec<u8>> + 'a>,
+    values: RefCell<HashMap<String, Option<Rc<T>>>>,
+}
+
+impl<'a, T: Default + prost::Message> ProtoCache<'a, T> {
+    /// Return a ProtoCache reading keys from `store`
+    pub fn wrap(store: &'a StoreGet) -> ProtoCache<'a, T> {
+        ProtoCache::from_fn(move |key| store.get_last(key))
+    }
+
+    fn from_fn<F: Fn(&str) -> Option<Vec<u8>> + 'a>(fetch: F) -> ProtoCache<'a, T> {
+        ProtoCache {
+            fetch: Box::new(fetch),
+            values: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the decoded value of a key, reading it from the store only the first
+    /// time the key is requested
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value can't be decoded as a `T`.
+    pub fn get<K: AsRef<str>>(&self, key: K) -> Option<Rc<T>> {
+        let key = key.as_ref();
+        if let Some(value) = self.values.borrow().get(key) {
+            return value.clone();
+        }
+
+        let value = (self.fetch)(key).map(|bytes| {
+            Rc::new(
+                crate::proto::decode(&bytes)
+                    .unwrap_or_else(|e| panic!("value of key {} can't be decoded: {}", key, e)),
+            )
+        });
+        self.values
+            .borrow_mut()
+            .insert(key.to_owned(), value.clone());
+        value
+    }
+}
+
+#[cfg(feature = "json")]
+impl StoreGet {
+    /// Dumps all the keys of the store as a JSON object, each value decoded as `T`
+    /// and then serialized to JSON. Keys are sorted lexicographically.
+    ///
+    /// This is a development tool only, available with the `json` feature. It scans
+    /// the whole store and decodes every value on each call which is very expensive
+    /// on big stores, do not use it in production modules.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a value can't be decoded as a `T`.
+    pub fn dump_json<T: Default + prost::Message + serde::Serialize>(&self) -> String {
+        entries_to_json::<T>(state::scan_prefix(self.idx(), i64::MAX, ""))
+    }
+}
+
+#[cfg(feature = "json")]
+fn entries_to_json<T: Default + prost::Message + serde::Serialize>(
+    entries: Vec<(String, Vec<u8>)>,
+) -> String {
+    let mut object = serde_json::Map::with_capacity(entries.len());
+    for (key, bytes) in entries {
+        let value: T = crate::proto::decode(&bytes)
+            .unwrap_or_else(|e| panic!("value of key {} can't be decoded: {}", key, e));
+        let json = serde_json::to_value(&value)
+            .unwrap_or_else(|e| panic!("value of key {} can't be serialized: {}", key, e));
+        object.insert(key, json);
+    }
+
+    serde_json::to_string_pretty(&serde_json::Value::Object(object))
+        .expect("JSON object is always serializable")
+}
+
+fn entries_to_map<T, K, F>(entries: Vec<(String, Vec<u8>)>, mut key_fn: F) -> HashMap<K, T>
+where
+    T: Default + prost::Message,
+    K: Eq + Hash,
+    F: FnMut(&T) -> K,
+{
+    entries
+        .into_iter()
+        .map(|(key, bytes)| {
+            let value: T = crate::proto::decode(&bytes)
+                .unwrap_or_else(|e| panic!("value of key {} can't be decoded: {}", key, e));
+            (key_fn(&value), value)
+        })
+        .collect()
+}
+
+fn modify_value<T, F>(key: &str, current: Option<Vec<u8>>, f: F) -> Vec<u8>
+where
+    T: Default + prost::Message,
+    F: FnOnce(&mut T),
+{
+    let mut value: T = match current {
+        Some(bytes) => crate::proto::decode(&bytes)
+            .unwrap_or_else(|e| panic!("value of key {} can't be decoded: {}", key, e)),
+        None => T::default(),
+    };
+    f(&mut value);
+
+    crate::proto::encode(&value)
+        .unwrap_or_else(|e| panic!("value of key {} can't be encoded: {}", key, e))
+}
+
+fn value_range(value: &[u8], offset: u64, len: u64) -> Option<Vec<u8>> {
+    if offset > value.len() as u64 {
+        return None;
+    }
+
+    let start = offset as usize;
+    let end = offset.saturating_add(len).min(value.len() as u64) as usize;
+    Some(value[start..end].to_vec())
+}
+
+fn assert_finite(key: &str, value: f64) {
+    if !value.is_finite() {
+        panic!("value {} of key {} is not a finite float64", value, key);
+    }
+}
+
+fn split_value(bytes: &[u8], sep: &str) -> Option<Vec<String>> {
+    let value = std::str::from_utf8(bytes).ok()?;
+    if value.is_empty() {
+        return Some(vec![]);
+    }
+
+    let value = value.strip_suffix(sep).unwrap_or(value);
+    Some(value.split(sep).map(str::to_owned).collect())
+}
+
+fn parse_scores(entries: Vec<(String, Vec<u8>)>) -> Result<Vec<(String, i64)>, Error> {
+    entries
+        .into_iter()
+        .map(|(key, bytes)| match decode_int64(&bytes) {
+            Some(score) => Ok((key, score)),
+            None => Err(Error::Unexpected(format!(
+                "value of key {} is not a valid int64",
+                key
+            ))),
+        })
+        .collect()
+}
+
+fn score_range(mut scores: Vec<(String, i64)>, min: i64, max: i64) -> Vec<(String, i64)> {
+    scores.retain(|(_, score)| *score >= min && *score <= max);
+    scores.sort_by(|(a_key, a_score), (b_key, b_score)| {
+        a_score.cmp(b_score).then_with(|| a_key.cmp(b_key))
+    });
+    scores
+}
+
+fn score_top_n(mut scores: Vec<(String, i64)>, n: usize) -> Vec<(String, i64)> {
+    scores.sort_by(|(a_key, a_score), (b_key, b_score)| {
+        b_score.cmp(a_score).then_with(|| a_key.cmp(b_key))
+    });
+    scores.truncate(n);
+    scores
+}
+
+fn decode_int64(bytes: &[u8]) -> Option<i64> {
+    std::str::from_utf8(bytes).ok()?.parse().ok()
+}
+
+fn decode_bigint(bytes: &[u8]) -> Option<BigInt> {
+    BigInt::parse_bytes(bytes, 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::Error;
+    use crate::proto;
+    #[cfg(feature = "json")]
+    use crate::store::entries_to_json;
+    use crate::store::{
+        assert_finite, decode_value, entries_to_map, join_values, modify_value, parse_bigint_value,
+        parse_number_value, parse_proto_value, parse_scores, score_range, score_top_n, split_value,
+        value_range, with_separator, FromStoreBytes, KeyField, Ordinal, ProtoCache, StoreAddInt64,
+        StoreGet, StoreSet,
+    };
+    use bigdecimal::BigDecimal;
+    use num_bigint::BigInt;
+    use std::cell::Cell;
+    use std::collections::HashMap;
+
+    #[derive(Clone, PartialEq, ::prost::Message)]
+    struct Pair {
+        #[prost(string, tag = "1")]
+        token0: String,
+    }
+
+    #[test]
+    fn it_reads_each_key_once() {
+        let calls = Cell::new(0);
+        let cache = ProtoCache::<Pair>::from_fn(|key| {
+            calls.set(calls.get() + 1);
+            match key {
+                "pair:a" => Some(
+                    proto::encode(&Pair {
+                        token0: "a".to_owned(),
+                    })
+                    .unwrap(),
+                ),
+                _ => None,
+            }
+        });
+
+        let first = cache.get("pair:a").unwrap();
+        for _ in 0..3 {
+            let pair = cache.get("pair:a").unwrap();
+            assert_eq!(pair.token0, "a");
+            assert!(std::rc::Rc::ptr_eq(&first, &pair));
+            assert_eq!(cache.get("pair:b"), None);
+        }
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn it_parses_bigint_values() {
+        assert_eq!(
+            parse_bigint_value("a", Some(b"-42".to_vec())).unwrap(),
+            Some(BigInt::from(-42))
+        );
+        assert_eq!(parse_bigint_value("a", Some(vec![])).unwrap(), None);
+        assert_eq!(parse_bigint_value("a", None).unwrap(), None);
+        assert_eq!(
+            parse_bigint_value("a", Some(b"12abc".to_vec()))
+                .unwrap_err()
+                .to_string(),
+            "unexpected error: `value of key a is not a valid bigint`"
+        );
+    }
+
+    #[test]
+    fn it_parses_number_values() {
+        assert_eq!(
+            parse_number_value::<i64>("a", Some(b"-42".to_vec()), "int64").unwrap(),
+            Some(-42)
+        );
+        assert_eq!(
+            parse_number_value::<i64>("a", Some(i64::MIN.to_string().into_bytes()), "int64")
+                .unwrap(),
+            Some(i64::MIN)
+        );
+        assert_eq!(
+            parse_number_value::<f64>("a", Some(b"1.5".to_vec()), "float64").unwrap(),
+            Some(1.5)
+        );
+        assert_eq!(
+            parse_number_value::<f64>("a", Some(b"-2e-3".to_vec()), "float64").unwrap(),
+            Some(-0.002)
+        );
+        assert_eq!(
+            parse_number_value::<f64>("a", Some(b"7".to_vec()), "float64").unwrap(),
+            Some(7.0)
+        );
+        assert_eq!(
+            parse_number_value::<i64>("a", Some(vec![]), "int64").unwrap(),
+            None
+        );
+        assert_eq!(
+            parse_number_value::<f64>("a", None, "float64").unwrap(),
+            None
+        );
+        assert_eq!(
+            parse_number_value::<i64>("a", Some(b"1.5".to_vec()), "int64")
+                .unwrap_err()
+                .to_string(),
+            "unexpected error: `value of key a is not a valid int64`"
+        );
+        assert!(parse_number_value::<f64>("a", Some(vec![0xff]), "float64").is_err());
+    }
+
+    #[test]
+    fn it_parses_proto_values() {
+        let pair = Pair {
+            token0: "weth".to_owned(),
+        };
+        let value = parse_proto_value::<Pair>("pair:a", Some(proto::encode(&pair).unwrap()));
+        assert_eq!(value.unwrap(), Some(pair));
+
+        assert_eq!(
+            parse_proto_value::<Pair>("pair:a", Some(vec![])).unwrap(),
+            None
+        );
+        assert_eq!(parse_proto_value::<Pair>("pair:a", None).unwrap(), None);
+        assert!(parse_proto_value::<Pair>("pair:a", Some(vec![0x0a, 0x05]))
+            .unwrap_err()
+            .to_string()
+            .starts_with("unexpected error: `value of key pair:a can't be decoded"));
+    }
+
+    #[test]
+    fn it_modifies_value_across_updates() {
+        let value = modify_value("pair:a", None, |pair: &mut Pair| {
+            assert_eq!(pair.token0, "");
+            pair.token0.push_str("we");
+        });
+        let value = modify_value("pair:a", Some(value), |pair: &mut Pair| {
+            pair.token0.push_str("th")
+        });
+
+        let pair: Pair = proto::decode(&value).unwrap();
+        assert_eq!(pair.token0, "weth");
+    }
+
+    #[test]
+    fn it_maps_entries_by_field() {
+        let entries: Vec<(String, Vec<u8>)> = vec!["weth", "usdc", "dai"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, token0)| {
+                let pair = Pair {
+                    token0: token0.to_owned(),
+                };
+                (format!("pair:{}", i), proto::encode(&pair).unwrap())
+            })
+            .collect();
+
+        let pairs = entries_to_map(entries, |pair: &Pair| pair.token0.clone());
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(pairs["usdc"].token0, "usdc");
+        assert_eq!(pairs["dai"].token0, "dai");
+        assert!(pairs.get("uni").is_none());
+    }
+
+    #[test]
+    fn it_joins_values_by_key() {
+        let values = |entries: &[(&str, &str)]| -> HashMap<String, Vec<u8>> {
+            entries
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.as_bytes().to_vec()))
+                .collect()
+        };
+        let pairs = StoreGet::new(0);
+        let tokens = StoreGet::with_default(1, b"unknown".to_vec());
+
+        let joined = join_values(
+            (&pairs, values(&[("a", "pair:a"), ("b", "pair:b")])),
+            (&tokens, values(&[("b", "token:b"), ("c", "token:c")])),
+            &["c", "b", "a", "d"],
+        );
+
+        let some = |value: &str| Some(value.as_bytes().to_vec());
+        assert_eq!(
+            joined,
+            vec![
+                ("c".to_owned(), None, some("token:c")),
+                ("b".to_owned(), some("pair:b"), some("token:b")),
+                ("a".to_owned(), some("pair:a"), some("unknown")),
+                ("d".to_owned(), None, some("unknown")),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_returns_values_in_key_order() {
+        let values: HashMap<String, Vec<u8>> = vec![("b", "token:b"), ("a", "token:a")]
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value.as_bytes().to_vec()))
+            .collect();
+        let keys = ["a", "c", "b", "a"];
+
+        let ordered = StoreGet::new(0).in_key_order(values.clone(), &keys);
+        assert_eq!(ordered.len(), keys.len());
+        assert_eq!(
+            ordered,
+            vec![
+                Some(b"token:a".to_vec()),
+                None,
+                Some(b"token:b".to_vec()),
+                Some(b"token:a".to_vec()),
+            ]
+        );
+
+        let ordered = StoreGet::with_default(0, b"unknown".to_vec()).in_key_order(values, &keys);
+        assert_eq!(ordered[1], Some(b"unknown".to_vec()));
+    }
+
+    #[test]
+    fn it_reads_value_range() {
+        let value = b"line1\nline2\n";
+
+        assert_eq!(value_range(value, 0, 6), Some(b"line1\n".to_vec()));
+        assert_eq!(value_range(value, 6, 100), Some(b"line2\n".to_vec()));
+        assert_eq!(value_range(value, 3, 0), Some(vec![]));
+        assert_eq!(value_range(value, 12, 10), Some(vec![]));
+        assert_eq!(value_range(value, 13, 1), None);
+        assert_eq!(
+            value_range(value, 2, u64::MAX),
+            Some(b"ne1\nline2\n".to_vec())
+        );
+    }
+
+    #[test]
+    fn it_separates_appended_values() {
+        assert_eq!(with_separator(0, "a", ","), b"a".to_vec());
+        assert_eq!(with_separator(1, "b", ","), b",b".to_vec());
+        assert_eq!(with_separator(3, "c", ", "), b", c".to_vec());
+
+        let mut list = vec![];
+        for value in ["a", "b", "c"] {
+            list.extend(with_separator(list.len() as u64, value, "\n"));
+        }
+        assert_eq!(list, b"a\nb\nc".to_vec());
+        assert_eq!(
+            split_value(&list, "\n"),
+            Some(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+        );
+    }
+
+    #[test]
+    fn it_decodes_values_as_built_in_types() {
+        let value = |bytes: &[u8]| Some(bytes.to_vec());
+
+        assert_eq!(
+            decode_value::<i64>("count", value(b"-42")).unwrap(),
+            Some(-42)
+        );
+        assert_eq!(
+            decode_value::<f64>("price", value(b"1.5")).unwrap(),
+            Some(1.5)
+        );
+        assert_eq!(
+            decode_value::<BigInt>("supply", value(b"123456789012345678901234567890")).unwrap(),
+            BigInt::parse_bytes(b"123456789012345678901234567890", 10)
+        );
+        assert_eq!(
+            decode_value::<BigDecimal>("volume", value(b"10.05")).unwrap(),
+            BigDecimal::parse_bytes(b"10.05", 10)
+        );
+        assert_eq!(
+            decode_value::<String>("name", value(b"Bored Ape")).unwrap(),
+            Some("Bored Ape".to_owned())
+        );
+        assert_eq!(
+            decode_value::<Vec<u8>>("owner", value(&[0xbc, 0x4c])).unwrap(),
+            Some(vec![0xbc, 0x4c])
+        );
+        assert_eq!(
+            decode_value::<Vec<u8>>("owner", value(b"")).unwrap(),
+            Some(vec![])
+        );
+        assert_eq!(decode_value::<i64>("count", None).unwrap(), None);
+        assert_eq!(decode_value::<i64>("count", value(b"")).unwrap(), None);
+        assert_eq!(decode_value::<f64>("price", value(b"")).unwrap(), None);
+        assert_eq!(decode_value::<BigInt>("supply", value(b"")).unwrap(), None);
+        assert_eq!(
+            decode_value::<BigDecimal>("volume", value(b"")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn it_reports_undecodable_values() {
+        let err = decode_value::<i64>("count", Some(b"1.5".to_vec())).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unexpected error: `value of key count is not a valid int64`"
+        );
+
+        let err = decode_value::<BigInt>("supply", Some(b"12abc".to_vec())).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unexpected error: `value of key supply is not a valid bigint`"
+        );
+        assert!(decode_value::<String>("name", Some(vec![0xff])).is_err());
+    }
+
+    #[test]
+    fn it_decodes_values_with_custom_impl() {
+        #[derive(Debug, PartialEq)]
+        struct Quote {
+            price: u64,
+            block: u64,
+        }
+
+        impl FromStoreBytes for Quote {
+            fn from_store_bytes(bytes: Vec<u8>) -> Result<Quote, Error> {
+                let value = String::from_store_bytes(bytes)?;
+                let (price, block) = value
+                    .split_once('@')
+                    .ok_or_else(|| Error::Decode(format!("{} is not a quote", value)))?;
+                Ok(Quote {
+                    price: price.parse()?,
+                    block: block.parse()?,
+                })
+            }
+        }
+
+        assert_eq!(
+            decode_value::<Quote>("quote", Some(b"1200@15000000".to_vec())).unwrap(),
+            Some(Quote {
+                price: 1200,
+                block: 15000000
+            })
+        );
+        assert_eq!(
+            decode_value::<Quote>("quote", Some(b"1200".to_vec()))
+                .unwrap_err()
+                .to_string(),
+            "value of key quote: decode error: 1200 is not a quote"
+        );
+    }
+
+    #[test]
+    fn it_creates_one_writer_per_run() {
+        crate::handlers::begin_invocation("store_totals");
+        StoreSet::new();
+        crate::handlers::begin_invocation("store_totals");
+        StoreSet::new();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "StoreAddInt64 can't be created, a writable store was already created by this module run"
+    )]
+    fn it_panics_on_second_writer_in_run() {
+        crate::handlers::begin_invocation("store_totals");
+        let _totals = StoreSet::new();
+        StoreAddInt64::new();
+    }
+
+    #[test]
+    fn it_checks_store_index_once() {
+        let store = StoreGet::new(2);
+        let checks = Cell::new(0);
+        let is_bound = |idx: u32| {
+            checks.set(checks.get() + 1);
+            idx < 3
+        };
+
+        store.ensure_bound(is_bound);
+        store.ensure_bound(is_bound);
+        assert_eq!(checks.get(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "store index 3 is not bound to this module")]
+    fn it_panics_on_unbound_store_index() {
+        StoreGet::new(3).ensure_bound(|idx| idx < 3);
+    }
+
+    #[test]
+    fn it_returns_default_for_absent_key() {
+        let store = StoreGet::with_default(0, b"0".to_vec());
+        assert_eq!(store.or_default(None), Some(b"0".to_vec()));
+        assert_eq!(store.or_default(Some(b"42".to_vec())), Some(b"42".to_vec()));
+
+        assert_eq!(StoreGet::new(0).or_default(None), None);
+    }
+
+    #[test]
+    fn it_orders_ordinals() {
+        let ordinals: Vec<Ordinal> = vec![Ordinal::new(7), 3.into(), Ordinal::default()];
+        let mut sorted = ordinals.clone();
+        sorted.sort();
+
+        assert_eq!(
+            sorted,
+            vec![Ordinal::new(0), Ordinal::new(3), Ordinal::new(7)]
+        );
+        assert_eq!(u64::from(ordinals[0]), 7);
+        assert_eq!(Ordinal::from(u64::MAX).value(), u64::MAX);
+    }
+
+    struct TestLog(u32);
+
+    impl crate::eth::Log for TestLog {
+        fn index(&self) -> u32 {
+            0
+        }
+
+        fn block_index(&self) -> u32 {
+            self.0
+        }
+    }
+
+    struct TestTransaction(Vec<TestLog>);
+
+    impl crate::eth::Transaction for TestTransaction {
+        type Log = TestLog;
+
+        fn hash(&self) -> &[u8] {
+            &[]
+        }
+
+        fn index(&self) -> u32 {
+            0
+        }
+
+        fn to(&self) -> &[u8] {
+            &[]
+        }
+
+        fn logs(&self) -> &[TestLog] {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn it_builds_ordinal_from_log() {
+        assert_eq!(Ordinal::from_log(&TestLog(42)).value() >> 32, 42);
+        assert!(Ordinal::from_log(&TestLog(1)) < Ordinal::from_log(&TestLog(2)));
+    }
+
+    #[test]
+    fn it_orders_ordinals_from_logs_and_calls() {
+        let first = TestTransaction(vec![TestLog(0), TestLog(1)]);
+        let second = TestTransaction(vec![TestLog(3), TestLog(2)]);
+        let mut items = vec![
+            (Ordinal::from_log(&second.0[0]), "trx1:log1"),
+            (Ordinal::from_call(&second, 1).unwrap(), "trx1:call1"),
+            (Ordinal::from_log(&first.0[1]), "trx0:log1"),
+            (Ordinal::from_call(&first, 0).unwrap(), "trx0:call0"),
+            (Ordinal::from_log(&second.0[1]), "trx1:log0"),
+            (Ordinal::from_call(&second, 0).unwrap(), "trx1:call0"),
+            (Ordinal::from_log(&first.0[0]), "trx0:log0"),
+        ];
+
+        items.sort();
+        let sorted: Vec<&str> = items.into_iter().map(|(_, item)| item).collect();
+
+        assert_eq!(
+            sorted,
+            vec![
+                "trx0:call0",
+                "trx0:log0",
+                "trx0:log1",
+                "trx1:call0",
+                "trx1:call1",
+                "trx1:log0",
+                "trx1:log1",
+            ]
+        );
+        assert_eq!(Ordinal::from_call(&TestTransaction(vec![]), 0), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "call index 2147483648 is too big")]
+    fn it_panics_on_call_index_overflowing_log_position() {
+        Ordinal::from_call(&TestTransaction(vec![TestLog(0)]), 1 << 31);
+    }
+
+    #[test]
+    fn it_round_trips_key_fields() {
+        let bytes = vec![0x00, 0xab, 0xff];
+        assert_eq!(bytes.to_key_field(), "00abff");
+        assert_eq!(Vec::<u8>::parse_key_field("00abff"), Some(bytes));
+        assert_eq!(Vec::<u8>::parse_key_field("0xab"), None);
+
+        assert_eq!(i64::MIN.to_key_field(), "-9223372036854775808");
+        assert_eq!(
+            i64::parse_key_field(&i64::MIN.to_key_field()),
+            Some(i64::MIN)
+        );
+        assert_eq!(u8::parse_key_field("256"), None);
+
+        assert_eq!(String::parse_key_field("a:b"), Some("a:b".to_owned()));
+    }
+
+    #[test]
+    fn it_accepts_finite_floats() {
+        assert_finite("key", 1.5);
+        assert_finite("key", -0.0);
+        assert_finite("key", f64::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "value NaN of key total is not a finite float64")]
+    fn it_rejects_nan() {
+        assert_finite("total", f64::NAN);
+    }
+
+    #[test]
+    #[should_panic(expected = "value inf of key total is not a finite float64")]
+    fn it_rejects_infinity() {
+        assert_finite("total", f64::INFINITY);
+    }
+
+    #[test]
+    #[should_panic(expected = "value -inf of key total is not a finite float64")]
+    fn it_rejects_negative_infinity() {
+        assert_finite("total", f64::NEG_INFINITY);
+    }
+
+    fn scores() -> Vec<(String, i64)> {
+        vec![
+            ("carol".to_owned(), 30),
+            ("alice".to_owned(), 10),
+            ("dave".to_owned(), -5),
+            ("bob".to_owned(), 30),
+        ]
+    }
+
+    #[test]
+    fn it_ranges_by_score() {
+        assert_eq!(
+            score_range(scores(), 0, 30),
+            vec![
+                ("alice".to_owned(), 10),
+                ("bob".to_owned(), 30),
+                ("carol".to_owned(), 30)
+            ]
+        );
+        assert_eq!(score_range(scores(), 11, 29), vec![]);
+    }
+
+    #[test]
+    fn it_takes_top_n() {
+        assert_eq!(
+            score_top_n(scores(), 3),
+            vec![
+                ("bob".to_owned(), 30),
+                ("carol".to_owned(), 30),
+                ("alice".to_owned(), 10)
+            ]
+        );
+        assert_eq!(score_top_n(scores(), 10).len(), 4);
+    }
+
+    #[test]
+    fn it_parses_scores() {
+        assert_eq!(
+            parse_scores(vec![
+                ("alice".to_owned(), b"10".to_vec()),
+                ("dave".to_owned(), b"-5".to_vec())
+            ])
+            .unwrap(),
+            vec![("alice".to_owned(), 10), ("dave".to_owned(), -5)]
+        );
+        assert_eq!(
+            parse_scores(vec![
+                ("alice".to_owned(), b"10".to_vec()),
+                ("bob".to_owned(), b"ten".to_vec())
+            ])
+            .unwrap_err()
+            .to_string(),
+            "unexpected error: `value of key bob is not a valid int64`"
+        );
+    }
+
+    #[test]
+    fn it_splits_empty_value() {
+        assert_eq!(split_value(b"", ";"), Some(vec![]));
+    }
+
+    #[test]
+    fn it_splits_single_entry() {
+        assert_eq!(split_value(b"a;", ";"), Some(vec!["a".to_owned()]));
+        assert_eq!(split_value(b"a", ";"), Some(vec!["a".to_owned()]));
+    }
+
+    #[test]
+    fn it_splits_multiple_entries() {
+        assert_eq!(
+            split_value(b"a;;b;c;", ";"),
+            Some(vec![
+                "a".to_owned(),
+                "".to_owned(),
+                "b".to_owned(),
+                "c".to_owned()
+            ])
+        );
+        assert_eq!(split_value(&[0xff, b';'], ";"), None);
+    }
+
+    #[cfg(feature = "json")]
+    #[derive(Clone, PartialEq, ::prost::Message, serde::Serialize)]
+    struct Token {
+        #[prost(string, tag = "1")]
+        symbol: String,
+        #[prost(uint64, tag = "2")]
+        decimals: u64,
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn it_dumps_entries_as_json() {
+        let token = |symbol: &str, decimals| {
+            proto::encode(&Token {
+                symbol: symbol.to_owned(),
+                decimals,
+            })
+            .unwrap()
+        };
+
+        let dump = entries_to_json::<Token>(vec![
+            ("token:a".to_owned(), token("AAA", 18)),
+            ("token:b".to_owned(), token("BBB", 6)),
+        ]);
+
+        let value: serde_json::Value = serde_json::from_str(&dump).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "token:a": { "symbol": "AAA", "decimals": 18 },
+                "token:b": { "symbol": "BBB", "decimals": 6 },
+            })
+        );
     }
 }
@@ -0,0 +1,144 @@
+//! Time helpers for Substreams.
+//!
+//! This crate offers helpers to turn block timestamps into deterministic bucket
+//! keys, for modules aggregating values over time windows.
+//!
+//! All buckets are computed in UTC. The canonical key formats are:
+//!
+//! * [Granularity::Hour]: `YYYY-MM-DDTHH`, like `2022-08-15T14`
+//! * [Granularity::Day]: `YYYY-MM-DD`, like `2022-08-15`
+//! * [Granularity::Week]: `W` followed by the date of the Monday starting the week,
+//!   like `W2022-08-15`. Weeks start on Monday at midnight.
+//! * [Granularity::Month]: `YYYY-MM`, like `2022-08`
+//! * [Granularity::Year]: `YYYY`, like `2022`
+//!
+
+const SECONDS_PER_HOUR: i64 = 60 * 60;
+const SECONDS_PER_DAY: i64 = 24 * SECONDS_PER_HOUR;
+
+/// Granularity is the size of a time bucket
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Granularity {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+/// Returns the key of the bucket containing `unix_seconds` for the given granularity.
+///
+/// # Examples
+///
+/// ```
+/// use substreams::time::{bucket_key, Granularity};
+///
+/// assert_eq!(bucket_key(1660573000, Granularity::Week), "W2022-08-15");
+/// ```
+pub fn bucket_key(unix_seconds: i64, granularity: Granularity) -> String {
+    let days = unix_seconds.div_euclid(SECONDS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+
+    match granularity {
+        Granularity::Hour => {
+            let hour = unix_seconds.rem_euclid(SECONDS_PER_DAY) / SECONDS_PER_HOUR;
+            format!("{:04}-{:02}-{:02}T{:02}", year, month, day, hour)
+        }
+        Granularity::Day => format!("{:04}-{:02}-{:02}", year, month, day),
+        Granularity::Week => {
+            // Day 0, 1970-01-01, is a Thursday, Monday is 3 days before.
+            let monday = days - (days + 3).rem_euclid(7);
+            let (year, month, day) = civil_from_days(monday);
+            format!("W{:04}-{:02}-{:02}", year, month, day)
+        }
+        Granularity::Month => format!("{:04}-{:02}", year, month),
+        Granularity::Year => format!("{:04}", year),
+    }
+}
+
+/// Returns the keys of the buckets containing `unix_seconds`, one for each of the
+/// given granularities in the same order, so the same value can be written to each
+/// of them.
+///
+/// # Examples
+///
+/// ```
+/// use substreams::time::{bucket_keys, Granularity};
+///
+/// let keys = bucket_keys(1660573000, &[Granularity::Day, Granularity::Month]);
+/// assert_eq!(keys, vec!["2022-08-15", "2022-08"]);
+/// ```
+pub fn bucket_keys(unix_seconds: i64, granularities: &[Granularity]) -> Vec<String> {
+    granularities
+        .iter()
+        .map(|granularity| bucket_key(unix_seconds, *granularity))
+        .collect()
+}
+
+// Converts a number of days since 1970-01-01 to a (year, month, day) date of the
+// proleptic Gregorian calendar, see http://howardhinnant.github.io/date_algorithms.html
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = if month <= 2 {
+        yoe + era * 400 + 1
+    } else {
+        yoe + era * 400
+    };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::time::{bucket_key, bucket_keys, Granularity};
+
+    #[test]
+    fn it_builds_bucket_keys() {
+        // 2022-08-15 14:16:40 UTC, a Monday
+        let keys = bucket_keys(
+            1660573000,
+            &[
+                Granularity::Hour,
+                Granularity::Day,
+                Granularity::Week,
+                Granularity::Month,
+                Granularity::Year,
+            ],
+        );
+
+        assert_eq!(
+            keys,
+            vec![
+                "2022-08-15T14",
+                "2022-08-15",
+                "W2022-08-15",
+                "2022-08",
+                "2022"
+            ]
+        );
+    }
+
+    #[test]
+    fn it_starts_weeks_on_monday() {
+        // 2022-12-31 23:59:59 UTC, a Saturday
+        assert_eq!(bucket_key(1672531199, Granularity::Week), "W2022-12-26");
+        // 2023-01-01 00:00:00 UTC, a Sunday
+        assert_eq!(bucket_key(1672531200, Granularity::Week), "W2022-12-26");
+        // 2023-01-02 00:00:00 UTC, a Monday
+        assert_eq!(bucket_key(1672617600, Granularity::Week), "W2023-01-02");
+    }
+
+    #[test]
+    fn it_handles_timestamps_before_epoch() {
+        assert_eq!(bucket_key(-1, Granularity::Hour), "1969-12-31T23");
+        assert_eq!(bucket_key(-1, Granularity::Week), "W1969-12-29");
+        assert_eq!(bucket_key(0, Granularity::Week), "W1969-12-29");
+    }
+}
@@ -9,6 +9,7 @@ import (
 	"io/ioutil"
 	"os"
 	"strconv"
+	"strings"
 	"sync"
 	"testing"
 	"time"
@@ -18,6 +19,8 @@ import (
 	"github.com/streamingfast/substreams/state"
 	"github.com/streamingfast/substreams/wasm"
 	"github.com/stretchr/testify/require"
+	"google.golang.org/protobuf/proto"
+	"google.golang.org/protobuf/types/known/wrapperspb"
 )
 
 func test_wasm_path(t *testing.T, wasmFile string) string {
@@ -35,6 +38,7 @@ func TestRustScript(t *testing.T) {
 		functionName string
 		parameters   []interface{}
 		builder      *state.Store
+		inputs       []*wasm.Input
 		assert       func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store)
 	}{
 		{
@@ -184,6 +188,38 @@ func TestRustScript(t *testing.T) {
 				require.False(t, found, "key_to_delete")
 			},
 		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_add_delete_prefix",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_ADD, "int64", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				require.Equal(t, map[string][]byte{"1:total": []byte("5")}, builder.KV)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_add_bigfloat_with_scale",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_ADD, "bigFloat", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				require.Equal(t, map[string][]byte{
+					"sum":     []byte("0.30"),
+					"rounded": []byte("1.24"),
+					"tie":     []byte("0.12"),
+				}, builder.KV)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_set_clear",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				require.Len(t, builder.KV, 0)
+				for _, key := range []string{"1:key", "2:key", "3:key"} {
+					_, found := builder.GetLast(key)
+					require.False(t, found, key)
+				}
+			},
+		},
 		{
 			wasmFile:     "testing_substreams.wasm",
 			functionName: "test_append_empty_string_on_same_key",
@@ -194,6 +230,14 @@ func TestRustScript(t *testing.T) {
 				require.Equal(t, "string1", string(value))
 			},
 		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_append_with_sep",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_APPEND, "some object", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				require.Equal(t, map[string][]byte{"key": []byte("string1,string2")}, builder.KV)
+			},
+		},
 		{
 			wasmFile:     "testing_substreams.wasm",
 			functionName: "test_append_string_on_same_key",
@@ -250,6 +294,828 @@ func TestRustScript(t *testing.T) {
 				require.Equal(t, "string2", string(value))
 			},
 		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_writable_store_first",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				mustNewInputStore(t, "a", map[string][]byte{"key": []byte("from_a")}),
+				mustNewInputStore(t, "b", map[string][]byte{"key": []byte("from_b")}),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("a")
+				require.True(t, found)
+				require.Equal(t, "from_a", string(value))
+				value, found = builder.GetLast("b")
+				require.True(t, found)
+				require.Equal(t, "from_b", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_writable_store_middle",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				mustNewInputStore(t, "a", map[string][]byte{"key": []byte("from_a")}),
+				mustNewInputStore(t, "b", map[string][]byte{"key": []byte("from_b")}),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("a")
+				require.True(t, found)
+				require.Equal(t, "from_a", string(value))
+				value, found = builder.GetLast("b")
+				require.True(t, found)
+				require.Equal(t, "from_b", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_sum_keys",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				mustNewInputStore(t, "totals", map[string][]byte{
+					"int64:a":  []byte("10"),
+					"int64:b":  []byte("-3"),
+					"bigint:a": []byte("12345678901234567890"),
+					"bigint:b": []byte("10"),
+				}),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("int64")
+				require.True(t, found)
+				require.Equal(t, "7", string(value))
+				value, found = builder.GetLast("bigint")
+				require.True(t, found)
+				require.Equal(t, "12345678901234567900", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_raw_input",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				{Type: wasm.InputSource, Name: "clock", StreamData: mustMarshal(t, &pbsubstreams.Clock{Number: 42})},
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("first_byte")
+				require.True(t, found)
+				require.Equal(t, []byte{0x10}, value)
+				value, found = builder.GetLast("number")
+				require.True(t, found)
+				require.Equal(t, "42", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "map_with_store",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				{Type: wasm.InputSource, Name: "clock", StreamData: mustMarshal(t, &pbsubstreams.Clock{Number: 42})},
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("last_block")
+				require.True(t, found)
+				require.Equal(t, "42", string(value))
+
+				clock := &pbsubstreams.Clock{}
+				require.NoError(t, proto.Unmarshal(instance.Output(), clock))
+				require.Equal(t, uint64(42), clock.Number)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "map_with_buffered_store",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				{Type: wasm.InputSource, Name: "clock", StreamData: mustMarshal(t, &pbsubstreams.Clock{Number: 42, Id: "abc"})},
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("last_block")
+				require.True(t, found)
+				require.Equal(t, "42", string(value))
+				value, found = builder.GetLast("last_id")
+				require.True(t, found)
+				require.Equal(t, "abc", string(value))
+
+				clock := &pbsubstreams.Clock{}
+				require.NoError(t, proto.Unmarshal(instance.Output(), clock))
+				require.Equal(t, uint64(42), clock.Number)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_clock_input",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				{Type: wasm.InputSource, Name: "clock", StreamData: mustMarshal(t, &pbsubstreams.Clock{Number: 42, Id: "abc"})},
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				require.Equal(t, []string{"block #42 (abc)"}, instance.Logs)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_module_name",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				{Type: wasm.InputSource, Name: "clock", StreamData: mustMarshal(t, &pbsubstreams.Clock{Number: 42})},
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				require.Equal(t, []string{
+					"test_module_name at block #42",
+					`module=test_module_name current module is Some("test_module_name")`,
+				}, instance.Logs)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_timed",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				{Type: wasm.InputSource, Name: "clock", StreamData: mustMarshal(t, &pbsubstreams.Clock{Number: 42})},
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("number")
+				require.True(t, found)
+				require.Equal(t, "42", string(value))
+				require.Len(t, instance.Logs, 1)
+				require.True(t, strings.HasPrefix(instance.Logs[0], "test_timed took "), instance.Logs[0])
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_optional_input",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				{Type: wasm.InputSource, Name: "clock", StreamData: mustMarshal(t, &pbsubstreams.Clock{Number: 42})},
+				{Type: wasm.InputSource, Name: "previous", StreamData: mustMarshal(t, &pbsubstreams.Clock{})},
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("previous")
+				require.True(t, found)
+				require.Equal(t, "some:0", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_optional_input",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				{Type: wasm.InputSource, Name: "clock", StreamData: mustMarshal(t, &pbsubstreams.Clock{Number: 42})},
+				{Type: wasm.InputSource, Name: "previous", Absent: true},
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("previous")
+				require.True(t, found)
+				require.Equal(t, "none", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_delete",
+			builder: withKV(
+				mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+				map[string][]byte{"token:a": []byte("a"), "token:ab": []byte("ab")},
+			),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				_, found := builder.GetLast("token:a")
+				require.False(t, found)
+				value, found := builder.GetLast("token:ab")
+				require.True(t, found)
+				require.Equal(t, "ab", string(value))
+
+				require.Len(t, builder.Deltas, 1)
+				require.Equal(t, pbsubstreams.StoreDelta_DELETE, builder.Deltas[0].Operation)
+				require.Equal(t, uint64(3), builder.Deltas[0].Ordinal)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_modify_proto",
+			builder: withKV(
+				mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+				map[string][]byte{"clock": mustMarshal(t, &pbsubstreams.Clock{Number: 100})},
+			),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("clock")
+				require.True(t, found)
+				clock := &pbsubstreams.Clock{}
+				require.NoError(t, proto.Unmarshal(value, clock))
+				require.Equal(t, uint64(115), clock.Number)
+				require.Equal(t, "first", clock.Id)
+
+				require.Len(t, builder.Deltas, 2)
+				first := &pbsubstreams.Clock{}
+				require.NoError(t, proto.Unmarshal(builder.Deltas[0].NewValue, first))
+				require.Equal(t, uint64(110), first.Number)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_store_get_bigint",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				mustNewInputStore(t, "volumes", map[string][]byte{
+					"valid":     []byte("-123456789012345678901234567890"),
+					"empty":     {},
+					"malformed": []byte("12abc"),
+				}),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				for key, expected := range map[string]string{
+					"valid":     "-123456789012345678901234567890",
+					"empty":     "none",
+					"malformed": "none",
+					"absent":    "none",
+				} {
+					value, found := builder.GetLast(key)
+					require.True(t, found)
+					require.Equal(t, expected, string(value), key)
+				}
+				require.Equal(t, []string{"unexpected error: `value of key malformed is not a valid bigint`"}, instance.Logs)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_store_get_numbers",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				func() *wasm.Input {
+					input := mustNewInputStore(t, "counts", map[string][]byte{"empty": {}, "malformed": []byte("1.5")})
+					input.Store.Set(1, "total", "5")
+					input.Store.Set(2, "total", "-12")
+					return input
+				}(),
+				func() *wasm.Input {
+					input := mustNewInputStore(t, "prices", map[string][]byte{"empty": {}, "malformed": []byte("abc")})
+					input.Store.Set(1, "eth", "1650.25")
+					input.Store.Set(2, "eth", "1e3")
+					return input
+				}(),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				for key, expected := range map[string]string{
+					"count_at_1":      "Some(5)",
+					"count_at_2":      "Some(-12)",
+					"count_last":      "Some(-12)",
+					"count_empty":     "None",
+					"count_malformed": "None",
+					"count_absent":    "None",
+					"price_at_1":      "Some(1650.25)",
+					"price_at_2":      "Some(1000.0)",
+					"price_last":      "Some(1000.0)",
+					"price_empty":     "None",
+					"price_malformed": "None",
+					"price_absent":    "None",
+				} {
+					value, found := builder.GetLast(key)
+					require.True(t, found, key)
+					require.Equal(t, expected, string(value), key)
+				}
+				require.Equal(t, []string{
+					"unexpected error: `value of key malformed is not a valid int64`",
+					"unexpected error: `value of key malformed is not a valid float64`",
+				}, instance.Logs)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_store_get_proto",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				mustNewInputStore(t, "clocks", map[string][]byte{
+					"valid":     mustMarshal(t, &pbsubstreams.Clock{Id: "abc", Number: 42}),
+					"empty":     {},
+					"malformed": {0x0a, 0x05},
+				}),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				for key, expected := range map[string]string{
+					"valid":     "abc:42",
+					"empty":     "none",
+					"malformed": "none",
+					"absent":    "none",
+				} {
+					value, found := builder.GetLast(key)
+					require.True(t, found)
+					require.Equal(t, expected, string(value), key)
+				}
+				require.Len(t, instance.Logs, 1)
+				require.Contains(t, instance.Logs[0], "value of key malformed can't be decoded")
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_store_get_last_as",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				mustNewInputStore(t, "values", map[string][]byte{
+					"valid":     []byte("42"),
+					"empty":     {},
+					"malformed": []byte("1.5"),
+				}),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				for key, expected := range map[string]string{
+					"int64_valid":      "Some(42)",
+					"int64_empty":      "None",
+					"int64_malformed":  "None",
+					"int64_absent":     "None",
+					"bigint_valid":     "Some(42)",
+					"bigint_empty":     "None",
+					"bigint_malformed": "None",
+					"bigint_absent":    "None",
+				} {
+					value, found := builder.GetLast(key)
+					require.True(t, found, key)
+					require.Equal(t, expected, string(value), key)
+				}
+				require.Equal(t, []string{
+					"unexpected error: `value of key malformed is not a valid int64`",
+					"unexpected error: `value of key malformed is not a valid bigint`",
+				}, instance.Logs)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_has",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				func() *wasm.Input {
+					input := mustNewInputStore(t, "tokens", map[string][]byte{"token:b": []byte("b")})
+					input.Store.Set(1, "token:a", "a")
+					input.Store.Del(2, "token:a")
+					return input
+				}(),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				for key, expected := range map[string]string{
+					"set_at_1":     "true",
+					"deleted_at_2": "false",
+					"last":         "false",
+					"kept":         "true",
+				} {
+					value, found := builder.GetLast(key)
+					require.True(t, found)
+					require.Equal(t, expected, string(value), key)
+				}
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_count_prefix",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				mustNewInputStore(t, "tokens", map[string][]byte{
+					"token:a":  []byte("a"),
+					"token:b":  []byte("b"),
+					"token:c":  []byte("c"),
+					"pair:a:b": []byte("ab"),
+				}),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("tokens")
+				require.True(t, found)
+				require.Equal(t, "3", string(value))
+				value, found = builder.GetLast("absent")
+				require.True(t, found)
+				require.Equal(t, "0", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_sorted_set_write",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "int64", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("alice")
+				require.True(t, found)
+				require.Equal(t, "40", string(value))
+				value, found = builder.GetLast("bob")
+				require.True(t, found)
+				require.Equal(t, "20", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_sorted_set_read",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				mustNewInputStore(t, "scores", map[string][]byte{
+					"alice": []byte("10"),
+					"bob":   []byte("20"),
+					"carol": []byte("20"),
+					"dave":  []byte("5"),
+				}),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("range")
+				require.True(t, found)
+				require.Equal(t, "alice,bob,carol", string(value))
+				value, found = builder.GetLast("top")
+				require.True(t, found)
+				require.Equal(t, "bob,carol", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_chained_get",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				mustNewInputStore(t, "overrides", map[string][]byte{
+					"a": []byte("override"),
+				}),
+				mustNewInputStore(t, "defaults", map[string][]byte{
+					"a": []byte("default"),
+					"b": []byte("default"),
+				}),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("a")
+				require.True(t, found)
+				require.Equal(t, "override", string(value))
+				value, found = builder.GetLast("b")
+				require.True(t, found)
+				require.Equal(t, "default", string(value))
+				value, found = builder.GetLast("absent")
+				require.True(t, found)
+				require.Equal(t, "none", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_get_last_many",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				mustNewInputStore(t, "tokens", map[string][]byte{
+					"a": []byte("token:a"),
+					"b": []byte("token:b"),
+					"c": []byte("token:c"),
+				}),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("values")
+				require.True(t, found)
+				require.Equal(t, "token:c,token:a,none,token:b", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_typed_deltas",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				func() *wasm.Input {
+					input := mustNewInputStore(t, "transfers", nil)
+					input.Deltas = true
+					// erc721.Transfer messages with only `token_id`, field 3, set
+					input.Store.Set(1, "transfer:a", string([]byte{0x18, 0x07}))
+					input.Store.Set(2, "transfer:a", string([]byte{0x18, 0x08}))
+					input.Store.Del(3, "transfer:a")
+					return input
+				}(),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("transfers")
+				require.True(t, found)
+				require.Equal(t, "transfer:a:none->7,transfer:a:7->8,transfer:a:8->none", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_set_sum_int64",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "set_sum_int64", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("total")
+				require.True(t, found)
+				require.Equal(t, "99", string(value))
+
+				var values []string
+				for _, delta := range builder.Deltas {
+					values = append(values, string(delta.NewValue))
+				}
+				require.Equal(t, []string{"5", "8", "100", "99"}, values)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_set_sum_bigint",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "set_sum_bigint", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("total")
+				require.True(t, found)
+				require.Equal(t, "123456789012345678901234567900", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_scan_prefix",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				func() *wasm.Input {
+					input := mustNewInputStore(t, "balances", map[string][]byte{
+						"balance:c": []byte("3"),
+						"owner:a":   []byte("x"),
+					})
+					input.Store.Set(1, "balance:b", "2")
+					input.Store.Set(2, "balance:a", "1")
+					return input
+				}(),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				for key, expected := range map[string]string{
+					"at_1": "balance:b=2,balance:c=3",
+					"last": "balance:a=1,balance:b=2,balance:c=3",
+					"all":  "balance:a=1,balance:b=2,balance:c=3,owner:a=x",
+					"none": "",
+				} {
+					value, found := builder.GetLast(key)
+					require.True(t, found, key)
+					require.Equal(t, expected, string(value), key)
+				}
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_get_last_with_ord",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				func() *wasm.Input {
+					input := mustNewInputStore(t, "tokens", map[string][]byte{"token:b": []byte("b")})
+					input.Store.Set(3, "token:a", "a1")
+					input.Store.Set(7, "token:a", "a2")
+					return input
+				}(),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				for key, expected := range map[string]string{
+					"token:a": "7:a2",
+					"token:b": "0:b",
+					"absent":  "none",
+				} {
+					value, found := builder.GetLast(key)
+					require.True(t, found, key)
+					require.Equal(t, expected, string(value), key)
+				}
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_get_last_range",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				mustNewInputStore(t, "logs", map[string][]byte{"logs": []byte("a;bb;ccc;")}),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				for key, expected := range map[string]string{
+					"chunks":   "a;bb|;ccc|;",
+					"len":      "9",
+					"past_end": "None",
+					"empty":    "Some([])",
+					"absent":   "None",
+				} {
+					value, found := builder.GetLast(key)
+					require.True(t, found, key)
+					require.Equal(t, expected, string(value), key)
+				}
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_max_many_int64",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_MAX, "int64", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				require.Equal(t, map[string][]byte{"a": []byte("10"), "b": []byte("20"), "c": []byte("10")}, builder.KV)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_min_many_bigint",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_MIN, "bigint", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				require.Equal(t, map[string][]byte{"a": []byte("-10"), "b": []byte("-20"), "c": []byte("-10")}, builder.KV)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_join",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				mustNewInputStore(t, "pairs", map[string][]byte{
+					"a": []byte("pair:a"),
+					"b": []byte("pair:b"),
+				}),
+				mustNewInputStore(t, "tokens", map[string][]byte{
+					"b": []byte("token:b"),
+					"c": []byte("token:c"),
+				}),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				for key, expected := range map[string]string{
+					"a": "pair:a|none",
+					"b": "pair:b|token:b",
+					"c": "none|token:c",
+				} {
+					value, found := builder.GetLast(key)
+					require.True(t, found, key)
+					require.Equal(t, expected, string(value), key)
+				}
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_add_bigint_returning",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_ADD, "bigint", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("supply")
+				require.True(t, found)
+				require.Equal(t, "107", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_log_ordinals",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_APPEND, "string", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("items")
+				require.True(t, found)
+				require.Equal(t, "trx0:log0;trx1:call0;trx1:log0;trx1:log1;", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_set_if_not_exists_reporting",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET_IF_NOT_EXISTS, "some object", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("key")
+				require.True(t, found)
+				require.Equal(t, "first", string(value))
+				require.Len(t, builder.Deltas, 1)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_set_if_not_exists_reporting_previous_block",
+			builder: func() *state.Store {
+				builder := mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET_IF_NOT_EXISTS, "some object", nil)
+				builder.KV["seen"] = []byte("previous")
+				return builder
+			}(),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				require.Equal(t, map[string][]byte{"seen": []byte("previous"), "new": []byte("first")}, builder.KV)
+				require.Len(t, builder.Deltas, 1)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_get_at_relative",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				func() *wasm.Input {
+					input := mustNewInputStore(t, "prices", nil)
+					input.Store.SetBytes(10, "price", []byte("1"))
+					input.Store.SetBytes(20, "price", []byte("2"))
+					input.Store.SetBytes(30, "price", []byte("3"))
+					return input
+				}(),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				for back, expected := range map[int]string{0: "3", 10: "2", 15: "1", 100: "none"} {
+					value, found := builder.GetLast(fmt.Sprintf("back:%d", back))
+					require.True(t, found)
+					require.Equal(t, expected, string(value))
+				}
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_map_last",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				mustNewInputStore(t, "counts", map[string][]byte{"count": []byte("41")}),
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("next")
+				require.True(t, found)
+				require.Equal(t, "42", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_contract_creation",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				for key, expected := range map[string]string{
+					"creation:to":              "None",
+					"creation:is_creation":     "true",
+					"transactions_to:contract": "02",
+					"transactions_to:empty":    "",
+				} {
+					value, found := builder.GetLast(key)
+					require.True(t, found, key)
+					require.Equal(t, expected, string(value), key)
+				}
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_indexed_logs",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				value, found := builder.GetLast("indexed_logs")
+				require.True(t, found)
+				require.Equal(t, "aa:0:0:0,aa:0:1:1,dd:2:2:0,cc:3:3:0", string(value))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_log_topics",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				for key, expected := range map[string]string{
+					"topics:0": "none:none,none:none,none:none,none:none,none:none",
+					"topics:1": "32:fc378daa952ba7f163c4a11628f55a4df523b3ef,none:none,none:none,none:none,none:none",
+					"topics:4": "32:fc378daa952ba7f163c4a11628f55a4df523b3ef,32:bc4ca0eda7647a8ab7c2061c2e118a18a936f13d,20:none,32:bc4ca0eda7647a8ab7c2061c2e118a18a936f13d,none:none",
+				} {
+					value, found := builder.GetLast(key)
+					require.True(t, found)
+					require.Equal(t, expected, string(value), key)
+				}
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "map_optional_output",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				{Type: wasm.InputSource, Name: "clock", StreamData: mustMarshal(t, &pbsubstreams.Clock{Number: 42})},
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				clock := &pbsubstreams.Clock{}
+				require.NoError(t, proto.Unmarshal(instance.Output(), clock))
+				require.Equal(t, uint64(42), clock.Number)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "map_optional_output",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				{Type: wasm.InputSource, Name: "clock", StreamData: mustMarshal(t, &pbsubstreams.Clock{Number: 43})},
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				require.Nil(t, instance.Output())
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_output_debug",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				require.Equal(t, []byte("final"), instance.Output())
+				require.Equal(t, [][]byte{[]byte("intermediate")}, instance.DebugOutputs())
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "test_output_json",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				require.Equal(t, []byte("final"), instance.Output())
+				require.Len(t, instance.DebugOutputs(), 1)
+				require.JSONEq(t, `{"from":"bc4ca0eda7647a8ab7c2061c2e118a18a936f13d","token_id":"42"}`, string(instance.DebugOutputs()[0]))
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "map_test_entities",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				{Type: wasm.InputSource, Name: "clock", StreamData: mustMarshal(t, &pbsubstreams.Clock{Number: 42})},
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				deltas := &pbsubstreams.StoreDeltas{}
+				require.NoError(t, proto.Unmarshal(instance.Output(), deltas))
+				require.Len(t, deltas.Deltas, 2)
+				require.Equal(t, "block:42:1", deltas.Deltas[0].Key)
+				require.Equal(t, "block:42:2", deltas.Deltas[1].Key)
+			},
+		},
+		{
+			wasmFile:     "testing_substreams.wasm",
+			functionName: "store_test_entities",
+			builder:      mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil),
+			inputs: []*wasm.Input{
+				{Type: wasm.InputSource, Name: "clock", StreamData: mustMarshal(t, &pbsubstreams.Clock{Number: 42})},
+			},
+			assert: func(t *testing.T, module *wasm.Module, instance *wasm.Instance, builder *state.Store) {
+				for _, ordinal := range []uint64{1, 2} {
+					value, found := builder.GetLast(fmt.Sprintf("block:42:%d", ordinal))
+					require.True(t, found)
+					delta := &pbsubstreams.StoreDelta{}
+					require.NoError(t, proto.Unmarshal(value, delta))
+					require.Equal(t, ordinal, delta.Ordinal)
+				}
+			},
+		},
 	}
 
 	for _, c := range cases {
@@ -266,7 +1132,7 @@ func TestRustScript(t *testing.T) {
 			module, err := runtime.NewModule(context.Background(), &pbsubstreams.Request{}, byteCode, "module.1", c.functionName)
 			require.NoError(t, err)
 
-			instance, err := module.NewInstance(&pbsubstreams.Clock{}, nil)
+			instance, err := module.NewInstance(&pbsubstreams.Clock{}, c.inputs)
 			require.NoError(t, err)
 			instance.SetOutputStore(c.builder)
 			err = instance.Execute()
@@ -276,6 +1142,296 @@ func TestRustScript(t *testing.T) {
 	}
 }
 
+func Test_PanicHookDisabled(t *testing.T) {
+	wasmFilePath := test_wasm_path(t, "testing_substreams.wasm")
+	file, err := os.Open(wasmFilePath)
+	require.NoError(t, err)
+	byteCode, err := ioutil.ReadAll(file)
+	require.NoError(t, err)
+
+	runtime := wasm.NewRuntime(nil)
+	module, err := runtime.NewModule(context.Background(), &pbsubstreams.Request{}, byteCode, "module.1", "test_panic_hook_disabled")
+	require.NoError(t, err)
+
+	instance, err := module.NewInstance(&pbsubstreams.Clock{}, nil)
+	require.NoError(t, err)
+	err = instance.Execute()
+	require.Error(t, err)
+	require.Nil(t, instance.Err(), "panic hook should not have been registered")
+}
+
+func Test_MapReturnsError(t *testing.T) {
+	wasmFilePath := test_wasm_path(t, "testing_substreams.wasm")
+	file, err := os.Open(wasmFilePath)
+	require.NoError(t, err)
+	byteCode, err := ioutil.ReadAll(file)
+	require.NoError(t, err)
+
+	runtime := wasm.NewRuntime(nil)
+	module, err := runtime.NewModule(context.Background(), &pbsubstreams.Request{}, byteCode, "module.1", "map_returns_error")
+	require.NoError(t, err)
+
+	instance, err := module.NewInstance(&pbsubstreams.Clock{}, []*wasm.Input{
+		{Type: wasm.InputSource, Name: "clock", StreamData: mustMarshal(t, &pbsubstreams.Clock{Number: 42})},
+	})
+	require.NoError(t, err)
+	err = instance.Execute()
+
+	var moduleErr *wasm.ModuleError
+	require.True(t, errors.As(err, &moduleErr), "expected a module error, got %v", err)
+	require.Equal(t, `module returned an error: Unexpected("block 42 is invalid")`, err.Error())
+	require.Nil(t, instance.Err(), "error should not go through the panic path")
+	require.Nil(t, instance.Output())
+}
+
+func Test_UnboundStoreIndex(t *testing.T) {
+	wasmFilePath := test_wasm_path(t, "testing_substreams.wasm")
+	file, err := os.Open(wasmFilePath)
+	require.NoError(t, err)
+	byteCode, err := ioutil.ReadAll(file)
+	require.NoError(t, err)
+
+	runtime := wasm.NewRuntime(nil)
+	module, err := runtime.NewModule(context.Background(), &pbsubstreams.Request{}, byteCode, "module.1", "test_unbound_store_index")
+	require.NoError(t, err)
+
+	instance, err := module.NewInstance(&pbsubstreams.Clock{}, nil)
+	require.NoError(t, err)
+	err = instance.Execute()
+
+	var panicErr *wasm.PanicError
+	require.True(t, errors.As(err, &panicErr), "expected a panic, got %v", err)
+	require.Contains(t, err.Error(), "store index 7 is not bound to this module")
+}
+
+func Test_StoreOperationPolicyMismatch(t *testing.T) {
+	wasmFilePath := test_wasm_path(t, "testing_substreams.wasm")
+	file, err := os.Open(wasmFilePath)
+	require.NoError(t, err)
+	byteCode, err := ioutil.ReadAll(file)
+	require.NoError(t, err)
+
+	cases := []struct {
+		functionName string
+		builder      *state.Store
+	}{
+		{"test_set_if_not_exists_reporting", mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil)},
+		{"test_set_sum_int64", mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "int64", nil)},
+		{"test_set_sum_bigint", mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_ADD, "set_sum_bigint", nil)},
+	}
+
+	for _, c := range cases {
+		t.Run(c.functionName, func(t *testing.T) {
+			runtime := wasm.NewRuntime(nil)
+			module, err := runtime.NewModule(context.Background(), &pbsubstreams.Request{}, byteCode, "module.1", c.functionName)
+			require.NoError(t, err)
+
+			instance, err := module.NewInstance(&pbsubstreams.Clock{}, nil)
+			require.NoError(t, err)
+			instance.SetOutputStore(c.builder)
+			err = instance.Execute()
+			require.Error(t, err)
+			require.Contains(t, err.Error(), "invalid store operation")
+			require.Empty(t, c.builder.KV)
+		})
+	}
+}
+
+func Test_WriterCreatedOnEachCall(t *testing.T) {
+	wasmFilePath := test_wasm_path(t, "testing_substreams.wasm")
+	file, err := os.Open(wasmFilePath)
+	require.NoError(t, err)
+	byteCode, err := ioutil.ReadAll(file)
+	require.NoError(t, err)
+
+	runtime := wasm.NewRuntime(nil)
+	module, err := runtime.NewModule(context.Background(), &pbsubstreams.Request{}, byteCode, "module.1", "test_writer_without_panic_hook")
+	require.NoError(t, err)
+
+	builder := mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "string", nil)
+	instance, err := module.NewInstance(&pbsubstreams.Clock{}, nil)
+	require.NoError(t, err)
+	instance.SetOutputStore(builder)
+	require.NoError(t, instance.Execute())
+	require.NoError(t, instance.Execute())
+
+	instance, err = module.NewInstance(&pbsubstreams.Clock{Number: 1}, nil)
+	require.NoError(t, err)
+	instance.SetOutputStore(builder)
+	require.NoError(t, instance.Execute())
+	require.Len(t, builder.Deltas, 3)
+}
+
+func Test_GetPreviousBlock(t *testing.T) {
+	wasmFilePath := test_wasm_path(t, "testing_substreams.wasm")
+	file, err := os.Open(wasmFilePath)
+	require.NoError(t, err)
+	byteCode, err := ioutil.ReadAll(file)
+	require.NoError(t, err)
+
+	runtime := wasm.NewRuntime(nil)
+	module, err := runtime.NewModule(context.Background(), &pbsubstreams.Request{}, byteCode, "module.1", "test_get_previous_block")
+	require.NoError(t, err)
+
+	builder := mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil)
+	execute := func(block uint64) {
+		clock := &pbsubstreams.Clock{Number: block}
+		instance, err := module.NewInstance(clock, []*wasm.Input{
+			{Type: wasm.InputSource, Name: "clock", StreamData: mustMarshal(t, clock)},
+		})
+		require.NoError(t, err)
+		instance.SetOutputStore(builder)
+		require.NoError(t, instance.Execute())
+	}
+
+	execute(1)
+	value, found := builder.GetLast("previous_balance:1")
+	require.True(t, found)
+	require.Equal(t, "none", string(value))
+	builder.Flush()
+
+	execute(2)
+	value, found = builder.GetLast("balance")
+	require.True(t, found)
+	require.Equal(t, "20", string(value))
+	value, found = builder.GetLast("previous_balance:2")
+	require.True(t, found)
+	require.Equal(t, "10", string(value))
+}
+
+func Test_GetAtBlock(t *testing.T) {
+	wasmFilePath := test_wasm_path(t, "testing_substreams.wasm")
+	file, err := os.Open(wasmFilePath)
+	require.NoError(t, err)
+	byteCode, err := ioutil.ReadAll(file)
+	require.NoError(t, err)
+
+	runtime := wasm.NewRuntime(nil)
+	module, err := runtime.NewModule(context.Background(), &pbsubstreams.Request{}, byteCode, "module.1", "test_get_at_block")
+	require.NoError(t, err)
+
+	prices := mustNewBuilder(t, "prices", 10, "modulehash.prices", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil)
+	prices.SetBytes(1, "price", []byte("1"))
+	prices.Flush()
+	writer, err := prices.WriteState(context.Background(), 100)
+	require.NoError(t, err)
+	require.NoError(t, writer.Write())
+	prices.SetBytes(1, "price", []byte("2"))
+	prices.Flush()
+	prices.SetBytes(1, "price", []byte("3"))
+
+	builder := mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil)
+	instance, err := module.NewInstance(&pbsubstreams.Clock{Number: 150}, []*wasm.Input{
+		{Type: wasm.InputStore, Name: "prices", Store: prices},
+	})
+	require.NoError(t, err)
+	instance.SetOutputStore(builder)
+	err = instance.Execute()
+	require.NoError(t, err)
+
+	for block, expected := range map[int]string{5: "none", 99: "1", 149: "2", 150: "3"} {
+		value, found := builder.GetLast(fmt.Sprintf("block:%d", block))
+		require.True(t, found)
+		require.Equal(t, expected, string(value), "block %d", block)
+	}
+}
+
+func Test_StartBlock(t *testing.T) {
+	wasmFilePath := test_wasm_path(t, "testing_substreams.wasm")
+	file, err := os.Open(wasmFilePath)
+	require.NoError(t, err)
+	byteCode, err := ioutil.ReadAll(file)
+	require.NoError(t, err)
+
+	runtime := wasm.NewRuntime(nil)
+	module, err := runtime.NewModule(context.Background(), &pbsubstreams.Request{}, byteCode, "module.1", "test_start_block")
+	require.NoError(t, err)
+	module.SetInitialBlock(12369621)
+
+	builder := mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil)
+	instance, err := module.NewInstance(&pbsubstreams.Clock{}, nil)
+	require.NoError(t, err)
+	instance.SetOutputStore(builder)
+	err = instance.Execute()
+	require.NoError(t, err)
+
+	value, found := builder.GetLast("start_block")
+	require.True(t, found)
+	require.Equal(t, "12369621", string(value))
+}
+
+func Test_Params(t *testing.T) {
+	wasmFilePath := test_wasm_path(t, "testing_substreams.wasm")
+	file, err := os.Open(wasmFilePath)
+	require.NoError(t, err)
+	byteCode, err := ioutil.ReadAll(file)
+	require.NoError(t, err)
+
+	runtime := wasm.NewRuntime(nil)
+	module, err := runtime.NewModule(context.Background(), &pbsubstreams.Request{}, byteCode, "module.1", "map_params")
+	require.NoError(t, err)
+
+	for _, params := range []string{"bc4ca0eda7647a8ab7c2061c2e118a18a936f13d", ""} {
+		module.SetParams([]byte(params))
+
+		instance, err := module.NewInstance(&pbsubstreams.Clock{}, nil)
+		require.NoError(t, err)
+		err = instance.Execute()
+		require.NoError(t, err)
+
+		output := &wrapperspb.StringValue{}
+		require.NoError(t, proto.Unmarshal(instance.Output(), output))
+		require.Equal(t, params, output.Value)
+	}
+}
+
+func Test_BatchMatchesSequential(t *testing.T) {
+	wasmFilePath := test_wasm_path(t, "testing_substreams.wasm")
+	file, err := os.Open(wasmFilePath)
+	require.NoError(t, err)
+	byteCode, err := ioutil.ReadAll(file)
+	require.NoError(t, err)
+
+	run := func(functionName string, updatePolicy pbsubstreams.Module_KindStore_UpdatePolicy, valueType string) *state.Store {
+		runtime := wasm.NewRuntime(nil)
+		module, err := runtime.NewModule(context.Background(), &pbsubstreams.Request{}, byteCode, "module.1", functionName)
+		require.NoError(t, err)
+
+		builder := mustNewBuilder(t, "builder.name.1", 0, "modulehash.1", updatePolicy, valueType, nil)
+		instance, err := module.NewInstance(&pbsubstreams.Clock{}, nil)
+		require.NoError(t, err)
+		instance.SetOutputStore(builder)
+		err = instance.Execute()
+		require.NoError(t, err)
+		return builder
+	}
+
+	cases := []struct {
+		name         string
+		updatePolicy pbsubstreams.Module_KindStore_UpdatePolicy
+		valueType    string
+		expected     map[string][]byte
+	}{
+		{"add_int64", pbsubstreams.Module_KindStore_UPDATE_POLICY_ADD, "int64", map[string][]byte{"a": []byte("-5"), "b": []byte("-3")}},
+		{"add_bigint", pbsubstreams.Module_KindStore_UPDATE_POLICY_ADD, "bigint", map[string][]byte{"a": []byte("-5"), "b": []byte("-3")}},
+		{"set", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", map[string][]byte{"a": []byte("-20"), "b": []byte("1")}},
+	}
+
+	for _, c := range cases {
+		t.Run(c.name, func(t *testing.T) {
+			batch := run(fmt.Sprintf("test_%s_batch", c.name), c.updatePolicy, c.valueType)
+			sequential := run(fmt.Sprintf("test_%s_sequential", c.name), c.updatePolicy, c.valueType)
+			buffered := run(fmt.Sprintf("test_%s_buffered", c.name), c.updatePolicy, c.valueType)
+
+			require.Equal(t, c.expected, batch.KV)
+			require.Equal(t, sequential.KV, batch.KV)
+			require.Equal(t, sequential.Deltas, batch.Deltas)
+			require.Equal(t, sequential.KV, buffered.KV)
+			require.Equal(t, sequential.Deltas, buffered.Deltas)
+		})
+	}
+}
+
 func Test_Recursion(t *testing.T) {
 	t.Skip()
 	wasmFilePath := test_wasm_path(t, "testing_substreams.wasm")
@@ -353,3 +1509,26 @@ func mustNewBuilder(t *testing.T, name string, moduleStartBlock uint64, moduleHa
 
 	return builder
 }
+
+func mustNewInputStore(t *testing.T, name string, kv map[string][]byte) *wasm.Input {
+	t.Helper()
+	store := mustNewBuilder(t, name, 0, "modulehash."+name, pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, "some object", nil)
+
+	return &wasm.Input{Type: wasm.InputStore, Name: name, Store: withKV(store, kv)}
+}
+
+func withKV(store *state.Store, kv map[string][]byte) *state.Store {
+	for key, value := range kv {
+		store.KV[key] = value
+	}
+
+	return store
+}
+
+func mustMarshal(t *testing.T, msg proto.Message) []byte {
+	t.Helper()
+	data, err := proto.Marshal(msg)
+	require.NoError(t, err)
+
+	return data
+}
@@ -0,0 +1,42 @@
+//! Implementations of the `substreams::eth` traits for the `sf.ethereum.type.v1` models.
+
+use crate::pb::eth;
+use substreams::eth::{Block, Log, Transaction};
+
+impl Log for eth::Log {
+    fn index(&self) -> u32 {
+        self.index
+    }
+
+    fn block_index(&self) -> u32 {
+        self.block_index
+    }
+}
+
+impl Transaction for eth::TransactionTrace {
+    type Log = eth::Log;
+
+    fn hash(&self) -> &[u8] {
+        &self.hash
+    }
+
+    fn index(&self) -> u32 {
+        self.index
+    }
+
+    fn to(&self) -> &[u8] {
+        &self.to
+    }
+
+    fn logs(&self) -> &[eth::Log] {
+        self.receipt.as_ref().map_or(&[], |receipt| &receipt.logs)
+    }
+}
+
+impl Block for eth::Block {
+    type Transaction = eth::TransactionTrace;
+
+    fn transactions(&self) -> &[eth::TransactionTrace] {
+        &self.transaction_traces
+    }
+}
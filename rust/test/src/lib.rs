@@ -1,14 +1,15 @@
+mod ethereum;
 mod pb;
 use bigdecimal::BigDecimal;
 use hex_literal::hex;
-use num_bigint::{BigInt, BigUint, TryFromBigIntError};
+use num_bigint::BigInt;
 use pb::{erc721, eth};
-use std::convert::TryInto;
 
 use substreams::{
     errors::Error,
+    eth::{Block, LogTopics, Transaction},
     log, store,
-    store::{StoreAddInt64, StoreMaxBigFloat},
+    store::{Ordinal, StoreAddInt64, StoreMaxBigFloat},
     Hex,
 };
 
@@ -28,21 +29,27 @@ fn map_transfers(blk: eth::Block) -> Result<erc721::Transfers, Error> {
                 return None;
             }
 
-            let token_id: Result<u64, TryFromBigIntError<BigUint>> =
-                BigUint::from_bytes_be(&log.topics[3]).try_into();
+            let (from, to, token_id) = match (
+                log.topics.topic_as_address(1),
+                log.topics.topic_as_address(2),
+                log.topics.topic(3),
+            ) {
+                (Some(from), Some(to), Some(token_id)) => (from, to, token_id),
+                _ => return None,
+            };
 
-            match token_id {
+            match substreams::bytes_to_u64_be(token_id) {
                 Ok(token_id) => Some(erc721::Transfer {
                     trx_hash: trx.hash.clone(),
-                    from: Vec::from(&log.topics[1][12..]),
-                    to: Vec::from(&log.topics[2][12..]),
+                    from: from.as_bytes().to_vec(),
+                    to: to.as_bytes().to_vec(),
                     token_id,
                     ordinal: log.block_index as u64,
                 }),
                 Err(e) => {
                     log::info!(
                         "The token_id value {} does not fit in a 64 bits unsigned integer: {}",
-                        Hex(&log.topics[3]),
+                        Hex(token_id),
                         e
                     );
 
@@ -61,8 +68,8 @@ fn store_nfts(
     tokens: store::StoreGet,
     output: store::StoreAddInt64,
 ) {
-    let tokens_first_opt = tokens.get_first(&"tokens".to_owned());
-    let pairs_last_opt = pairs.get_first(&"pairs".to_owned());
+    let tokens_first_opt = tokens.get_first("tokens");
+    let pairs_last_opt = pairs.get_first("pairs");
     log::info!("tokens {:?} pairs {:?}", tokens_first_opt, pairs_last_opt);
     for transfer in transfers.transfers {
         if hex::encode(&transfer.from) != "0000000000000000000000000000000000000000" {
@@ -76,11 +83,12 @@ fn store_nfts(
 }
 
 fn generate_key(holder: &[u8]) -> String {
-    return format!(
-        "total:{}:{}",
-        Hex::encode(holder),
-        Hex::encode(&TRACKED_CONTRACT)
-    );
+    let mut key = String::with_capacity(6 + 2 * holder.len() + 1 + 40);
+    key.push_str("total:");
+    Hex::encode_to(holder, &mut key);
+    key.push(':');
+    Hex::encode_to(&TRACKED_CONTRACT, &mut key);
+    key
 }
 
 const TRACKED_CONTRACT: [u8; 20] = hex!("bc4ca0eda7647a8ab7c2061c2e118a18a936f13d");
@@ -92,45 +100,46 @@ pub fn is_erc721transfer_event(log: &eth::Log) -> bool {
         return false;
     }
 
-    return log.topics[0] == TRANSFER_TOPIC;
+    return log.topics.topic(0) == Some(&TRANSFER_TOPIC[..]);
 }
 
-#[substreams::handlers::store]
+#[substreams::handlers::store(policy = "add", value_type = "bigint")]
 fn test_sum_big_int(output: store::StoreAddBigInt) {
     output.add(
         1,
-        "test.key.1".to_string(),
+        "test.key.1",
         &BigInt::parse_bytes(b"10", 10).unwrap(),
     );
     output.add(
         1,
-        "test.key.1".to_string(),
+        "test.key.1",
         &BigInt::parse_bytes(b"10", 10).unwrap(),
     );
 }
 
 #[substreams::handlers::store]
 fn test_sum_int64(o: StoreAddInt64) {
+    // `String` and `&str` keys address the same entry
     o.add(1, "sum.int.64".to_string(), 10);
-    o.add(1, "sum.int.64".to_string(), 10);
+    o.add(1, "sum.int.64", 10);
 }
 
 #[substreams::handlers::store]
 fn test_sum_float64(o: store::StoreAddFloat64) {
     o.add(1, "sum.float.64".to_string(), 10.75);
-    o.add(1, "sum.float.64".to_string(), 10.75);
+    o.add(1, "sum.float.64", 10.75);
 }
 
 #[substreams::handlers::store]
 fn test_sum_big_float_small_number(o: store::StoreAddBigFloat) {
     o.add(
         1,
-        "sum.big.float".to_string(),
+        "sum.big.float",
         &BigDecimal::parse_bytes(b"10.5", 10).unwrap(),
     );
     o.add(
         1,
-        "sum.big.float".to_string(),
+        "sum.big.float",
         &BigDecimal::parse_bytes(b"10.5", 10).unwrap(),
     );
 }
@@ -139,96 +148,748 @@ fn test_sum_big_float_small_number(o: store::StoreAddBigFloat) {
 fn test_sum_big_float_big_number(s: store::StoreAddBigFloat) {
     s.add(
         1,
-        "sum.big.float".to_string(),
+        "sum.big.float",
         &BigDecimal::parse_bytes(b"12345678987654321.5", 10).unwrap(),
     );
     s.add(
         1,
-        "sum.big.float".to_string(),
+        "sum.big.float",
         &BigDecimal::parse_bytes(b"12345678987654321.5", 10).unwrap(),
     );
 }
 
 #[substreams::handlers::store]
 fn test_set_min_int64(s: store::StoreMinInt64) {
-    s.min(1, "set_min_int64".to_string(), 5);
-    s.min(1, "set_min_int64".to_string(), 2);
+    s.min(1, "set_min_int64", 5);
+    s.min(1, "set_min_int64", 2);
 }
 
 #[substreams::handlers::store]
 fn test_set_min_bigint(s: store::StoreMinBigInt) {
     s.min(
         1,
-        "set_min_bigint".to_string(),
+        "set_min_bigint",
         &BigInt::parse_bytes(b"5", 10).unwrap(),
     );
     s.min(
         1,
-        "set_min_bigint".to_string(),
+        "set_min_bigint",
         &BigInt::parse_bytes(b"3", 10).unwrap(),
     );
 }
 
 #[substreams::handlers::store]
 fn test_set_min_float64(s: store::StoreMinFloat64) {
-    s.min(1, "set_min_float64".to_string(), 10.05);
-    s.min(1, "set_min_float64".to_string(), 10.04);
+    s.min(1, "set_min_float64", 10.05);
+    s.min(1, "set_min_float64", 10.04);
 }
 
 #[substreams::handlers::store]
 fn test_set_min_bigfloat(s: store::StoreMinBigFloat) {
     s.min(
         1,
-        "set_min_bigfloat".to_string(),
+        "set_min_bigfloat",
         &BigDecimal::parse_bytes(b"11.05", 10).unwrap(),
     );
     s.min(
         1,
-        "set_min_bigfloat".to_string(),
+        "set_min_bigfloat",
         &BigDecimal::parse_bytes(b"11.04", 10).unwrap(),
     );
 }
 
 #[substreams::handlers::store]
 fn test_set_max_int64(s: store::StoreMaxInt64) {
-    s.max(1, "set_max_int64".to_string(), 5);
-    s.max(1, "set_max_int64".to_string(), 2);
+    s.max(1, "set_max_int64", 5);
+    s.max(1, "set_max_int64", 2);
 }
 
 #[substreams::handlers::store]
 fn test_set_max_bigint(my_store: store::StoreMaxBigInt) {
     my_store.max(
         1,
-        "set_max_bigint".to_string(),
+        "set_max_bigint",
         &BigInt::parse_bytes(b"5", 10).unwrap(),
     );
     my_store.max(
         1,
-        "set_max_bigint".to_string(),
+        "set_max_bigint",
         &BigInt::parse_bytes(b"3", 10).unwrap(),
     );
 }
 
 #[substreams::handlers::store]
 fn test_set_max_float64(s: store::StoreMaxFloat64) {
-    s.max(1, "set_max_float64".to_string(), 10.05);
-    s.max(1, "set_max_float64".to_string(), 10.04);
+    s.max(1, "set_max_float64", 10.05);
+    s.max(1, "set_max_float64", 10.04);
 }
 
 #[substreams::handlers::store]
 fn test_set_max_bigfloat(s: StoreMaxBigFloat) {
     s.max(
         1,
-        "set_max_bigfloat".to_string(),
+        "set_max_bigfloat",
         &BigDecimal::parse_bytes(b"11.05", 10).unwrap(),
     );
     s.max(
         1,
-        "set_max_bigfloat".to_string(),
+        "set_max_bigfloat",
         &BigDecimal::parse_bytes(b"11.04", 10).unwrap(),
     );
 }
 
+#[substreams::handlers::store]
+fn test_writable_store_first(s: store::StoreSet, a: store::StoreGet, b: store::StoreGet) {
+    s.set(1, "a", &a.get_last("key").unwrap());
+    s.set(1, "b", &b.get_last("key").unwrap());
+}
+
+#[substreams::handlers::store]
+fn test_writable_store_middle(a: store::StoreGet, s: store::StoreSet, b: store::StoreGet) {
+    s.set(1, "a", &a.get_last("key").unwrap());
+    s.set(1, "b", &b.get_last("key").unwrap());
+}
+
+#[substreams::handlers::store]
+fn test_sum_keys(totals: store::StoreGet, s: store::StoreSet) {
+    let keys = vec!["int64:a", "int64:b", "absent"];
+    s.set(1, "int64", &totals.sum_int64(&keys).to_string().into_bytes());
+
+    let keys = vec!["bigint:a", "bigint:b", "absent"];
+    s.set(1, "bigint", &totals.sum_bigint(&keys).to_string().into_bytes());
+}
+
+#[substreams::handlers::store]
+fn test_clock_input(clock: substreams::pb::substreams::Clock) {
+    log::info!("block #{} ({})", clock.number, clock.id);
+}
+
+#[substreams::handlers::map]
+fn map_with_store(
+    clock: substreams::pb::substreams::Clock,
+    s: store::StoreSet,
+) -> Result<substreams::pb::substreams::Clock, Error> {
+    s.set(1, "last_block", &clock.number.to_string().into_bytes());
+    Ok(clock)
+}
+
+#[substreams::handlers::map]
+fn map_with_buffered_store(
+    clock: substreams::pb::substreams::Clock,
+    s: store::StoreSet,
+) -> Result<substreams::pb::substreams::Clock, Error> {
+    store::set_buffered(true);
+    s.set(1, "last_block", &clock.number.to_string().into_bytes());
+    s.set(2, "last_id", &clock.id.clone().into_bytes());
+    Ok(clock)
+}
+
+#[substreams::handlers::map]
+fn map_returns_error(
+    clock: substreams::pb::substreams::Clock,
+) -> Result<substreams::pb::substreams::Clock, Error> {
+    Err(Error::Unexpected(format!("block {} is invalid", clock.number)))
+}
+
+#[substreams::handlers::store(panic_hook = false)]
+fn test_panic_hook_disabled() {
+    panic!("panicking without the panic hook");
+}
+
+#[substreams::handlers::store]
+fn test_raw_input(clock: substreams::pb::substreams::Clock, clock_raw: &[u8], s: store::StoreSet) {
+    s.set(1, "first_byte", &clock_raw[..1].to_vec());
+    s.set(1, "number", &clock.number.to_string().into_bytes());
+}
+
+#[substreams::handlers::store]
+fn test_module_name(clock: substreams::pb::substreams::Clock) {
+    log::info!("{} at block #{}", SUBSTREAMS_MODULE_NAME, clock.number);
+    log::prefix_module(true);
+    log::info!("current module is {:?}", substreams::current_module());
+}
+
+#[substreams::handlers::store(timed)]
+fn test_timed(clock: substreams::pb::substreams::Clock, s: store::StoreSet) {
+    s.set(clock.number, "number", &clock.number.to_string().into_bytes());
+}
+
+#[substreams::handlers::store]
+fn test_optional_input(
+    clock: substreams::pb::substreams::Clock,
+    previous: Option<substreams::pb::substreams::Clock>,
+    s: store::StoreSet,
+) {
+    let previous = match previous {
+        Some(previous) => format!("some:{}", previous.number),
+        None => "none".to_string(),
+    };
+    s.set(clock.number, "previous", &previous.into_bytes());
+}
+
+#[substreams::handlers::store]
+fn test_delete(s: store::StoreDelete) {
+    s.delete(3, "token:a");
+    s.delete(4, "token:absent");
+}
+
+#[substreams::handlers::store]
+fn test_get_previous_block(clock: substreams::pb::substreams::Clock, s: store::StoreSet) {
+    s.set(1, "balance", &(clock.number * 10).to_string().into_bytes());
+
+    let previous = s
+        .get_previous_block("balance")
+        .unwrap_or_else(|| b"none".to_vec());
+    s.set(2, format!("previous_balance:{}", clock.number), &previous);
+}
+
+#[substreams::handlers::store]
+fn test_modify_proto(s: store::StoreSet) {
+    use substreams::pb::substreams::Clock;
+
+    s.modify_proto(1, "clock", |clock: &mut Clock| {
+        clock.number += 10;
+        clock.id = "first".to_string();
+    });
+    s.modify_proto(2, "clock", |clock: &mut Clock| clock.number += 5);
+}
+
+#[substreams::handlers::store]
+fn test_store_get_bigint(volumes: store::StoreGetBigInt, s: store::StoreSet) {
+    let describe = |value: Option<BigInt>| match value {
+        Some(value) => value.to_string(),
+        None => "none".to_string(),
+    };
+    for key in ["valid", "empty", "malformed", "absent"] {
+        s.set(1, key, &describe(volumes.get_last(key)).into_bytes());
+    }
+}
+
+#[substreams::handlers::store]
+fn test_store_get_numbers(
+    counts: store::StoreGetInt64,
+    prices: store::StoreGetFloat64,
+    s: store::StoreSet,
+) {
+    let keys = ["empty", "malformed", "absent"];
+
+    s.set(1, "count_at_1", &format!("{:?}", counts.get_at(1, "total")).into_bytes());
+    s.set(1, "count_at_2", &format!("{:?}", counts.get_at(2, "total")).into_bytes());
+    s.set(1, "count_last", &format!("{:?}", counts.get_last("total")).into_bytes());
+    for key in keys {
+        s.set(1, format!("count_{}", key), &format!("{:?}", counts.get_last(key)).into_bytes());
+    }
+
+    s.set(1, "price_at_1", &format!("{:?}", prices.get_at(1, "eth")).into_bytes());
+    s.set(1, "price_at_2", &format!("{:?}", prices.get_at(2, "eth")).into_bytes());
+    s.set(1, "price_last", &format!("{:?}", prices.get_last("eth")).into_bytes());
+    for key in keys {
+        s.set(1, format!("price_{}", key), &format!("{:?}", prices.get_last(key)).into_bytes());
+    }
+}
+
+#[substreams::handlers::store]
+fn test_store_get_proto(
+    clocks: store::StoreGetProto<substreams::pb::substreams::Clock>,
+    s: store::StoreSet,
+) {
+    for key in ["valid", "empty", "malformed", "absent"] {
+        let describe = match clocks.get_last(key) {
+            Some(clock) => format!("{}:{}", clock.id, clock.number),
+            None => "none".to_string(),
+        };
+        s.set(1, key, &describe.into_bytes());
+    }
+}
+
+#[substreams::handlers::store]
+fn test_store_get_last_as(values: store::StoreGet, s: store::StoreSet) {
+    for key in ["valid", "empty", "malformed", "absent"] {
+        let int64 = values.get_last_as::<i64, _>(key);
+        s.set(1, format!("int64_{}", key), &format!("{:?}", int64).into_bytes());
+        let bigint = values.get_last_as::<BigInt, _>(key);
+        s.set(1, format!("bigint_{}", key), &format!("{:?}", bigint).into_bytes());
+    }
+}
+
+#[substreams::handlers::store]
+fn test_has(tokens: store::StoreGet, s: store::StoreSet) {
+    s.set(1, "set_at_1", &tokens.has_at(1, "token:a").to_string().into_bytes());
+    s.set(1, "deleted_at_2", &tokens.has_at(2, "token:a").to_string().into_bytes());
+    s.set(1, "last", &tokens.has_last("token:a").to_string().into_bytes());
+    s.set(1, "kept", &tokens.has_last("token:b").to_string().into_bytes());
+}
+
+#[substreams::handlers::store]
+fn test_count_prefix(tokens: store::StoreGet, s: store::StoreSet) {
+    s.set(1, "tokens", &tokens.count_prefix("token:").to_string().into_bytes());
+    s.set(1, "absent", &tokens.count_prefix("absent:").to_string().into_bytes());
+}
+
+#[substreams::handlers::store]
+fn test_sorted_set_write(s: store::StoreSortedSet) {
+    s.set(1, "alice", 10);
+    s.set(2, "bob", 20);
+    s.set(3, "alice", 40);
+}
+
+#[substreams::handlers::store]
+fn test_sorted_set_read(scores: store::StoreGetSortedSet, s: store::StoreSet) {
+    let members = |entries: Vec<(String, i64)>| {
+        entries
+            .into_iter()
+            .map(|(member, _)| member)
+            .collect::<Vec<String>>()
+            .join(",")
+    };
+
+    s.set(1, "range", &members(scores.range(10, 20).unwrap()).into_bytes());
+    s.set(1, "top", &members(scores.top_n(2).unwrap()).into_bytes());
+}
+
+#[substreams::handlers::store]
+fn test_chained_get(overrides: store::StoreGet, defaults: store::StoreGet, s: store::StoreSet) {
+    let chained = store::Chained::new(vec![overrides, defaults]);
+
+    s.set(1, "a", &chained.get_last("a").unwrap());
+    s.set(1, "b", &chained.get_last("b").unwrap());
+    if chained.get_last("absent").is_none() {
+        s.set(1, "absent", &b"none".to_vec());
+    }
+}
+
+#[substreams::handlers::store]
+fn test_get_last_many(tokens: store::StoreGet, s: store::StoreSet) {
+    let keys = vec!["c", "a", "absent", "b"];
+    let values = tokens.get_last_many(&keys);
+    let joined: Vec<String> = values
+        .into_iter()
+        .map(|value| value.map_or("none".to_string(), |v| String::from_utf8(v).unwrap()))
+        .collect();
+    s.set(1, "values", &joined.join(",").into_bytes());
+}
+
+#[substreams::handlers::store]
+fn test_typed_deltas(transfers: store::Deltas<erc721::Transfer>, s: store::StoreSet) {
+    let token_id = |transfer: &Option<erc721::Transfer>| match transfer {
+        Some(transfer) => transfer.token_id.to_string(),
+        None => "none".to_string(),
+    };
+    let described: Vec<String> = transfers
+        .iter()
+        .map(|(key, delta)| {
+            format!("{}:{}->{}", key, token_id(&delta.old_value), token_id(&delta.new_value))
+        })
+        .collect();
+    s.set(1, "transfers", &described.join(",").into_bytes());
+}
+
+#[substreams::handlers::store]
+fn test_set_sum_int64(s: store::StoreSetSumInt64) {
+    s.sum(1, "total", 5);
+    s.sum(2, "total", 3);
+    s.set(3, "total", 100);
+    s.sum(4, "total", -1);
+}
+
+#[substreams::handlers::store]
+fn test_set_sum_bigint(s: store::StoreSetSumBigInt) {
+    s.set(1, "total", &BigInt::from(10));
+    s.sum(2, "total", &BigInt::parse_bytes(b"123456789012345678901234567890", 10).unwrap());
+}
+
+#[substreams::handlers::store]
+fn test_scan_prefix(balances: store::StoreGet, s: store::StoreSet) {
+    let describe = |entries: Vec<(String, Vec<u8>)>| {
+        entries
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, String::from_utf8(value).unwrap()))
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+
+    s.set(1, "at_1", &describe(balances.scan_prefix(1, "balance:")).into_bytes());
+    s.set(1, "last", &describe(balances.scan_prefix(u64::MAX, "balance:")).into_bytes());
+    s.set(1, "all", &describe(balances.scan_prefix(u64::MAX, "")).into_bytes());
+    s.set(1, "none", &describe(balances.scan_prefix(u64::MAX, "absent:")).into_bytes());
+}
+
+#[substreams::handlers::store]
+fn test_get_last_with_ord(tokens: store::StoreGet, s: store::StoreSet) {
+    for key in ["token:a", "token:b", "absent"] {
+        let value = match tokens.get_last_with_ord(key) {
+            Some((ord, value)) => format!("{}:{}", ord, String::from_utf8(value).unwrap()),
+            None => "none".to_string(),
+        };
+        s.set(1, key, &value.into_bytes());
+    }
+}
+
+#[substreams::handlers::store]
+fn test_get_last_range(logs: store::StoreGet, s: store::StoreSet) {
+    let len = logs.get_last_len("logs").unwrap();
+    let mut chunks = vec![];
+    let mut offset = 0;
+    while offset < len {
+        let chunk = logs.get_last_range("logs", offset, 4).unwrap();
+        offset += chunk.len() as u64;
+        chunks.push(String::from_utf8(chunk).unwrap());
+    }
+    s.set(1, "chunks", &chunks.join("|").into_bytes());
+    s.set(1, "len", &len.to_string().into_bytes());
+    s.set(1, "past_end", &format!("{:?}", logs.get_last_range("logs", len + 1, 4)).into_bytes());
+    s.set(1, "empty", &format!("{:?}", logs.get_last_range("logs", 2, 0)).into_bytes());
+    s.set(1, "absent", &format!("{:?}", logs.get_last_len("absent")).into_bytes());
+}
+
+#[substreams::handlers::store]
+fn test_max_many_int64(s: store::StoreMaxInt64) {
+    s.max(1, "b", 20);
+    s.max_many(2, &vec!["a", "b", "c"], 10);
+}
+
+#[substreams::handlers::store]
+fn test_min_many_bigint(s: store::StoreMinBigInt) {
+    s.min(1, "b", &BigInt::from(-20));
+    s.min_many(2, &vec!["a", "b", "c"], &BigInt::from(-10));
+}
+
+#[substreams::handlers::store]
+fn test_join(pairs: store::StoreGet, tokens: store::StoreGet, s: store::StoreSet) {
+    let keys = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    for (key, pair, token) in store::join(&pairs, &tokens, &keys) {
+        let value = |value: Option<Vec<u8>>| value.unwrap_or_else(|| b"none".to_vec());
+        let mut joined = value(pair);
+        joined.push(b'|');
+        joined.extend(value(token));
+        s.set(1, key, &joined);
+    }
+}
+
+#[substreams::handlers::map]
+fn map_optional_output(
+    clock: substreams::pb::substreams::Clock,
+) -> Option<substreams::pb::substreams::Clock> {
+    if clock.number % 2 == 1 {
+        return None;
+    }
+    Some(clock)
+}
+
+#[substreams::handlers::entities(entities = "deltas", key = "delta_key", ordinal = "ordinal")]
+fn test_entities(
+    clock: substreams::pb::substreams::Clock,
+) -> Result<substreams::pb::substreams::StoreDeltas, Error> {
+    let delta = |ordinal: u64| substreams::pb::substreams::StoreDelta {
+        ordinal,
+        key: format!("block:{}:{}", clock.number, ordinal),
+        ..Default::default()
+    };
+
+    Ok(substreams::pb::substreams::StoreDeltas {
+        deltas: vec![delta(1), delta(2)],
+    })
+}
+
+fn delta_key(delta: &substreams::pb::substreams::StoreDelta) -> String {
+    delta.key.clone()
+}
+
+#[substreams::handlers::store]
+fn test_add_bigint_returning(s: store::StoreAddBigInt) {
+    let totals: Vec<String> = vec![10, -3, 100]
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| {
+            s.add_returning(i as u64 + 1, "supply", &BigInt::from(value))
+                .to_string()
+        })
+        .collect();
+
+    assert_eq!(totals, vec!["10", "7", "107"]);
+}
+
+#[substreams::handlers::store]
+fn test_get_at_relative(prices: store::StoreGet, s: store::StoreSet) {
+    for back in [0, 10, 15, 100] {
+        let value = prices
+            .get_at_relative(back, "price")
+            .unwrap_or_else(|| b"none".to_vec());
+        s.set(1, format!("back:{}", back), &value);
+    }
+}
+
+#[substreams::handlers::store]
+fn test_get_at_block(prices: store::StoreGet, s: store::StoreSet) {
+    for block in [5, 99, 149, 150] {
+        let value = prices
+            .get_at_block(block, "price")
+            .unwrap_or_else(|| b"none".to_vec());
+        s.set(1, format!("block:{}", block), &value);
+    }
+}
+
+#[substreams::handlers::store]
+fn test_start_block(s: store::StoreSet) {
+    s.set(1, "start_block", &substreams::module::start_block().to_string().into_bytes());
+}
+
+#[substreams::handlers::map]
+fn map_params(params: String) -> Result<String, Error> {
+    Ok(params)
+}
+
+#[substreams::handlers::store]
+fn test_map_last(counts: store::StoreGet, s: store::StoreSet) {
+    let parse = |bytes: Vec<u8>| -> i64 { String::from_utf8(bytes).unwrap().parse().unwrap() };
+
+    let next = counts.map_last("count", parse).unwrap() + 1;
+    s.set(1, "next", &next.to_string().into_bytes());
+    assert_eq!(counts.map_last("absent", parse), None);
+}
+
+#[substreams::handlers::store]
+fn test_contract_creation(s: store::StoreSet) {
+    let contract = hex!("bc4ca0eda7647a8ab7c2061c2e118a18a936f13d").to_vec();
+    let block = eth::Block {
+        transaction_traces: vec![
+            eth::TransactionTrace {
+                hash: vec![0x01],
+                ..Default::default()
+            },
+            eth::TransactionTrace {
+                hash: vec![0x02],
+                to: contract.clone(),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    let creation = &block.transaction_traces[0];
+    s.set(1, "creation:to", &format!("{:?}", creation.to_address()).into_bytes());
+    s.set(1, "creation:is_creation", &creation.is_contract_creation().to_string().into_bytes());
+
+    let hashes = |address: &[u8]| -> String {
+        substreams::eth::transactions_to(&block, address)
+            .map(|trx| Hex(&trx.hash).to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    };
+    s.set(1, "transactions_to:contract", &hashes(&contract).into_bytes());
+    s.set(1, "transactions_to:empty", &hashes(&[]).into_bytes());
+}
+
+#[substreams::handlers::store]
+fn test_indexed_logs(s: store::StoreSet) {
+    let trx = |hash: u8, index: u32, logs: Vec<(u32, u32)>| eth::TransactionTrace {
+        hash: vec![hash],
+        index,
+        receipt: Some(eth::TransactionReceipt {
+            logs: logs
+                .into_iter()
+                .map(|(index, block_index)| eth::Log {
+                    index,
+                    block_index,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    let block = eth::Block {
+        transaction_traces: vec![
+            trx(0xaa, 0, vec![(0, 0), (1, 1)]),
+            eth::TransactionTrace {
+                hash: vec![0xbb],
+                index: 1,
+                ..Default::default()
+            },
+            trx(0xcc, 3, vec![(0, 3)]),
+            trx(0xdd, 2, vec![(0, 2)]),
+        ],
+        ..Default::default()
+    };
+
+    let logs: Vec<String> = block
+        .indexed_logs()
+        .iter()
+        .map(|log| {
+            format!(
+                "{}:{}:{}:{}",
+                Hex(log.trx_hash),
+                log.trx_index,
+                log.log_index,
+                log.log.index
+            )
+        })
+        .collect();
+    s.set(1, "indexed_logs", &logs.join(",").into_bytes());
+}
+
+#[substreams::handlers::store]
+fn test_log_topics(s: store::StoreSet) {
+    let address = hex!("bc4ca0eda7647a8ab7c2061c2e118a18a936f13d");
+    let mut padded = vec![0u8; 12];
+    padded.extend_from_slice(&address);
+    let logs = vec![
+        eth::Log::default(),
+        eth::Log {
+            topics: vec![TRANSFER_TOPIC.to_vec()],
+            ..Default::default()
+        },
+        eth::Log {
+            topics: vec![
+                TRANSFER_TOPIC.to_vec(),
+                padded.clone(),
+                address.to_vec(),
+                padded,
+            ],
+            ..Default::default()
+        },
+    ];
+
+    for log in &logs {
+        let topics: Vec<String> = (0..5)
+            .map(|i| {
+                let len = log.topics.topic(i).map(|topic| topic.len().to_string());
+                let address = log.topics.topic_as_address(i).map(|address| address.to_string());
+                format!(
+                    "{}:{}",
+                    len.unwrap_or_else(|| "none".to_string()),
+                    address.unwrap_or_else(|| "none".to_string())
+                )
+            })
+            .collect();
+        s.set(
+            1,
+            format!("topics:{}", log.topics.len()),
+            &topics.join(",").into_bytes(),
+        );
+    }
+}
+
+#[substreams::handlers::store]
+fn test_log_ordinals(s: store::StoreAppend) {
+    let log = |index: u32, block_index: u32| eth::Log {
+        index,
+        block_index,
+        ..Default::default()
+    };
+    let logs = vec![
+        (log(1, 2), "trx1:log1"),
+        (log(0, 0), "trx0:log0"),
+        (log(0, 1), "trx1:log0"),
+    ];
+
+    let trx = eth::TransactionTrace {
+        receipt: Some(eth::TransactionReceipt {
+            logs: vec![log(0, 1), log(1, 2)],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let mut items: Vec<(Ordinal, &str)> = logs
+        .iter()
+        .map(|(log, item)| (Ordinal::from_log(log), *item))
+        .collect();
+    items.push((Ordinal::from_call(&trx, 0).unwrap(), "trx1:call0"));
+    items.sort();
+
+    for (ordinal, item) in items {
+        s.append_delimited(ordinal, "items", item, ";");
+    }
+}
+
+#[substreams::handlers::store]
+fn test_set_if_not_exists_reporting(s: store::StoreSetIfNotExists) {
+    assert!(s.set_if_not_exists_reporting(1, "key", &b"first".to_vec()));
+    assert!(!s.set_if_not_exists_reporting(2, "key", &b"second".to_vec()));
+}
+
+#[substreams::handlers::store]
+fn test_set_if_not_exists_reporting_previous_block(s: store::StoreSetIfNotExists) {
+    assert!(!s.set_if_not_exists_reporting(1, "seen", &b"again".to_vec()));
+    assert!(s.set_if_not_exists_reporting(1, "new", &b"first".to_vec()));
+}
+
+fn batch_ops() -> Vec<(u64, &'static str, i64)> {
+    vec![(1, "a", 10), (2, "b", -4), (2, "a", 5), (3, "b", 1), (4, "a", -20)]
+}
+
+#[substreams::handlers::store]
+fn test_add_int64_batch(s: store::StoreAddInt64) {
+    s.add_batch(&batch_ops());
+}
+
+#[substreams::handlers::store]
+fn test_add_int64_sequential(s: store::StoreAddInt64) {
+    for (ord, key, value) in batch_ops() {
+        s.add(ord, key, value);
+    }
+}
+
+#[substreams::handlers::store]
+fn test_add_int64_buffered(s: store::StoreAddInt64) {
+    store::set_buffered(true);
+    for (ord, key, value) in batch_ops() {
+        s.add(ord, key, value);
+    }
+}
+
+#[substreams::handlers::store]
+fn test_add_bigint_batch(s: store::StoreAddBigInt) {
+    let ops: Vec<(u64, &str, BigInt)> = batch_ops()
+        .into_iter()
+        .map(|(ord, key, value)| (ord, key, BigInt::from(value)))
+        .collect();
+    s.add_batch(&ops);
+}
+
+#[substreams::handlers::store]
+fn test_add_bigint_sequential(s: store::StoreAddBigInt) {
+    for (ord, key, value) in batch_ops() {
+        s.add(ord, key, &BigInt::from(value));
+    }
+}
+
+#[substreams::handlers::store]
+fn test_add_bigint_buffered(s: store::StoreAddBigInt) {
+    store::set_buffered(true);
+    for (ord, key, value) in batch_ops() {
+        s.add(ord, key, &BigInt::from(value));
+    }
+}
+
+#[substreams::handlers::store]
+fn test_set_batch(s: store::StoreSet) {
+    let ops: Vec<(u64, &str, Vec<u8>)> = batch_ops()
+        .into_iter()
+        .map(|(ord, key, value)| (ord, key, value.to_string().into_bytes()))
+        .collect();
+    s.set_batch(&ops);
+}
+
+#[substreams::handlers::store]
+fn test_set_sequential(s: store::StoreSet) {
+    for (ord, key, value) in batch_ops() {
+        s.set(ord, key, &value.to_string().into_bytes());
+    }
+}
+
+#[substreams::handlers::store]
+fn test_set_buffered(s: store::StoreSet) {
+    store::set_buffered(true);
+    for (i, (ord, key, value)) in batch_ops().into_iter().enumerate() {
+        s.set(ord, key, &value.to_string().into_bytes());
+        if i == 1 {
+            store::flush();
+        }
+    }
+}
+
 // wasm extension tests
 #[link(wasm_import_module = "myext")]
 extern "C" {
@@ -266,9 +927,63 @@ fn test_wasm_extension_fail() {
 
 #[substreams::handlers::store]
 extern "C" fn test_set_delete_prefix(s: store::StoreSet) {
-    s.set(1, "1:key_to_keep".to_string(), &[1, 2, 3, 4].to_vec());
-    s.set(2, "2:key_to_delete".to_string(), &[5, 6, 7, 8].to_vec());
-    s.delete_prefix(3, &"2:".to_string());
+    s.set(1, "1:key_to_keep", &[1, 2, 3, 4].to_vec());
+    s.set(2, "2:key_to_delete", &[5, 6, 7, 8].to_vec());
+    s.delete_prefix(3, "2:");
+}
+
+#[substreams::handlers::store]
+fn test_add_delete_prefix(s: store::StoreAddInt64) {
+    s.add(1, "1:total", 5);
+    s.add(1, "2:total", 7);
+    s.add(2, "2:total", 3);
+    s.delete_prefix(3, "2:");
+}
+
+#[substreams::handlers::store]
+fn test_add_bigfloat_with_scale(s: store::StoreAddBigFloat) {
+    let s = s.with_scale(2);
+    s.add(1, "sum", &BigDecimal::parse_bytes(b"0.1", 10).unwrap());
+    s.add(1, "sum", &BigDecimal::parse_bytes(b"0.2", 10).unwrap());
+    s.add(1, "rounded", &BigDecimal::parse_bytes(b"1.2351", 10).unwrap());
+    s.add(1, "tie", &BigDecimal::parse_bytes(b"0.125", 10).unwrap());
+}
+
+#[substreams::handlers::store]
+extern "C" fn test_set_clear(s: store::StoreSet) {
+    s.set(1, "1:key", &[1, 2, 3, 4].to_vec());
+    s.set(2, "2:key", &[5, 6, 7, 8].to_vec());
+    s.set(2, "3:key", &[9].to_vec());
+    s.clear(3);
+}
+
+#[no_mangle]
+extern "C" fn test_unbound_store_index() {
+    substreams::register_panic_hook();
+    let s = store::StoreGet::new(7);
+    s.get_last("key");
+}
+
+#[no_mangle]
+extern "C" fn test_writer_without_panic_hook() {
+    let s = store::StoreSet::new();
+    s.set(1, "key", &b"value".to_vec());
+}
+
+#[no_mangle]
+extern "C" fn test_output_debug() {
+    substreams::output_raw(b"final".to_vec());
+    substreams::output_debug(b"intermediate".to_vec());
+}
+
+#[no_mangle]
+extern "C" fn test_output_json() {
+    let mut transfer = std::collections::BTreeMap::new();
+    transfer.insert("from", "bc4ca0eda7647a8ab7c2061c2e118a18a936f13d");
+    transfer.insert("token_id", "42");
+
+    substreams::output_raw(b"final".to_vec());
+    substreams::output_json(&transfer);
 }
 
 #[no_mangle]
@@ -282,20 +997,28 @@ extern "C" fn test_make_it_crash(data_ptr: *mut u8, data_len: usize) {
 
 #[substreams::handlers::store]
 extern "C" fn test_append_empty_string_on_same_key(s: store::StoreAppend) {
-    s.append(1, "key".to_string(), &"string1".to_string());
-    s.append(1, "key".to_string(), &"".to_string());
+    s.append(1, "key", "string1");
+    s.append(1, "key", "");
+}
+
+#[substreams::handlers::store]
+fn test_append_with_sep(s: store::StoreAppend) {
+    s.append_with_sep(1, "key", "string1", ",");
+    s.append_with_sep(1, "key", "", ",");
+    s.append_with_sep(2, "key", "string2", ",");
+    s.append_with_sep(1, "empty", "", ",");
 }
 
 #[substreams::handlers::store]
 extern "C" fn test_append_string_on_same_key(s: store::StoreAppend) {
-    s.append(1, "key".to_string(), &"string1".to_string());
-    s.append(1, "key".to_string(), &"string2".to_string());
+    s.append(1, "key", "string1");
+    s.append(1, "key", "string2");
 }
 
 #[substreams::handlers::store]
 extern "C" fn test_append_string_on_different_key(s: store::StoreAppend) {
-    s.append(1, "key".to_string(), &"string1".to_string());
-    s.append(1, "key1".to_string(), &"string2".to_string());
+    s.append(1, "key", "string1");
+    s.append(1, "key1", "string2");
 }
 
 #[substreams::handlers::store]
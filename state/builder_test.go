@@ -1,6 +1,8 @@
 package state
 
 import (
+	"context"
+	"fmt"
 	"testing"
 
 	"github.com/streamingfast/dstore"
@@ -8,6 +10,7 @@ import (
 	pbsubstreams "github.com/streamingfast/substreams/pb/sf/substreams/v1"
 
 	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
 )
 
 func TestApplyDelta(t *testing.T) {
@@ -249,6 +252,92 @@ func TestStateBuilder(t *testing.T) {
 	val, found = builder.GetLast("1")
 	assert.Equal(t, "val7", string(val))
 	assert.True(t, found)
+
+	val, ord, found := builder.GetLastWithOrdinal("1")
+	assert.Equal(t, "val7", string(val))
+	assert.Equal(t, uint64(5), ord)
+	assert.True(t, found)
+}
+
+func TestStateBuilder_GetLastWithOrdinal(t *testing.T) {
+	builder := mustNewBuilder(t, "b", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_UNSET, "", nil)
+
+	builder.Set(3, "previous", "val1")
+	builder.Flush()
+	builder.Set(2, "current", "val2")
+	builder.Set(4, "current", "val3")
+	builder.Set(1, "deleted", "val4")
+	builder.Del(6, "deleted")
+
+	val, ord, found := builder.GetLastWithOrdinal("previous")
+	assert.Equal(t, "val1", string(val))
+	assert.Equal(t, uint64(0), ord)
+	assert.True(t, found)
+
+	val, ord, found = builder.GetLastWithOrdinal("current")
+	assert.Equal(t, "val3", string(val))
+	assert.Equal(t, uint64(4), ord)
+	assert.True(t, found)
+
+	_, _, found = builder.GetLastWithOrdinal("deleted")
+	assert.False(t, found)
+
+	_, _, found = builder.GetLastWithOrdinal("absent")
+	assert.False(t, found)
+}
+
+func TestStateBuilder_GetAtBlock(t *testing.T) {
+	ctx := context.Background()
+	builder := mustNewBuilder(t, "b", 10, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_UNSET, "", nil)
+
+	for _, boundary := range []uint64{100, 200} {
+		builder.Set(1, "price", fmt.Sprintf("%d", boundary))
+		builder.Flush()
+		writer, err := builder.WriteState(ctx, boundary)
+		require.NoError(t, err)
+		require.NoError(t, writer.Write())
+	}
+	builder.Set(1, "price", "248")
+	builder.Flush()
+	builder.Set(1, "price", "250")
+
+	tests := []struct {
+		blockNum      uint64
+		expectedValue string
+		expectedFound bool
+	}{
+		{5, "", false},
+		{99, "100", true},
+		{199, "200", true},
+		{249, "248", true},
+		{250, "250", true},
+		{300, "250", true},
+	}
+	for _, test := range tests {
+		val, found, err := builder.GetAtBlock(ctx, 250, test.blockNum, "price")
+		require.NoError(t, err)
+		assert.Equal(t, test.expectedFound, found, "block %d", test.blockNum)
+		assert.Equal(t, test.expectedValue, string(val), "block %d", test.blockNum)
+	}
+
+	_, _, err := builder.GetAtBlock(ctx, 250, 150, "price")
+	assert.Error(t, err)
+
+	// the last snapshot read is kept in memory
+	require.NoError(t, builder.DeleteStore(ctx, 200).Delete())
+	val, found, err := builder.GetAtBlock(ctx, 250, 199, "price")
+	require.NoError(t, err)
+	assert.True(t, found)
+	assert.Equal(t, "200", string(val))
+
+	_, found, err = builder.GetAtBlock(ctx, 250, 199, "absent")
+	require.NoError(t, err)
+	assert.False(t, found)
+
+	// the previous ones are not
+	require.NoError(t, builder.DeleteStore(ctx, 100).Delete())
+	_, _, err = builder.GetAtBlock(ctx, 250, 99, "price")
+	assert.Error(t, err)
 }
 
 func mustNewBuilder(t *testing.T, name string, moduleStartBlock uint64, moduleHash string, updatePolicy pbsubstreams.Module_KindStore_UpdatePolicy, valueType string, store dstore.Store) *Store {
@@ -1,11 +1,17 @@
 package state
 
-import "math/big"
+import (
+	"context"
+	"math/big"
+)
 
 type Reader interface {
 	GetFirst(key string) ([]byte, bool)
 	GetLast(key string) ([]byte, bool)
+	GetLastWithOrdinal(key string) ([]byte, uint64, bool)
 	GetAt(ord uint64, key string) ([]byte, bool)
+	GetAtBlock(ctx context.Context, currentBlock, blockNum uint64, key string) ([]byte, bool, error)
+	KeysWithPrefix(prefix string) []string
 }
 
 type UpdateKeySetter interface {
@@ -14,8 +20,8 @@ type UpdateKeySetter interface {
 }
 
 type ConditionalKeySetter interface {
-	SetIfNotExists(ord uint64, key string, value string)
-	SetBytesIfNotExists(ord uint64, key string, value []byte)
+	SetIfNotExists(ord uint64, key string, value string) bool
+	SetBytesIfNotExists(ord uint64, key string, value []byte) bool
 }
 
 type Deleter interface {
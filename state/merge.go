@@ -17,6 +17,9 @@ const (
 	OutputValueTypeBigFloat = "bigfloat"
 	OutputValueTypeString   = "string"
 
+	OutputValueTypeSetSumInt64  = "set_sum_int64"
+	OutputValueTypeSetSumBigInt = "set_sum_bigint"
+
 	mergeDataKey = "__!__metadata" ///NEVER EVER CHANGE THIS
 )
 
@@ -55,6 +58,10 @@ func (s *Store) Merge(nextStore *Store) error {
 
 	switch s.UpdatePolicy {
 	case pbsubstreams.Module_KindStore_UPDATE_POLICY_SET:
+		if s.IsSetSum() {
+			s.mergeSetSum(nextStore)
+			break
+		}
 		for k, v := range nextStore.KV {
 			s.KV[k] = v
 		}
@@ -33,6 +33,10 @@ type Store struct {
 
 	lastOrdinal uint64
 	logger      *zap.Logger
+
+	snapshot *storeSnapshot // last full snapshot loaded by GetAtBlock
+
+	setSumKeys map[string]bool // keys of a partial set-sum store only summed since storeInitialBlock
 }
 
 func NewStore(name string, saveInterval uint64, moduleInitialBlock uint64, moduleHash string, updatePolicy pbsubstreams.Module_KindStore_UpdatePolicy, valueType string, store dstore.Store, logger *zap.Logger) (*Store, error) {
@@ -135,7 +139,12 @@ func (s *Store) load(ctx context.Context, stateFileName string) error {
 		if err = json.Unmarshal(data, &kv); err != nil {
 			return fmt.Errorf("unmarshal data: %w", err)
 		}
+		setSumKeys, err := takeSetSumKeys(kv)
+		if err != nil {
+			return err
+		}
 		s.KV = kv
+		s.setSumKeys = setSumKeys
 
 		s.logger.Debug("unmarshalling kv", zap.String("file_name", stateFileName), zap.Object("store", s))
 		return nil
@@ -156,7 +165,12 @@ func (s *Store) WriteState(ctx context.Context, endBoundaryBlock uint64) (*store
 
 	//kv := stringMap(s.KV) // FOR READABILITY ON DISK
 
-	content, err := json.MarshalIndent(s.KV, "", "  ")
+	kv, err := s.addSetSumKeys(s.KV)
+	if err != nil {
+		return nil, err
+	}
+
+	content, err := json.MarshalIndent(kv, "", "  ")
 	if err != nil {
 		return nil, fmt.Errorf("marshal kv state: %w", err)
 	}
@@ -271,6 +285,7 @@ func (s *Store) Flush() {
 func (s *Store) Roll(lastBlock uint64) {
 	s.storeInitialBlock = lastBlock
 	s.KV = map[string][]byte{}
+	s.setSumKeys = nil
 }
 
 // func (s *Store) SetNextLiveBoundary(requestedStartBlock uint64) {
@@ -0,0 +1,120 @@
+package state
+
+import (
+	"encoding/json"
+	"fmt"
+	"math/big"
+	"strings"
+)
+
+// setSumKeysKey holds, in the file of a partial set-sum store, the keys whose value is
+// only made of `sum` contributions since the start of the segment. Such a value is a
+// delta to apply on top of the value of the previous segment when merging partial
+// stores, while the value of a key `set` in the segment replaces it.
+const setSumKeysKey = "__!__set_sum_keys" ///NEVER EVER CHANGE THIS
+
+// IsSetSum returns whether the store is a set-sum store, a `set` store whose values are
+// totals that can be both summed and reset.
+func (s *Store) IsSetSum() bool {
+	valueType := strings.ToLower(s.ValueType)
+	return valueType == OutputValueTypeSetSumInt64 || valueType == OutputValueTypeSetSumBigInt
+}
+
+// SetSumBigInt sets the value of the key to `value` when `sum` is false, and adds `value`
+// to the value of the key as of `ord` otherwise, starting from 0 when the key is not set.
+func (s *Store) SetSumBigInt(ord uint64, key string, value *big.Int, sum bool) {
+	if !sum {
+		s.set(ord, key, []byte(value.String()))
+		delete(s.setSumKeys, key)
+		return
+	}
+
+	total := new(big.Int)
+	if val, found := s.GetAt(ord, key); found {
+		total = setSumTotal(key, val)
+	} else if s.IsPartial() {
+		// the key may have a value in the previous segments, only known when merging
+		s.markSetSumKey(key)
+	}
+	s.set(ord, key, []byte(total.Add(total, value).String()))
+}
+
+func (s *Store) SetSumInt64(ord uint64, key string, value int64, sum bool) {
+	s.SetSumBigInt(ord, key, big.NewInt(value), sum)
+}
+
+// mergeSetSum merges the keys of `nextStore`, the store of the next segment, into `s`: the
+// value of a key only summed in the next segment is added to its value in `s`, the value
+// of any other key replaces it.
+func (s *Store) mergeSetSum(nextStore *Store) {
+	for k, v := range nextStore.KV {
+		if !nextStore.setSumKeys[k] {
+			s.KV[k] = v
+			delete(s.setSumKeys, k)
+			continue
+		}
+
+		prev, found := s.KV[k]
+		if !found {
+			s.KV[k] = v
+			if s.IsPartial() {
+				s.markSetSumKey(k)
+			}
+			continue
+		}
+
+		total := setSumTotal(k, prev)
+		s.KV[k] = []byte(total.Add(total, setSumTotal(k, v)).String())
+	}
+}
+
+func (s *Store) markSetSumKey(key string) {
+	if s.setSumKeys == nil {
+		s.setSumKeys = map[string]bool{}
+	}
+	s.setSumKeys[key] = true
+}
+
+func setSumTotal(key string, value []byte) *big.Int {
+	total, ok := new(big.Int).SetString(string(value), 10)
+	if !ok {
+		panic(fmt.Sprintf("cannot load set-sum value %q of key %q", value, key))
+	}
+	return total
+}
+
+// addSetSumKeys adds the summed keys of a partial set-sum store to `kv`, the content of
+// its file, `kv` is returned unchanged for any other store.
+func (s *Store) addSetSumKeys(kv map[string][]byte) (map[string][]byte, error) {
+	if !s.IsSetSum() || !s.IsPartial() || len(s.setSumKeys) == 0 {
+		return kv, nil
+	}
+
+	keys, err := json.Marshal(s.setSumKeys)
+	if err != nil {
+		return nil, fmt.Errorf("marshal set-sum keys: %w", err)
+	}
+
+	withKeys := make(map[string][]byte, len(kv)+1)
+	for k, v := range kv {
+		withKeys[k] = v
+	}
+	withKeys[setSumKeysKey] = keys
+	return withKeys, nil
+}
+
+// takeSetSumKeys removes the summed keys of a partial set-sum store from `kv`, the
+// content of its file, and returns them.
+func takeSetSumKeys(kv map[string][]byte) (map[string]bool, error) {
+	keys, found := kv[setSumKeysKey]
+	if !found {
+		return nil, nil
+	}
+	delete(kv, setSumKeysKey)
+
+	setSumKeys := map[string]bool{}
+	if err := json.Unmarshal(keys, &setSumKeys); err != nil {
+		return nil, fmt.Errorf("unmarshal set-sum keys: %w", err)
+	}
+	return setSumKeys, nil
+}
@@ -0,0 +1,64 @@
+package state
+
+import (
+	"context"
+	"math/big"
+	"testing"
+
+	"github.com/streamingfast/substreams/block"
+	pbsubstreams "github.com/streamingfast/substreams/pb/sf/substreams/v1"
+	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
+)
+
+func TestStoreSetSum(t *testing.T) {
+	s := mustNewBuilder(t, "b", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, OutputValueTypeSetSumInt64, nil)
+
+	s.SetSumInt64(1, "total", 5, true)
+	s.SetSumInt64(2, "total", 3, true)
+	s.SetSumInt64(3, "total", 100, false)
+	s.SetSumInt64(4, "total", -1, true)
+	s.SetSumBigInt(5, "big", big.NewInt(10), false)
+	s.SetSumBigInt(6, "big", new(big.Int).Lsh(big.NewInt(1), 100), true)
+
+	val, found := s.GetLast("total")
+	assert.True(t, found)
+	assert.Equal(t, "99", string(val))
+
+	val, found = s.GetLast("big")
+	assert.True(t, found)
+	assert.Equal(t, "1267650600228229401496703205386", string(val))
+}
+
+func TestStoreSetSum_MergePartial(t *testing.T) {
+	ctx := context.Background()
+	s := mustNewBuilder(t, "b", 0, "modulehash.1", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET, OutputValueTypeSetSumInt64, nil)
+	s.SetSumInt64(1, "one", 10, false)
+	s.SetSumInt64(2, "two", 20, false)
+	s.SetSumInt64(3, "three", 30, true)
+
+	next := s.CloneStructure(100)
+	next.SetSumInt64(1, "one", 5, true)
+	next.SetSumInt64(2, "two", 2, false)
+	next.SetSumInt64(3, "three", -3, true)
+	next.SetSumInt64(4, "four", 4, false)
+	next.SetSumInt64(5, "four", 1, true)
+	next.SetSumInt64(6, "five", 7, true)
+
+	writer, err := next.WriteState(ctx, 200)
+	require.NoError(t, err)
+	require.NoError(t, writer.Write())
+
+	loaded, err := s.LoadFrom(ctx, &block.Range{StartBlock: 100, ExclusiveEndBlock: 200})
+	require.NoError(t, err)
+	assert.Equal(t, next.KV, loaded.KV)
+
+	require.NoError(t, s.Merge(loaded))
+	assert.Equal(t, map[string][]byte{
+		"one":   []byte("15"),
+		"two":   []byte("2"),
+		"three": []byte("27"),
+		"four":  []byte("5"),
+		"five":  []byte("7"),
+	}, s.KV)
+}
@@ -1,10 +1,15 @@
 package state
 
 import (
+	"fmt"
 	"math/big"
 	"strconv"
 )
 
+// MaxBigFloatScale is the largest number of decimals SumBigFloatWithScale rounds to, the
+// precision bigfloat values are stored with.
+const MaxBigFloatScale = 100
+
 func (s *Store) SumBigInt(ord uint64, key string, value *big.Int) {
 	sum := new(big.Int)
 	val, found := s.GetAt(ord, key)
@@ -54,6 +59,43 @@ func (s *Store) SumFloat64(ord uint64, key string, value float64) {
 }
 
 func (s *Store) SumBigFloat(ord uint64, key string, value *big.Float) {
+	s.set(ord, key, []byte(s.sumBigFloat(ord, key, value).Text('g', 100)))
+}
+
+// SumBigFloatWithScale adds value to the key like SumBigFloat, but stores the total
+// rounded to `scale` decimals, to the nearest with ties to even. A scale above
+// MaxBigFloatScale is rejected.
+func (s *Store) SumBigFloatWithScale(ord uint64, key string, value *big.Float, scale int) error {
+	if scale < 0 || scale > MaxBigFloatScale {
+		return fmt.Errorf("invalid bigfloat scale %d, must be between 0 and %d", scale, MaxBigFloatScale)
+	}
+
+	s.set(ord, key, []byte(roundDecimal(s.sumBigFloat(ord, key, value), scale)))
+	return nil
+}
+
+// roundDecimal formats value with `scale` decimals, rounded to the nearest with ties to
+// even. The rounding is done on the shortest decimal representation of value, not on its
+// binary one, so a total like 2.675, which has no exact binary representation, is a tie
+// rounded to 2.68.
+func roundDecimal(value *big.Float, scale int) string {
+	exact, ok := new(big.Rat).SetString(value.Text('g', -1))
+	if !ok {
+		panic(fmt.Sprintf("cannot round bigfloat %s", value.Text('g', -1)))
+	}
+
+	unit := new(big.Int).Exp(big.NewInt(10), big.NewInt(int64(scale)), nil)
+	scaled := exact.Mul(exact, new(big.Rat).SetInt(unit))
+	quo, rem := new(big.Int).QuoRem(scaled.Num(), scaled.Denom(), new(big.Int))
+
+	half := new(big.Int).Mul(rem.Abs(rem), big.NewInt(2)).Cmp(scaled.Denom())
+	if half > 0 || (half == 0 && quo.Bit(0) == 1) {
+		quo.Add(quo, big.NewInt(int64(scaled.Sign())))
+	}
+	return new(big.Rat).SetFrac(quo, unit).FloatString(scale)
+}
+
+func (s *Store) sumBigFloat(ord uint64, key string, value *big.Float) *big.Float {
 	sum := new(big.Float)
 	val, found := s.GetAt(ord, key)
 	if !found {
@@ -66,5 +108,5 @@ func (s *Store) SumBigFloat(ord uint64, key string, value *big.Float) {
 			sum.Add(prev, value)
 		}
 	}
-	s.set(ord, key, []byte(sum.Text('g', 100)))
+	return sum
 }
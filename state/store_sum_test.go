@@ -8,6 +8,7 @@ import (
 	"github.com/streamingfast/dstore"
 	pbsubstreams "github.com/streamingfast/substreams/pb/sf/substreams/v1"
 	"github.com/stretchr/testify/assert"
+	"github.com/stretchr/testify/require"
 )
 
 func initTestStore(key string, value []byte) *Store {
@@ -146,6 +147,53 @@ func TestStoreSumFloat64(t *testing.T) {
 	}
 }
 
+func TestStoreSumBigFloatWithScale(t *testing.T) {
+	b := initTestStore("key", []byte("1"))
+
+	require.NoError(t, b.SumBigFloatWithScale(0, "key", big.NewFloat(0.25), 2))
+	actual, found := b.GetAt(0, "key")
+	assert.True(t, found)
+	assert.Equal(t, "1.25", string(actual))
+
+	require.NoError(t, b.SumBigFloatWithScale(0, "key", big.NewFloat(0.125), 2))
+	actual, _ = b.GetAt(0, "key")
+	assert.Equal(t, "1.38", string(actual))
+
+	require.NoError(t, b.SumBigFloatWithScale(0, "other", big.NewFloat(0.125), 2))
+	actual, _ = b.GetAt(0, "other")
+	assert.Equal(t, "0.12", string(actual))
+
+	require.NoError(t, b.SumBigFloatWithScale(0, "other", big.NewFloat(1), 0))
+	actual, _ = b.GetAt(0, "other")
+	assert.Equal(t, "1", string(actual))
+
+	assert.Error(t, b.SumBigFloatWithScale(0, "key", big.NewFloat(1), MaxBigFloatScale+1))
+	assert.Error(t, b.SumBigFloatWithScale(0, "key", big.NewFloat(1), -1))
+	actual, _ = b.GetAt(0, "key")
+	assert.Equal(t, "1.38", string(actual))
+}
+
+func TestRoundDecimal(t *testing.T) {
+	parse := func(value string) *big.Float {
+		parsed, _, err := big.ParseFloat(value, 10, 100, big.ToNearestEven)
+		require.NoError(t, err)
+		return parsed
+	}
+
+	for value, expected := range map[string]string{
+		"2.675":  "2.68",
+		"2.665":  "2.66",
+		"2.6651": "2.67",
+		"-2.675": "-2.68",
+		"-2.665": "-2.66",
+		"1.5":    "1.50",
+		"0.005":  "0.00",
+		"100":    "100.00",
+	} {
+		assert.Equal(t, expected, roundDecimal(parse(value), 2), value)
+	}
+}
+
 func TestStoreSumBigFloat(t *testing.T) {
 	tests := []struct {
 		name          string
@@ -1,7 +1,10 @@
 package state
 
 import (
+	"context"
 	"fmt"
+	"sort"
+	"strings"
 
 	pbsubstreams "github.com/streamingfast/substreams/pb/sf/substreams/v1"
 )
@@ -28,6 +31,23 @@ func (s *Store) GetLast(key string) ([]byte, bool) {
 	return val, found
 }
 
+// GetLastWithOrdinal returns the value of the key like GetLast, along with the ordinal of
+// the delta of the current block that wrote it, or 0 when the value comes from a previous
+// block.
+func (s *Store) GetLastWithOrdinal(key string) ([]byte, uint64, bool) {
+	val, found := s.GetLast(key)
+	if !found {
+		return nil, 0, false
+	}
+
+	for i := len(s.Deltas) - 1; i >= 0; i-- {
+		if s.Deltas[i].Key == key {
+			return val, s.Deltas[i].Ordinal, true
+		}
+	}
+	return val, 0, true
+}
+
 // GetAt returns the key for the state that includes the processing of `ord`.
 func (s *Store) GetAt(ord uint64, key string) (out []byte, found bool) {
 	out, found = s.GetLast(key)
@@ -53,3 +73,88 @@ func (s *Store) GetAt(ord uint64, key string) (out []byte, found bool) {
 	}
 	return
 }
+
+// GetAtBlock returns the value of the key as of the end of block `blockNum`, while
+// `currentBlock` is being processed. A block before the initial block of the module
+// has no value, the current block or a later one reads the last value and the previous
+// block reads the value at the start of the current block. Any other block is read from
+// the full snapshot of the store ending right after it, which only exists at save
+// interval boundaries, so only the blocks preceding a boundary can be resolved. Only the
+// last snapshot read is kept in memory, reading another block of the same save interval
+// costs nothing while reading blocks of alternating intervals loads a snapshot each time.
+func (s *Store) GetAtBlock(ctx context.Context, currentBlock, blockNum uint64, key string) ([]byte, bool, error) {
+	switch {
+	case blockNum < s.ModuleInitialBlock:
+		return nil, false, nil
+	case blockNum >= currentBlock:
+		val, found := s.GetLast(key)
+		return val, found, nil
+	case blockNum+1 == currentBlock:
+		val, found := s.GetFirst(key)
+		return val, found, nil
+	}
+
+	kv, err := s.snapshotAt(ctx, blockNum+1)
+	if err != nil {
+		return nil, false, fmt.Errorf("reading store %q at block %d: %w", s.Name, blockNum, err)
+	}
+	val, found := kv[key]
+	return val, found, nil
+}
+
+type storeSnapshot struct {
+	exclusiveEndBlock uint64
+	kv                map[string][]byte
+}
+
+func (s *Store) snapshotAt(ctx context.Context, exclusiveEndBlock uint64) (map[string][]byte, error) {
+	if s.snapshot != nil && s.snapshot.exclusiveEndBlock == exclusiveEndBlock {
+		return s.snapshot.kv, nil
+	}
+	if s.SaveInterval == 0 || exclusiveEndBlock%s.SaveInterval != 0 {
+		return nil, fmt.Errorf("no snapshot ends at block %d, only the previous block and the last block of a %d blocks save interval can be read", exclusiveEndBlock, s.SaveInterval)
+	}
+
+	snapshot := s.CloneStructure(s.ModuleInitialBlock)
+	if err := snapshot.Fetch(ctx, exclusiveEndBlock); err != nil {
+		return nil, fmt.Errorf("loading snapshot: %w", err)
+	}
+
+	s.snapshot = &storeSnapshot{exclusiveEndBlock: exclusiveEndBlock, kv: snapshot.KV}
+	return snapshot.KV, nil
+}
+
+// MaxOrdinal returns the highest ordinal of the deltas of the current block, 0 if
+// the store has no delta.
+func (s *Store) MaxOrdinal() uint64 {
+	var max uint64
+	for _, delta := range s.Deltas {
+		if delta.Ordinal > max {
+			max = delta.Ordinal
+		}
+	}
+	return max
+}
+
+// KeysWithPrefix returns, sorted lexicographically, all the keys starting with `prefix`
+// that are either in the store or touched by one of the deltas of the current block.
+func (s *Store) KeysWithPrefix(prefix string) []string {
+	seen := map[string]bool{}
+	for key := range s.KV {
+		if strings.HasPrefix(key, prefix) {
+			seen[key] = true
+		}
+	}
+	for _, delta := range s.Deltas {
+		if strings.HasPrefix(delta.Key, prefix) {
+			seen[delta.Key] = true
+		}
+	}
+
+	keys := make([]string, 0, len(seen))
+	for key := range seen {
+		keys = append(keys, key)
+	}
+	sort.Strings(keys)
+	return keys
+}
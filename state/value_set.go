@@ -7,12 +7,14 @@ import (
 	pbsubstreams "github.com/streamingfast/substreams/pb/sf/substreams/v1"
 )
 
-func (s *Store) SetBytesIfNotExists(ord uint64, key string, value []byte) {
-	s.setIfNotExists(ord, key, value)
+// SetBytesIfNotExists sets the key only if it doesn't exist yet, and returns whether the
+// key was created by this call.
+func (s *Store) SetBytesIfNotExists(ord uint64, key string, value []byte) bool {
+	return s.setIfNotExists(ord, key, value)
 }
 
-func (s *Store) SetIfNotExists(ord uint64, key string, value string) {
-	s.setIfNotExists(ord, key, []byte(value))
+func (s *Store) SetIfNotExists(ord uint64, key string, value string) bool {
+	return s.setIfNotExists(ord, key, []byte(value))
 }
 
 func (s *Store) SetBytes(ord uint64, key string, value []byte) {
@@ -66,12 +68,12 @@ func (s *Store) set(ord uint64, key string, value []byte) {
 	s.Deltas = append(s.Deltas, delta)
 }
 
-func (s *Store) setIfNotExists(ord uint64, key string, value []byte) {
+func (s *Store) setIfNotExists(ord uint64, key string, value []byte) bool {
 	s.bumpOrdinal(ord)
 
 	_, found := s.GetLast(key)
 	if found {
-		return
+		return false
 	}
 
 	delta := &pbsubstreams.StoreDelta{
@@ -83,4 +85,5 @@ func (s *Store) setIfNotExists(ord uint64, key string, value []byte) {
 	}
 	s.ApplyDelta(delta)
 	s.Deltas = append(s.Deltas, delta)
+	return true
 }
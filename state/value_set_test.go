@@ -0,0 +1,19 @@
+package state
+
+import (
+	"testing"
+
+	pbsubstreams "github.com/streamingfast/substreams/pb/sf/substreams/v1"
+	"github.com/stretchr/testify/assert"
+)
+
+func TestValueSetIfNotExists(t *testing.T) {
+	s := mustNewStore(t, "b", 0, "hash", pbsubstreams.Module_KindStore_UPDATE_POLICY_SET_IF_NOT_EXISTS, "", nil)
+
+	assert.True(t, s.SetBytesIfNotExists(1, "key", []byte("first")))
+	assert.False(t, s.SetBytesIfNotExists(2, "key", []byte("second")))
+
+	res, found := s.GetLast("key")
+	assert.True(t, found)
+	assert.Equal(t, []byte("first"), res)
+}
@@ -19,6 +19,9 @@ type Input struct {
 
 	// Transient data between calls
 	StreamData []byte
+	// Absent is set when the input has no data at all for the current call, as opposed to
+	// an empty message which is present with a zero length StreamData.
+	Absent bool
 
 	// InputType == InputStore || OutputStore
 	Store  *state.Store
@@ -18,9 +18,12 @@ type Instance struct {
 
 	clock *pbsubstreams.Clock
 
-	args        []interface{} // to the `entrypoint` function
-	returnValue []byte
-	panicError  *PanicError
+	args          []interface{} // to the `entrypoint` function
+	presentInputs map[int]bool  // presence of the source inputs, keyed by index of their first argument
+	returnValue   []byte
+	debugOutputs  [][]byte
+	panicError    *PanicError
+	moduleError   *ModuleError
 
 	Logs           []string
 	LogsByteCount  uint64
@@ -30,22 +33,34 @@ type Instance struct {
 }
 
 func (i *Instance) Execute() (err error) {
+	if err = i.Module.callBeginHook(); err != nil {
+		return err
+	}
 	if _, err = i.entrypoint.Call(i.Module.wasmStore, i.args...); err != nil {
 		if i.panicError != nil {
 			return i.panicError
 		}
 		return fmt.Errorf("executing module %q: %w", i.Module.name, err)
 	}
+	if i.moduleError != nil {
+		return i.moduleError
+	}
 	return nil
 }
 
 func (i *Instance) ExecuteWithArgs(args ...interface{}) (err error) {
+	if err = i.Module.callBeginHook(); err != nil {
+		return err
+	}
 	if _, err = i.entrypoint.Call(i.Module.wasmStore, args...); err != nil {
 		if i.panicError != nil {
 			return i.panicError
 		}
 		return fmt.Errorf("executing module with args %q: %w", i.Module.name, err)
 	}
+	if i.moduleError != nil {
+		return i.moduleError
+	}
 	return nil
 }
 
@@ -74,8 +89,16 @@ func (i *Instance) Output() []byte {
 	return i.returnValue
 }
 
+// DebugOutputs returns the payloads emitted by the module with `output_debug`, in order.
+// They are for inspection during development only and never part of Output().
+func (i *Instance) DebugOutputs() [][]byte {
+	return i.debugOutputs
+}
+
 func (i *Instance) SetOutputStore(store *state.Store) {
 	i.outputStore = store
+	i.updatePolicy = store.UpdatePolicy
+	i.valueType = store.ValueType
 }
 
 const maxLogByteCount = 128 * 1024 // 128 KiB
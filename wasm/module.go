@@ -4,6 +4,7 @@ import (
 	"context"
 	"errors"
 	"fmt"
+	"time"
 
 	"github.com/bytecodealliance/wasmtime-go"
 	"github.com/dustin/go-humanize"
@@ -13,9 +14,12 @@ import (
 )
 
 type Module struct {
+	ctx     context.Context // used by the host functions loading store snapshots
 	runtime *Runtime
 
-	name string
+	name         string
+	initialBlock uint64
+	params       []byte
 
 	wasmCode        []byte
 	CurrentInstance *Instance
@@ -25,6 +29,7 @@ type Module struct {
 	wasmStore       *wasmtime.Store
 	wasmModule      *wasmtime.Module
 	wasmLinker      *wasmtime.Linker
+	beginCall       *wasmtime.Func // optional `substreams_begin_call` export, called before the entrypoint
 	Heap            *Heap
 }
 
@@ -38,6 +43,7 @@ func (r *Runtime) NewModule(ctx context.Context, request *pbsubstreams.Request,
 	}
 
 	m := &Module{
+		ctx:        ctx,
 		runtime:    r,
 		wasmEngine: engine,
 		wasmLinker: linker,
@@ -74,9 +80,38 @@ func (r *Runtime) NewModule(ctx context.Context, request *pbsubstreams.Request,
 	heap := NewHeap(memory, alloc, dealloc, m.wasmStore)
 	m.Heap = heap
 	m.wasmInstance = instance
+	if export := instance.GetExport(m.wasmStore, "substreams_begin_call"); export != nil {
+		m.beginCall = export.Func()
+	}
 	return m, nil
 }
 
+// callBeginHook lets the module reset the state it keeps from one call of its entrypoint
+// to the next, the wasm instance being reused for every block. Modules built with an
+// older SDK don't export the hook.
+func (m *Module) callBeginHook() error {
+	if m.beginCall == nil {
+		return nil
+	}
+	if _, err := m.beginCall.Call(m.wasmStore); err != nil {
+		return fmt.Errorf("calling substreams_begin_call of module %q: %w", m.name, err)
+	}
+	return nil
+}
+
+// SetInitialBlock sets the initial block of the module, as declared in the manifest,
+// returned to the module by the `start_block` import.
+func (m *Module) SetInitialBlock(initialBlock uint64) {
+	m.initialBlock = initialBlock
+}
+
+// SetParams sets the parameters of the module, returned to the module by the `params`
+// import, as UTF-8 text or an encoded protobuf message depending on the handler. The
+// module definitions have no parameters yet, so the pipeline never sets them.
+func (m *Module) SetParams(params []byte) {
+	m.params = params
+}
+
 func (m *Module) NewInstance(clock *pbsubstreams.Clock, inputs []*Input) (*Instance, error) {
 	entrypoint := m.wasmInstance.GetExport(m.wasmStore, m.entrypoint).Func()
 	if entrypoint == nil {
@@ -84,9 +119,10 @@ func (m *Module) NewInstance(clock *pbsubstreams.Clock, inputs []*Input) (*Insta
 	}
 
 	m.CurrentInstance = &Instance{
-		Module:     m,
-		clock:      clock,
-		entrypoint: entrypoint,
+		Module:        m,
+		clock:         clock,
+		entrypoint:    entrypoint,
+		presentInputs: map[int]bool{},
 	}
 
 	var args []interface{}
@@ -98,6 +134,7 @@ func (m *Module) NewInstance(clock *pbsubstreams.Clock, inputs []*Input) (*Insta
 				return nil, fmt.Errorf("writing %q to heap: %w", input.Name, err)
 			}
 			length := int32(len(input.StreamData))
+			m.CurrentInstance.presentInputs[len(args)] = !input.Absent
 			args = append(args, ptr, length)
 		case InputStore:
 			if input.Deltas {
@@ -178,6 +215,81 @@ func (m *Module) newImports() error {
 		return fmt.Errorf("registering panic import: %w", err)
 	}
 
+	if err = linker.FuncWrap("env", "set_error",
+		func(msgPtr, msgLength int32) {
+			message := m.Heap.ReadString(msgPtr, msgLength)
+			m.CurrentInstance.moduleError = &ModuleError{message}
+		},
+	); err != nil {
+		return fmt.Errorf("registering set_error import: %w", err)
+	}
+
+	if err = linker.FuncWrap("env", "output_debug",
+		func(ptr, length int32) {
+			message := m.Heap.ReadBytes(ptr, length)
+			debugOutput := make([]byte, length)
+			copy(debugOutput, message)
+			m.CurrentInstance.debugOutputs = append(m.CurrentInstance.debugOutputs, debugOutput)
+		},
+	); err != nil {
+		return fmt.Errorf("registering output_debug import: %w", err)
+	}
+
+	if err = linker.FuncWrap("env", "progress",
+		func(fraction float32, notePtr, noteLength int32) {
+			note := m.Heap.ReadString(notePtr, noteLength)
+			if tracer.Enabled() {
+				zlog.Debug("module progress", zap.String("module_name", m.CurrentInstance.Module.name), zap.Float32("fraction", fraction), zap.String("note", note))
+			}
+
+			m.CurrentInstance.PushExecutionStack(fmt.Sprintf("progress: %.2f %s", fraction, note))
+		},
+	); err != nil {
+		return fmt.Errorf("registering progress import: %w", err)
+	}
+
+	if err = linker.FuncWrap("env", "input_present",
+		func(argIndex int32) int32 {
+			present, found := m.CurrentInstance.presentInputs[int(argIndex)]
+			if !found {
+				returnErrorString("env", fmt.Sprintf("input_present: no source input at argument %d", argIndex))
+			}
+			if !present {
+				return 0
+			}
+			return 1
+		},
+	); err != nil {
+		return fmt.Errorf("registering input_present import: %w", err)
+	}
+
+	if err = linker.FuncWrap("env", "now_nanos",
+		func() int64 {
+			return time.Now().UnixNano()
+		},
+	); err != nil {
+		return fmt.Errorf("registering now_nanos import: %w", err)
+	}
+
+	if err = linker.FuncWrap("env", "start_block",
+		func() int64 {
+			return int64(m.initialBlock)
+		},
+	); err != nil {
+		return fmt.Errorf("registering start_block import: %w", err)
+	}
+
+	if err = linker.FuncWrap("env", "params",
+		func(outputPtr int32) {
+			err := m.CurrentInstance.WriteOutputToHeap(outputPtr, m.params, "params")
+			if err != nil {
+				returnError("env", fmt.Errorf("writing params to output ptr %d: %w", outputPtr, err))
+			}
+		},
+	); err != nil {
+		return fmt.Errorf("registering params import: %w", err)
+	}
+
 	if err = linker.FuncWrap("env", "output",
 		func(ptr, length int32) {
 			message := m.Heap.ReadBytes(ptr, length)
@@ -247,13 +359,23 @@ func returnError(moduleName string, cause error) {
 func (m *Module) registerStateImports(linker *wasmtime.Linker) error {
 	functions := map[string]interface{}{}
 	functions["set"] = m.set
+	functions["set_batch"] = m.setBatch
 	functions["set_if_not_exists"] = m.setIfNotExists
+	functions["set_if_not_exists_reporting"] = m.setIfNotExistsReporting
 	functions["append"] = m.append
+	functions["delete"] = m.delete
 	functions["delete_prefix"] = m.deletePrefix
+	functions["clear"] = m.clear
 	functions["add_bigint"] = m.addBigInt
+	functions["add_bigint_returning"] = m.addBigIntReturning
+	functions["add_bigint_batch"] = m.addBigIntBatch
 	functions["add_bigfloat"] = m.addBigFloat
+	functions["add_bigfloat_scaled"] = m.addBigFloatScaled
 	functions["add_int64"] = m.addInt64
+	functions["add_int64_batch"] = m.addInt64Batch
 	functions["add_float64"] = m.addFloat64
+	functions["set_sum_int64"] = m.setSumInt64
+	functions["set_sum_bigint"] = m.setSumBigInt
 	functions["set_min_int64"] = m.setMinInt64
 	functions["set_min_bigint"] = m.setMinBigint
 	functions["set_min_float64"] = m.setMinfloat64
@@ -262,9 +384,23 @@ func (m *Module) registerStateImports(linker *wasmtime.Linker) error {
 	functions["set_max_bigint"] = m.setMaxBigint
 	functions["set_max_float64"] = m.setMaxFloat64
 	functions["set_max_bigfloat"] = m.setMaxBigfloat
+	functions["store_bound"] = m.storeBound
 	functions["get_at"] = m.getAt
+	functions["get_at_relative"] = m.getAtRelative
+	functions["get_at_block"] = m.getAtBlock
 	functions["get_first"] = m.getFirst
 	functions["get_last"] = m.getLast
+	functions["get_last_many"] = m.getLastMany
+	functions["get_last_with_ord"] = m.getLastWithOrd
+	functions["get_last_len"] = m.getLastLen
+	functions["get_last_range"] = m.getLastRange
+	functions["get_previous_block"] = m.getPreviousBlock
+	functions["get_output_at"] = m.getOutputAt
+	functions["get_output_len"] = m.getOutputLen
+	functions["scan_prefix"] = m.scanPrefix
+	functions["count_prefix"] = m.countPrefix
+	functions["has_at"] = m.hasAt
+	functions["has_last"] = m.hasLast
 
 	for n, f := range functions {
 		if err := linker.FuncWrap("state", n, f); err != nil {
@@ -3,8 +3,11 @@ package wasm
 import (
 	"fmt"
 	"math/big"
+	"strconv"
 
 	pbsubstreams "github.com/streamingfast/substreams/pb/sf/substreams/v1"
+	"github.com/streamingfast/substreams/state"
+	"google.golang.org/protobuf/proto"
 )
 
 func returnStateErrorString(cause string) {
@@ -25,6 +28,18 @@ func (m *Module) set(ord int64, keyPtr, keyLength, valPtr, valLength int32) {
 	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.set  %q", m.name, key))
 }
 
+func (m *Module) setBatch(opsPtr, opsLength int32) {
+	if m.CurrentInstance.outputStore == nil && m.CurrentInstance.updatePolicy != pbsubstreams.Module_KindStore_UPDATE_POLICY_SET {
+		returnStateErrorString("invalid store operation: 'set_batch' only valid for stores with updatePolicy == 'replace'")
+	}
+	ops := m.readBatch("set_batch", opsPtr, opsLength)
+
+	for _, op := range ops {
+		m.CurrentInstance.outputStore.SetBytes(op.Ordinal, op.Key, op.NewValue)
+	}
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.setBatch  %d operations", m.name, len(ops)))
+}
+
 func (m *Module) setIfNotExists(ord int64, keyPtr, keyLength, valPtr, valLength int32) {
 	if m.CurrentInstance.outputStore == nil && m.CurrentInstance.updatePolicy != pbsubstreams.Module_KindStore_UPDATE_POLICY_SET_IF_NOT_EXISTS {
 		returnStateErrorString("invalid store operation: 'set_if_not_exists' only valid for stores with updatePolicy == 'ignore'")
@@ -36,6 +51,21 @@ func (m *Module) setIfNotExists(ord int64, keyPtr, keyLength, valPtr, valLength
 	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.setIfNotExists  %q", m.name, key))
 }
 
+func (m *Module) setIfNotExistsReporting(ord int64, keyPtr, keyLength, valPtr, valLength int32) int32 {
+	if m.CurrentInstance.outputStore == nil || m.CurrentInstance.updatePolicy != pbsubstreams.Module_KindStore_UPDATE_POLICY_SET_IF_NOT_EXISTS {
+		returnStateErrorString("invalid store operation: 'set_if_not_exists_reporting' only valid for stores with updatePolicy == 'ignore'")
+	}
+	key := m.Heap.ReadString(keyPtr, keyLength)
+	value := m.Heap.ReadBytes(valPtr, valLength)
+
+	created := m.CurrentInstance.outputStore.SetBytesIfNotExists(uint64(ord), key, value)
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.setIfNotExistsReporting  %q: created:%t", m.name, key, created))
+	if !created {
+		return 0
+	}
+	return 1
+}
+
 func (m *Module) append(ord int64, keyPtr, keyLength, valPtr, valLength int32) {
 	if m.CurrentInstance.outputStore == nil && m.CurrentInstance.updatePolicy != pbsubstreams.Module_KindStore_UPDATE_POLICY_APPEND {
 		returnStateErrorString("invalid store operation: 'append' only valid for stores with updatePolicy == 'append'")
@@ -48,12 +78,23 @@ func (m *Module) append(ord int64, keyPtr, keyLength, valPtr, valLength int32) {
 	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.append  %q", m.name, key))
 }
 
+func (m *Module) delete(ord int64, keyPtr, keyLength int32) {
+	key := m.Heap.ReadString(keyPtr, keyLength)
+	m.CurrentInstance.outputStore.Del(uint64(ord), key)
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.delete %q", m.name, key))
+}
+
 func (m *Module) deletePrefix(ord int64, keyPtr, keyLength int32) {
 	prefix := m.Heap.ReadString(keyPtr, keyLength)
 	m.CurrentInstance.outputStore.DeletePrefix(uint64(ord), prefix)
 	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.deletePrefix  %s ", m.name, prefix))
 }
 
+func (m *Module) clear(ord int64) {
+	m.CurrentInstance.outputStore.DeletePrefix(uint64(ord), "")
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.clear", m.name))
+}
+
 func (m *Module) addBigInt(ord int64, keyPtr, keyLength, valPtr, valLength int32) {
 	if m.CurrentInstance.outputStore == nil && m.CurrentInstance.updatePolicy != pbsubstreams.Module_KindStore_UPDATE_POLICY_ADD && m.CurrentInstance.valueType != "bigint" {
 		returnErrorString("state", "invalid store operation: 'add_bigint' only valid for stores with updatePolicy == 'add' and valueType == 'bigint'")
@@ -68,6 +109,40 @@ func (m *Module) addBigInt(ord int64, keyPtr, keyLength, valPtr, valLength int32
 	return
 }
 
+func (m *Module) addBigIntReturning(ord int64, keyPtr, keyLength, valPtr, valLength, outputPtr int32) {
+	if m.CurrentInstance.outputStore == nil && m.CurrentInstance.updatePolicy != pbsubstreams.Module_KindStore_UPDATE_POLICY_ADD && m.CurrentInstance.valueType != "bigint" {
+		returnErrorString("state", "invalid store operation: 'add_bigint_returning' only valid for stores with updatePolicy == 'add' and valueType == 'bigint'")
+	}
+	key := m.Heap.ReadString(keyPtr, keyLength)
+	value := m.Heap.ReadString(valPtr, valLength)
+
+	toAdd, _ := new(big.Int).SetString(value, 10)
+	m.CurrentInstance.outputStore.SumBigInt(uint64(ord), key, toAdd)
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.addBigIntReturning  %q", m.name, key))
+
+	total, _ := m.CurrentInstance.outputStore.GetLast(key)
+	err := m.CurrentInstance.WriteOutputToHeap(outputPtr, total, key)
+	if err != nil {
+		returnStateError(fmt.Errorf("writing total to output ptr %d: %w", outputPtr, err))
+	}
+}
+
+func (m *Module) addBigIntBatch(opsPtr, opsLength int32) {
+	if m.CurrentInstance.outputStore == nil && m.CurrentInstance.updatePolicy != pbsubstreams.Module_KindStore_UPDATE_POLICY_ADD && m.CurrentInstance.valueType != "bigint" {
+		returnStateErrorString("invalid store operation: 'add_bigint_batch' only valid for stores with updatePolicy == 'add' and valueType == 'bigint'")
+	}
+	ops := m.readBatch("add_bigint_batch", opsPtr, opsLength)
+
+	for _, op := range ops {
+		toAdd, ok := new(big.Int).SetString(string(op.NewValue), 10)
+		if !ok {
+			returnStateErrorString(fmt.Sprintf("'add_bigint_batch' failed: invalid bigint %q for key %q", string(op.NewValue), op.Key))
+		}
+		m.CurrentInstance.outputStore.SumBigInt(op.Ordinal, op.Key, toAdd)
+	}
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.addBigIntBatch  %d operations", m.name, len(ops)))
+}
+
 func (m *Module) addBigFloat(ord int64, keyPtr, keyLength, valPtr, valLength int32) {
 	if m.CurrentInstance.outputStore == nil && m.CurrentInstance.updatePolicy != pbsubstreams.Module_KindStore_UPDATE_POLICY_ADD && m.CurrentInstance.valueType != "bigfloat" {
 		returnErrorString("state", "invalid store operation: 'add_bigfloat' only valid for stores with updatePolicy == 'add' and valueType == 'bigfloat'")
@@ -85,6 +160,25 @@ func (m *Module) addBigFloat(ord int64, keyPtr, keyLength, valPtr, valLength int
 	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.addBigFloat  %q", m.name, key))
 }
 
+func (m *Module) addBigFloatScaled(ord int64, keyPtr, keyLength, valPtr, valLength int32, scale int32) {
+	if m.CurrentInstance.outputStore == nil && m.CurrentInstance.updatePolicy != pbsubstreams.Module_KindStore_UPDATE_POLICY_ADD && m.CurrentInstance.valueType != "bigfloat" {
+		returnErrorString("state", "invalid store operation: 'add_bigfloat_scaled' only valid for stores with updatePolicy == 'add' and valueType == 'bigfloat'")
+	}
+
+	key := m.Heap.ReadString(keyPtr, keyLength)
+	value := m.Heap.ReadString(valPtr, valLength)
+
+	toAdd, _, err := big.ParseFloat(value, 10, 100, big.ToNearestEven)
+	if err != nil {
+		returnStateError(fmt.Errorf("parsing bigfloat: %w", err))
+	}
+
+	if err := m.CurrentInstance.outputStore.SumBigFloatWithScale(uint64(ord), key, toAdd, int(uint32(scale))); err != nil {
+		returnStateError(err)
+	}
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.addBigFloatScaled  %q", m.name, key))
+}
+
 func (m *Module) addInt64(ord int64, keyPtr, keyLength int32, value int64) {
 	if m.CurrentInstance.outputStore == nil && m.CurrentInstance.updatePolicy != pbsubstreams.Module_KindStore_UPDATE_POLICY_ADD && m.CurrentInstance.valueType != "int64" {
 		returnStateErrorString("invalid store operation: 'add_int64' only valid for stores with updatePolicy == 'add' and valueType == 'int64'")
@@ -96,6 +190,47 @@ func (m *Module) addInt64(ord int64, keyPtr, keyLength int32, value int64) {
 
 }
 
+func (m *Module) addInt64Batch(opsPtr, opsLength int32) {
+	if m.CurrentInstance.outputStore == nil && m.CurrentInstance.updatePolicy != pbsubstreams.Module_KindStore_UPDATE_POLICY_ADD && m.CurrentInstance.valueType != "int64" {
+		returnStateErrorString("invalid store operation: 'add_int64_batch' only valid for stores with updatePolicy == 'add' and valueType == 'int64'")
+	}
+	ops := m.readBatch("add_int64_batch", opsPtr, opsLength)
+
+	for _, op := range ops {
+		value, err := strconv.ParseInt(string(op.NewValue), 10, 64)
+		if err != nil {
+			returnStateError(fmt.Errorf("'add_int64_batch' failed: invalid int64 for key %q: %w", op.Key, err))
+		}
+		m.CurrentInstance.outputStore.SumInt64(op.Ordinal, op.Key, value)
+	}
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.addInt64Batch  %d operations", m.name, len(ops)))
+}
+
+func (m *Module) setSumInt64(ord int64, keyPtr, keyLength int32, value int64, sum int32) {
+	if m.CurrentInstance.outputStore == nil || m.CurrentInstance.updatePolicy != pbsubstreams.Module_KindStore_UPDATE_POLICY_SET || m.CurrentInstance.valueType != state.OutputValueTypeSetSumInt64 {
+		returnStateErrorString("invalid store operation: 'set_sum_int64' only valid for stores with updatePolicy == 'set' and valueType == 'set_sum_int64'")
+	}
+	key := m.Heap.ReadString(keyPtr, keyLength)
+
+	m.CurrentInstance.outputStore.SetSumInt64(uint64(ord), key, value, sum == 1)
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.setSumInt64  %q sum:%t", m.name, key, sum == 1))
+}
+
+func (m *Module) setSumBigInt(ord int64, keyPtr, keyLength, valPtr, valLength int32, sum int32) {
+	if m.CurrentInstance.outputStore == nil || m.CurrentInstance.updatePolicy != pbsubstreams.Module_KindStore_UPDATE_POLICY_SET || m.CurrentInstance.valueType != state.OutputValueTypeSetSumBigInt {
+		returnStateErrorString("invalid store operation: 'set_sum_bigint' only valid for stores with updatePolicy == 'set' and valueType == 'set_sum_bigint'")
+	}
+	key := m.Heap.ReadString(keyPtr, keyLength)
+	value := m.Heap.ReadString(valPtr, valLength)
+
+	toSet, ok := new(big.Int).SetString(value, 10)
+	if !ok {
+		returnStateError(fmt.Errorf("parsing bigint: %q", value))
+	}
+	m.CurrentInstance.outputStore.SetSumBigInt(uint64(ord), key, toSet, sum == 1)
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.setSumBigInt  %q sum:%t", m.name, key, sum == 1))
+}
+
 func (m *Module) addFloat64(ord int64, keyPtr, keyLength int32, value float64) {
 	if m.CurrentInstance.outputStore == nil && m.CurrentInstance.updatePolicy != pbsubstreams.Module_KindStore_UPDATE_POLICY_ADD && m.CurrentInstance.valueType != "float64" {
 		returnStateErrorString("invalid store operation: 'add_float64' only valid for stores with updatePolicy == 'add' and valueType == 'float64'")
@@ -221,6 +356,61 @@ func (m *Module) getAt(storeIndex int32, ord int64, keyPtr, keyLength, outputPtr
 	return 1
 }
 
+func (m *Module) getAtRelative(storeIndex int32, back int64, keyPtr, keyLength, outputPtr int32) int32 {
+	if int(storeIndex+1) > len(m.CurrentInstance.inputStores) {
+		returnStateError(fmt.Errorf("'get_at_relative' failed: invalid store index %d, %d stores declared", storeIndex, len(m.CurrentInstance.inputStores)))
+	}
+	readStore := m.CurrentInstance.inputStores[storeIndex]
+	key := m.Heap.ReadString(keyPtr, keyLength)
+
+	var ord uint64
+	if maxOrdinal := readStore.MaxOrdinal(); uint64(back) < maxOrdinal {
+		ord = maxOrdinal - uint64(back)
+	}
+
+	value, found := readStore.GetAt(ord, key)
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.getAtRelative %q back:%d ord:%d: found:%t", m.name, key, back, ord, found))
+	if !found {
+		return 0
+	}
+
+	err := m.CurrentInstance.WriteOutputToHeap(outputPtr, value, key)
+	if err != nil {
+		returnStateError(fmt.Errorf("writing value to output ptr %d: %w", outputPtr, err))
+	}
+	return 1
+}
+
+func (m *Module) getAtBlock(storeIndex int32, blockNum int64, keyPtr, keyLength, outputPtr int32) int32 {
+	if int(storeIndex+1) > len(m.CurrentInstance.inputStores) {
+		returnStateError(fmt.Errorf("'get_at_block' failed: invalid store index %d, %d stores declared", storeIndex, len(m.CurrentInstance.inputStores)))
+	}
+	readStore := m.CurrentInstance.inputStores[storeIndex]
+	key := m.Heap.ReadString(keyPtr, keyLength)
+
+	value, found, err := readStore.GetAtBlock(m.ctx, m.CurrentInstance.clock.Number, uint64(blockNum), key)
+	if err != nil {
+		returnStateError(fmt.Errorf("'get_at_block' failed: %w", err))
+	}
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.getAtBlock %q block:%d: found:%t", m.name, key, blockNum, found))
+	if !found {
+		return 0
+	}
+
+	err = m.CurrentInstance.WriteOutputToHeap(outputPtr, value, key)
+	if err != nil {
+		returnStateError(fmt.Errorf("writing value to output ptr %d: %w", outputPtr, err))
+	}
+	return 1
+}
+
+func (m *Module) storeBound(storeIndex int32) int32 {
+	if storeIndex < 0 || int(storeIndex) >= len(m.CurrentInstance.inputStores) || m.CurrentInstance.inputStores[storeIndex] == nil {
+		return 0
+	}
+	return 1
+}
+
 func (m *Module) getFirst(storeIndex int32, keyPtr, keyLength, outputPtr int32) int32 {
 	if int(storeIndex)+1 > len(m.CurrentInstance.inputStores) {
 		returnStateError(fmt.Errorf("'get_first' failed: invalid store index %d, %d stores declared", storeIndex, len(m.CurrentInstance.inputStores)))
@@ -260,3 +450,244 @@ func (m *Module) getLast(storeIndex int32, keyPtr, keyLength, outputPtr int32) i
 	}
 	return 1
 }
+
+func (m *Module) getLastWithOrd(storeIndex int32, keyPtr, keyLength, outputPtr int32) int64 {
+	if int(storeIndex)+1 > len(m.CurrentInstance.inputStores) {
+		returnStateError(fmt.Errorf("'get_last_with_ord' failed: invalid store index %d, %d stores declared", storeIndex, len(m.CurrentInstance.inputStores)))
+	}
+
+	readStore := m.CurrentInstance.inputStores[storeIndex]
+
+	key := m.Heap.ReadString(keyPtr, keyLength)
+	value, ord, found := readStore.GetLastWithOrdinal(key)
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.getLastWithOrd %q: found:%t ord:%d", m.name, key, found, ord))
+	if !found {
+		return -1
+	}
+
+	err := m.CurrentInstance.WriteOutputToHeap(outputPtr, value, key)
+	if err != nil {
+		returnStateError(fmt.Errorf("writing value to output ptr %d: %w", outputPtr, err))
+	}
+	return int64(ord)
+}
+
+func (m *Module) getLastLen(storeIndex int32, keyPtr, keyLength int32) int64 {
+	if int(storeIndex)+1 > len(m.CurrentInstance.inputStores) {
+		returnStateError(fmt.Errorf("'get_last_len' failed: invalid store index %d, %d stores declared", storeIndex, len(m.CurrentInstance.inputStores)))
+	}
+
+	readStore := m.CurrentInstance.inputStores[storeIndex]
+
+	key := m.Heap.ReadString(keyPtr, keyLength)
+	value, found := readStore.GetLast(key)
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.getLastLen %q: found:%t", m.name, key, found))
+	if !found {
+		return -1
+	}
+	return int64(len(value))
+}
+
+func (m *Module) getLastRange(storeIndex int32, keyPtr, keyLength int32, offset, length int64, outputPtr int32) int32 {
+	if int(storeIndex)+1 > len(m.CurrentInstance.inputStores) {
+		returnStateError(fmt.Errorf("'get_last_range' failed: invalid store index %d, %d stores declared", storeIndex, len(m.CurrentInstance.inputStores)))
+	}
+
+	readStore := m.CurrentInstance.inputStores[storeIndex]
+
+	key := m.Heap.ReadString(keyPtr, keyLength)
+	value, found := readStore.GetLast(key)
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.getLastRange %q offset:%d length:%d: found:%t", m.name, key, uint64(offset), uint64(length), found))
+	if !found || uint64(offset) > uint64(len(value)) {
+		return 0
+	}
+
+	end := uint64(len(value))
+	if remaining := end - uint64(offset); uint64(length) < remaining {
+		end = uint64(offset) + uint64(length)
+	}
+
+	err := m.CurrentInstance.WriteOutputToHeap(outputPtr, value[offset:end], key)
+	if err != nil {
+		returnStateError(fmt.Errorf("writing value to output ptr %d: %w", outputPtr, err))
+	}
+	return 1
+}
+
+func (m *Module) getLastMany(storeIndex int32, keysPtr, keysLength, outputPtr int32) int32 {
+	if int(storeIndex)+1 > len(m.CurrentInstance.inputStores) {
+		returnStateError(fmt.Errorf("'get_last_many' failed: invalid store index %d, %d stores declared", storeIndex, len(m.CurrentInstance.inputStores)))
+	}
+
+	readStore := m.CurrentInstance.inputStores[storeIndex]
+
+	entries := &pbsubstreams.StoreDeltas{}
+	keys := m.readBatch("get_last_many", keysPtr, keysLength)
+	for _, key := range keys {
+		value, found := readStore.GetLast(key.Key)
+		if !found {
+			continue
+		}
+		entries.Deltas = append(entries.Deltas, &pbsubstreams.StoreDelta{Key: key.Key, NewValue: value})
+	}
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.getLastMany %d keys: found:%d", m.name, len(keys), len(entries.Deltas)))
+	if len(entries.Deltas) == 0 {
+		return 0
+	}
+
+	value, err := proto.Marshal(entries)
+	if err != nil {
+		returnStateError(fmt.Errorf("marshalling entries of %d keys: %w", len(keys), err))
+	}
+
+	err = m.CurrentInstance.WriteOutputToHeap(outputPtr, value, "get_last_many")
+	if err != nil {
+		returnStateError(fmt.Errorf("writing value to output ptr %d: %w", outputPtr, err))
+	}
+	return 1
+}
+
+func (m *Module) getPreviousBlock(keyPtr, keyLength, outputPtr int32) int32 {
+	if m.CurrentInstance.outputStore == nil {
+		returnStateErrorString("'get_previous_block' failed: module has no output store")
+	}
+
+	key := m.Heap.ReadString(keyPtr, keyLength)
+	value, found := m.CurrentInstance.outputStore.GetFirst(key)
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.getPreviousBlock %q: found:%t", m.name, key, found))
+	if !found {
+		return 0
+	}
+
+	err := m.CurrentInstance.WriteOutputToHeap(outputPtr, value, key)
+	if err != nil {
+		returnStateError(fmt.Errorf("writing value to output ptr %d: %w", outputPtr, err))
+	}
+	return 1
+}
+
+func (m *Module) getOutputAt(ord int64, keyPtr, keyLength, outputPtr int32) int32 {
+	if m.CurrentInstance.outputStore == nil {
+		returnStateErrorString("'get_output_at' failed: module has no output store")
+	}
+
+	key := m.Heap.ReadString(keyPtr, keyLength)
+	value, found := m.CurrentInstance.outputStore.GetAt(uint64(ord), key)
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.getOutputAt %q ord:%d: found:%t", m.name, key, ord, found))
+	if !found {
+		return 0
+	}
+
+	err := m.CurrentInstance.WriteOutputToHeap(outputPtr, value, key)
+	if err != nil {
+		returnStateError(fmt.Errorf("writing value to output ptr %d: %w", outputPtr, err))
+	}
+	return 1
+}
+
+func (m *Module) getOutputLen(ord int64, keyPtr, keyLength int32) int64 {
+	if m.CurrentInstance.outputStore == nil {
+		returnStateErrorString("'get_output_len' failed: module has no output store")
+	}
+
+	key := m.Heap.ReadString(keyPtr, keyLength)
+	value, found := m.CurrentInstance.outputStore.GetAt(uint64(ord), key)
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.getOutputLen %q ord:%d: found:%t", m.name, key, ord, found))
+	if !found {
+		return -1
+	}
+	return int64(len(value))
+}
+
+func (m *Module) countPrefix(storeIndex int32, prefixPtr, prefixLength int32) int64 {
+	if int(storeIndex)+1 > len(m.CurrentInstance.inputStores) {
+		returnStateError(fmt.Errorf("'count_prefix' failed: invalid store index %d, %d stores declared", storeIndex, len(m.CurrentInstance.inputStores)))
+	}
+
+	readStore := m.CurrentInstance.inputStores[storeIndex]
+
+	prefix := m.Heap.ReadString(prefixPtr, prefixLength)
+	var count int64
+	for _, key := range readStore.KeysWithPrefix(prefix) {
+		if _, found := readStore.GetLast(key); found {
+			count++
+		}
+	}
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.countPrefix %q: count:%d", m.name, prefix, count))
+	return count
+}
+
+func (m *Module) hasAt(storeIndex int32, ord int64, keyPtr, keyLength int32) int32 {
+	if int(storeIndex)+1 > len(m.CurrentInstance.inputStores) {
+		returnStateError(fmt.Errorf("'has_at' failed: invalid store index %d, %d stores declared", storeIndex, len(m.CurrentInstance.inputStores)))
+	}
+
+	readStore := m.CurrentInstance.inputStores[storeIndex]
+
+	key := m.Heap.ReadString(keyPtr, keyLength)
+	_, found := readStore.GetAt(uint64(ord), key)
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.hasAt %q ord:%d: found:%t", m.name, key, ord, found))
+	if !found {
+		return 0
+	}
+	return 1
+}
+
+func (m *Module) hasLast(storeIndex int32, keyPtr, keyLength int32) int32 {
+	if int(storeIndex)+1 > len(m.CurrentInstance.inputStores) {
+		returnStateError(fmt.Errorf("'has_last' failed: invalid store index %d, %d stores declared", storeIndex, len(m.CurrentInstance.inputStores)))
+	}
+
+	readStore := m.CurrentInstance.inputStores[storeIndex]
+
+	key := m.Heap.ReadString(keyPtr, keyLength)
+	_, found := readStore.GetLast(key)
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.hasLast %q: found:%t", m.name, key, found))
+	if !found {
+		return 0
+	}
+	return 1
+}
+
+func (m *Module) scanPrefix(storeIndex int32, ord int64, prefixPtr, prefixLength, outputPtr int32) int32 {
+	if int(storeIndex)+1 > len(m.CurrentInstance.inputStores) {
+		returnStateError(fmt.Errorf("'scan_prefix' failed: invalid store index %d, %d stores declared", storeIndex, len(m.CurrentInstance.inputStores)))
+	}
+
+	readStore := m.CurrentInstance.inputStores[storeIndex]
+
+	prefix := m.Heap.ReadString(prefixPtr, prefixLength)
+	entries := &pbsubstreams.StoreDeltas{}
+	for _, key := range readStore.KeysWithPrefix(prefix) {
+		value, found := readStore.GetAt(uint64(ord), key)
+		if !found {
+			continue
+		}
+		entries.Deltas = append(entries.Deltas, &pbsubstreams.StoreDelta{Key: key, NewValue: value})
+	}
+	m.CurrentInstance.PushExecutionStack(fmt.Sprintf("%s.scanPrefix %q: found:%d", m.name, prefix, len(entries.Deltas)))
+	if len(entries.Deltas) == 0 {
+		return 0
+	}
+
+	value, err := proto.Marshal(entries)
+	if err != nil {
+		returnStateError(fmt.Errorf("marshalling entries of prefix %q: %w", prefix, err))
+	}
+
+	err = m.CurrentInstance.WriteOutputToHeap(outputPtr, value, prefix)
+	if err != nil {
+		returnStateError(fmt.Errorf("writing value to output ptr %d: %w", outputPtr, err))
+	}
+	return 1
+}
+
+// readBatch decodes the operations of a batched call, sent as `StoreDeltas` with one
+// delta per operation, to be applied in order.
+func (m *Module) readBatch(name string, opsPtr, opsLength int32) []*pbsubstreams.StoreDelta {
+	ops := &pbsubstreams.StoreDeltas{}
+	if err := proto.Unmarshal(m.Heap.ReadBytes(opsPtr, opsLength), ops); err != nil {
+		returnStateError(fmt.Errorf("'%s' failed: decoding operations: %w", name, err))
+	}
+	return ops.Deltas
+}
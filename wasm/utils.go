@@ -14,3 +14,13 @@ type PanicError struct {
 func (e *PanicError) Error() string {
 	return fmt.Sprintf("panic in the wasm: %q at %s:%d:%d", e.message, e.filename, e.lineNumber, e.columnNumber)
 }
+
+// ModuleError is the error returned by a handler, the module ran to completion without
+// output, as opposed to a PanicError where the module trapped.
+type ModuleError struct {
+	message string
+}
+
+func (e *ModuleError) Error() string {
+	return fmt.Sprintf("module returned an error: %s", e.message)
+}